@@ -0,0 +1,9 @@
+-- Cache of the volumes fetched from the external books API
+CREATE TABLE volumes (
+    book_id TEXT PRIMARY KEY,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    data JSONB NOT NULL
+);
+
+SELECT diesel_manage_updated_at('volumes');
@@ -0,0 +1,11 @@
+ALTER TABLE books ADD COLUMN library_name TEXT;
+
+UPDATE books
+SET library_name = libraries.name
+FROM libraries
+WHERE libraries.id = books.library_id;
+
+ALTER TABLE books DROP COLUMN library_id;
+ALTER TABLE books RENAME COLUMN library_name TO library_id;
+
+DROP TABLE libraries;
@@ -0,0 +1,38 @@
+CREATE TABLE libraries (
+    id UUID PRIMARY KEY,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    owner_id TEXT NOT NULL,
+    name TEXT NOT NULL,
+    description TEXT,
+    visibility TEXT NOT NULL DEFAULT 'private',
+    sort_order INTEGER NOT NULL DEFAULT 0
+);
+
+CREATE INDEX libraries_owner_id_idx ON libraries (owner_id);
+
+SELECT diesel_manage_updated_at('libraries');
+
+-- Create a library for each free-text library_id used by the books
+INSERT INTO libraries (id, owner_id, name, sort_order)
+SELECT
+    gen_random_uuid(),
+    user_id,
+    library_id,
+    ROW_NUMBER() OVER (PARTITION BY user_id ORDER BY library_id) - 1
+FROM (
+    SELECT DISTINCT user_id, library_id
+    FROM books
+    WHERE library_id IS NOT NULL AND library_id <> ''
+) AS existing_libraries;
+
+-- Point the books to the new libraries
+ALTER TABLE books ADD COLUMN library_uuid UUID REFERENCES libraries (id) ON DELETE SET NULL;
+
+UPDATE books
+SET library_uuid = libraries.id
+FROM libraries
+WHERE libraries.owner_id = books.user_id AND libraries.name = books.library_id;
+
+ALTER TABLE books DROP COLUMN library_id;
+ALTER TABLE books RENAME COLUMN library_uuid TO library_id;
@@ -0,0 +1,2 @@
+DROP TABLE library_invites;
+DROP TABLE library_members;
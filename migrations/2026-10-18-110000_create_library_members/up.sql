@@ -0,0 +1,25 @@
+CREATE TABLE library_members (
+    library_id UUID NOT NULL REFERENCES libraries (id) ON DELETE CASCADE,
+    user_id TEXT NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    role TEXT NOT NULL,
+    PRIMARY KEY (library_id, user_id)
+);
+
+CREATE INDEX library_members_user_id_idx ON library_members (user_id);
+
+SELECT diesel_manage_updated_at('library_members');
+
+-- Owners are members of their libraries
+INSERT INTO library_members (library_id, user_id, role)
+SELECT id, owner_id, 'owner' FROM libraries;
+
+CREATE TABLE library_invites (
+    code TEXT PRIMARY KEY,
+    library_id UUID NOT NULL REFERENCES libraries (id) ON DELETE CASCADE,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    created_by TEXT NOT NULL,
+    role TEXT NOT NULL,
+    expires_at TIMESTAMP NOT NULL
+);
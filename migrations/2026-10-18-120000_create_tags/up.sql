@@ -0,0 +1,16 @@
+CREATE TABLE tags (
+    id UUID PRIMARY KEY,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    user_id TEXT NOT NULL,
+    parent_id UUID REFERENCES tags (id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    color TEXT,
+    icon TEXT
+);
+
+-- Sibling tags have different names, root tags included
+CREATE UNIQUE INDEX tags_user_id_parent_id_name_idx
+    ON tags (user_id, COALESCE(parent_id, '00000000-0000-0000-0000-000000000000'), name);
+
+SELECT diesel_manage_updated_at('tags');
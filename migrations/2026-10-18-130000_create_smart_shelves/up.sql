@@ -0,0 +1,12 @@
+CREATE TABLE smart_shelves (
+    id UUID PRIMARY KEY,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    user_id TEXT NOT NULL,
+    name TEXT NOT NULL,
+    filters JSONB NOT NULL DEFAULT '{}'
+);
+
+CREATE INDEX smart_shelves_user_id_idx ON smart_shelves (user_id);
+
+SELECT diesel_manage_updated_at('smart_shelves');
@@ -0,0 +1,17 @@
+CREATE TABLE highlights (
+    id UUID PRIMARY KEY,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    book_id UUID NOT NULL REFERENCES books (id) ON DELETE CASCADE,
+    user_id TEXT NOT NULL,
+    text TEXT NOT NULL,
+    page INTEGER,
+    location TEXT,
+    chapter TEXT,
+    comment TEXT
+);
+
+CREATE INDEX highlights_user_id_idx ON highlights (user_id);
+CREATE INDEX highlights_book_id_idx ON highlights (book_id);
+
+SELECT diesel_manage_updated_at('highlights');
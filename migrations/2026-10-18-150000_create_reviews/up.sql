@@ -0,0 +1,18 @@
+CREATE TABLE reviews (
+    id UUID PRIMARY KEY,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    -- One review for each book of the shelf
+    book_id UUID NOT NULL UNIQUE REFERENCES books (id) ON DELETE CASCADE,
+    user_id TEXT NOT NULL,
+    title TEXT,
+    body TEXT NOT NULL,
+    plot_rating DOUBLE PRECISION,
+    characters_rating DOUBLE PRECISION,
+    writing_rating DOUBLE PRECISION,
+    visibility TEXT NOT NULL DEFAULT 'private'
+);
+
+CREATE INDEX reviews_user_id_idx ON reviews (user_id);
+
+SELECT diesel_manage_updated_at('reviews');
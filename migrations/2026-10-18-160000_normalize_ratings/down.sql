@@ -0,0 +1,18 @@
+ALTER TABLE books DROP CONSTRAINT books_rating_check;
+
+-- Books first rated after the normalization keep their canonical rating
+UPDATE books SET rating = books_rating_backup.rating
+FROM books_rating_backup
+WHERE books_rating_backup.book_id = books.id;
+
+UPDATE reviews SET
+    plot_rating = reviews_rating_backup.plot_rating,
+    characters_rating = reviews_rating_backup.characters_rating,
+    writing_rating = reviews_rating_backup.writing_rating
+FROM reviews_rating_backup
+WHERE reviews_rating_backup.review_id = reviews.id;
+
+DROP TABLE reviews_rating_backup;
+DROP TABLE books_rating_backup;
+
+DROP TABLE user_preferences;
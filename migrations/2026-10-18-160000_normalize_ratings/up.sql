@@ -0,0 +1,41 @@
+CREATE TABLE user_preferences (
+    user_id TEXT PRIMARY KEY,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    rating_scale TEXT NOT NULL DEFAULT 'stars'
+);
+
+SELECT diesel_manage_updated_at('user_preferences');
+
+-- The ratings before the normalization, restored by down.sql
+CREATE TABLE books_rating_backup (
+    book_id UUID PRIMARY KEY REFERENCES books (id) ON DELETE CASCADE,
+    rating DOUBLE PRECISION NOT NULL
+);
+INSERT INTO books_rating_backup (book_id, rating)
+SELECT id, rating FROM books WHERE rating IS NOT NULL;
+
+CREATE TABLE reviews_rating_backup (
+    review_id UUID PRIMARY KEY REFERENCES reviews (id) ON DELETE CASCADE,
+    plot_rating DOUBLE PRECISION,
+    characters_rating DOUBLE PRECISION,
+    writing_rating DOUBLE PRECISION
+);
+INSERT INTO reviews_rating_backup (review_id, plot_rating, characters_rating, writing_rating)
+SELECT id, plot_rating, characters_rating, writing_rating FROM reviews
+WHERE plot_rating IS NOT NULL OR characters_rating IS NOT NULL OR writing_rating IS NOT NULL;
+
+-- Ratings are stored from 0 to 5 stars, in quarters of star.
+-- Ratings above 5 were saved on a 10 point scale, 0 is a valid rating
+UPDATE books SET rating = CASE
+    WHEN rating > 5 THEN ROUND(LEAST(rating, 10) / 2 * 4) / 4
+    ELSE ROUND(GREATEST(rating, 0) * 4) / 4
+END
+WHERE rating IS NOT NULL;
+
+UPDATE reviews SET
+    plot_rating = ROUND(LEAST(GREATEST(plot_rating, 0), 5) * 4) / 4,
+    characters_rating = ROUND(LEAST(GREATEST(characters_rating, 0), 5) * 4) / 4,
+    writing_rating = ROUND(LEAST(GREATEST(writing_rating, 0), 5) * 4) / 4;
+
+ALTER TABLE books ADD CONSTRAINT books_rating_check CHECK (rating >= 0 AND rating <= 5);
@@ -0,0 +1,4 @@
+DROP TABLE price_history;
+
+ALTER TABLE books DROP COLUMN price_alert_at;
+ALTER TABLE books DROP COLUMN target_price;
@@ -0,0 +1,17 @@
+ALTER TABLE books ADD COLUMN target_price DOUBLE PRECISION;
+ALTER TABLE books ADD COLUMN price_alert_at TIMESTAMP;
+
+-- Prices are checked for each volume, shared by the users with the volume in their wishlist
+CREATE TABLE price_history (
+    id UUID PRIMARY KEY,
+    book_id TEXT NOT NULL,
+    checked_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    country TEXT,
+    saleability TEXT,
+    list_price DOUBLE PRECISION,
+    retail_price DOUBLE PRECISION,
+    currency_code TEXT,
+    buy_link TEXT
+);
+
+CREATE INDEX price_history_book_id_checked_at_idx ON price_history (book_id, checked_at);
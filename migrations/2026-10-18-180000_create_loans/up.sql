@@ -0,0 +1,21 @@
+CREATE TABLE loans (
+    id UUID PRIMARY KEY,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    book_id UUID NOT NULL REFERENCES books (id) ON DELETE CASCADE,
+    user_id TEXT NOT NULL,
+    borrower_name TEXT NOT NULL,
+    borrower_contact TEXT,
+    lent_at DATE NOT NULL,
+    due_at DATE,
+    returned_at DATE,
+    CHECK (due_at IS NULL OR due_at >= lent_at),
+    CHECK (returned_at IS NULL OR returned_at >= lent_at)
+);
+
+CREATE INDEX loans_user_id_idx ON loans (user_id);
+CREATE INDEX loans_book_id_idx ON loans (book_id);
+-- a book can only be lent to one borrower at a time
+CREATE UNIQUE INDEX loans_book_id_active_idx ON loans (book_id) WHERE returned_at IS NULL;
+
+SELECT diesel_manage_updated_at('loans');
@@ -0,0 +1,2 @@
+DROP TABLE series_books;
+DROP TABLE series;
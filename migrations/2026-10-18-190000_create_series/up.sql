@@ -0,0 +1,28 @@
+CREATE TABLE series (
+    id UUID PRIMARY KEY,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    user_id TEXT NOT NULL,
+    name TEXT NOT NULL,
+    description TEXT,
+    UNIQUE (user_id, name)
+);
+
+CREATE INDEX series_user_id_idx ON series (user_id);
+
+SELECT diesel_manage_updated_at('series');
+
+-- positions are fractional, 2.5 is a novella between the second and the third book
+CREATE TABLE series_books (
+    id UUID PRIMARY KEY,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    series_id UUID NOT NULL REFERENCES series (id) ON DELETE CASCADE,
+    book_id UUID NOT NULL REFERENCES books (id) ON DELETE CASCADE,
+    position DOUBLE PRECISION NOT NULL CHECK (position >= 0),
+    UNIQUE (series_id, book_id)
+);
+
+CREATE INDEX series_books_book_id_idx ON series_books (book_id);
+
+SELECT diesel_manage_updated_at('series_books');
@@ -0,0 +1,2 @@
+DROP TABLE volume_authors;
+DROP TABLE authors;
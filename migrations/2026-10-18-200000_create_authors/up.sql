@@ -0,0 +1,48 @@
+-- authors are shared by all the users, like the cached volumes.
+-- The key groups the variants of a name: "J.R.R. Tolkien" and "J. R. R. Tolkien"
+CREATE TABLE authors (
+    id UUID PRIMARY KEY,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    name TEXT NOT NULL,
+    key TEXT NOT NULL UNIQUE,
+    variants TEXT[] NOT NULL DEFAULT '{}'
+);
+
+SELECT diesel_manage_updated_at('authors');
+
+CREATE TABLE volume_authors (
+    book_id TEXT NOT NULL REFERENCES volumes (book_id) ON DELETE CASCADE,
+    author_id UUID NOT NULL REFERENCES authors (id) ON DELETE CASCADE,
+    position INTEGER NOT NULL,
+    PRIMARY KEY (book_id, author_id)
+);
+
+CREATE INDEX volume_authors_author_id_idx ON volume_authors (author_id);
+
+-- link the volumes already cached
+CREATE TEMPORARY TABLE volume_author_names AS
+SELECT v.book_id, a.name, a.position::INTEGER AS position,
+    btrim(lower(regexp_replace(a.name, '[^[:alnum:]]+', ' ', 'g'))) AS key
+FROM volumes v,
+    jsonb_array_elements_text(
+        CASE WHEN jsonb_typeof(v.data -> 'volumeInfo' -> 'authors') = 'array'
+            THEN v.data -> 'volumeInfo' -> 'authors'
+            ELSE '[]'::jsonb
+        END
+    ) WITH ORDINALITY AS a(name, position);
+
+DELETE FROM volume_author_names WHERE key = '';
+
+INSERT INTO authors (id, name, key, variants)
+SELECT gen_random_uuid(), MIN(btrim(name)), key, ARRAY_AGG(DISTINCT btrim(name))
+FROM volume_author_names
+GROUP BY key;
+
+INSERT INTO volume_authors (book_id, author_id, position)
+SELECT n.book_id, au.id, MIN(n.position)
+FROM volume_author_names n
+JOIN authors au ON au.key = n.key
+GROUP BY n.book_id, au.id;
+
+DROP TABLE volume_author_names;
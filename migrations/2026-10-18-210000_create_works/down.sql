@@ -0,0 +1,2 @@
+ALTER TABLE volumes DROP COLUMN work_id;
+DROP TABLE works;
@@ -0,0 +1,47 @@
+-- a work groups the editions of a book: hardcover, paperback, ebook...
+-- The key is the title without its parenthesized parts and the first author, normalized
+CREATE TABLE works (
+    id UUID PRIMARY KEY,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    title TEXT NOT NULL,
+    key TEXT NOT NULL UNIQUE
+);
+
+SELECT diesel_manage_updated_at('works');
+
+ALTER TABLE volumes ADD COLUMN work_id UUID REFERENCES works (id) ON DELETE SET NULL;
+
+CREATE INDEX volumes_work_id_idx ON volumes (work_id);
+
+-- group the volumes already cached
+CREATE TEMPORARY TABLE volume_work_keys AS
+SELECT book_id, title,
+    btrim(lower(regexp_replace(
+        regexp_replace(title, '\([^)]*\)|\[[^]]*\]', ' ', 'g'),
+        '[^[:alnum:]]+', ' ', 'g'
+    ))) AS title_key,
+    btrim(lower(regexp_replace(author, '[^[:alnum:]]+', ' ', 'g'))) AS author_key
+FROM (
+    SELECT book_id,
+        COALESCE(data -> 'volumeInfo' ->> 'title', '') AS title,
+        CASE WHEN jsonb_typeof(data -> 'volumeInfo' -> 'authors') = 'array'
+            THEN COALESCE(data -> 'volumeInfo' -> 'authors' ->> 0, '')
+            ELSE ''
+        END AS author
+    FROM volumes
+) v;
+
+DELETE FROM volume_work_keys WHERE title_key = '';
+
+INSERT INTO works (id, title, key)
+SELECT gen_random_uuid(), MIN(btrim(title)), title_key || '/' || author_key
+FROM volume_work_keys
+GROUP BY title_key || '/' || author_key;
+
+UPDATE volumes v SET work_id = w.id
+FROM volume_work_keys k
+JOIN works w ON w.key = k.title_key || '/' || k.author_key
+WHERE v.book_id = k.book_id;
+
+DROP TABLE volume_work_keys;
@@ -0,0 +1,18 @@
+CREATE TABLE custom_fields (
+    id UUID PRIMARY KEY,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    user_id TEXT NOT NULL,
+    key TEXT NOT NULL,
+    name TEXT NOT NULL,
+    field_type TEXT NOT NULL,
+    options TEXT[] NOT NULL DEFAULT '{}',
+    UNIQUE (user_id, key)
+);
+
+SELECT diesel_manage_updated_at('custom_fields');
+
+-- values of the custom fields of the user that saved the book, by key
+ALTER TABLE books ADD COLUMN custom_fields JSONB NOT NULL DEFAULT '{}';
+
+CREATE INDEX books_custom_fields_idx ON books USING GIN (custom_fields jsonb_path_ops);
@@ -0,0 +1,2 @@
+DELETE FROM volumes WHERE user_id IS NOT NULL;
+ALTER TABLE volumes DROP COLUMN user_id;
@@ -0,0 +1,6 @@
+-- manual volumes are created by a user for the books missing from the external
+-- API, like zines or manuscripts. Their ids start with "manual:" and they are
+-- never fetched again, the volumes of the external API have no user
+ALTER TABLE volumes ADD COLUMN user_id TEXT;
+
+CREATE INDEX volumes_user_id_idx ON volumes (user_id) WHERE user_id IS NOT NULL;
@@ -0,0 +1,2 @@
+-- metadata set by the user over the one of the external API, by field of the book
+ALTER TABLE books ADD COLUMN metadata_overrides JSONB NOT NULL DEFAULT '{}';
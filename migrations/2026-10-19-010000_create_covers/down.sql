@@ -0,0 +1,2 @@
+ALTER TABLE books DROP COLUMN custom_cover_id;
+DROP TABLE covers;
@@ -0,0 +1,15 @@
+-- covers of the volumes downloaded from the external API. The images are stored
+-- on disk, in every size, and downloaded again when the volume links another image
+CREATE TABLE covers (
+    book_id TEXT PRIMARY KEY REFERENCES volumes (book_id) ON DELETE CASCADE,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    source_url TEXT NOT NULL,
+    width INTEGER NOT NULL,
+    height INTEGER NOT NULL
+);
+
+SELECT diesel_manage_updated_at('covers');
+
+-- cover uploaded by the user, replacing the one of the volume
+ALTER TABLE books ADD COLUMN custom_cover_id UUID;
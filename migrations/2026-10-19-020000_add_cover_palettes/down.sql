@@ -0,0 +1,9 @@
+ALTER TABLE books
+    DROP COLUMN custom_cover_blurhash,
+    DROP COLUMN custom_cover_dominant_color,
+    DROP COLUMN custom_cover_accent_color;
+
+ALTER TABLE covers
+    DROP COLUMN blurhash,
+    DROP COLUMN dominant_color,
+    DROP COLUMN accent_color;
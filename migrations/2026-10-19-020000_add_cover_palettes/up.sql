@@ -0,0 +1,11 @@
+-- placeholders shown while the covers load, computed when the images are cached.
+-- The colors are hex codes, like #1a2b3c
+ALTER TABLE covers
+    ADD COLUMN blurhash TEXT,
+    ADD COLUMN dominant_color TEXT,
+    ADD COLUMN accent_color TEXT;
+
+ALTER TABLE books
+    ADD COLUMN custom_cover_blurhash TEXT,
+    ADD COLUMN custom_cover_dominant_color TEXT,
+    ADD COLUMN custom_cover_accent_color TEXT;
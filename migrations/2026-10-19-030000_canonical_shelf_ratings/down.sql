@@ -0,0 +1,13 @@
+WITH scales AS (
+    SELECT user_id, CASE rating_scale WHEN 'ten' THEN 10 WHEN 'hundred' THEN 100 END AS max_rating
+    FROM user_preferences
+)
+UPDATE smart_shelves SET filters = filters
+    || CASE WHEN jsonb_typeof(filters->'minRating') = 'number' THEN jsonb_build_object(
+        'minRating', (filters->>'minRating')::NUMERIC * scales.max_rating / 5
+    ) ELSE '{}' END
+    || CASE WHEN jsonb_typeof(filters->'maxRating') = 'number' THEN jsonb_build_object(
+        'maxRating', (filters->>'maxRating')::NUMERIC * scales.max_rating / 5
+    ) ELSE '{}' END
+FROM scales
+WHERE scales.user_id = smart_shelves.user_id AND scales.max_rating IS NOT NULL;
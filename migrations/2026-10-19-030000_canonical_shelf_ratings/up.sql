@@ -0,0 +1,18 @@
+-- The rating filters of the smart shelves are stored in the canonical scale,
+-- from 0 to 5 stars in quarters of star, like the ratings of the books.
+-- The existing ones were saved in the scale preferred by the user
+WITH scales AS (
+    SELECT user_id, CASE rating_scale WHEN 'ten' THEN 10 WHEN 'hundred' THEN 100 END AS max_rating
+    FROM user_preferences
+)
+UPDATE smart_shelves SET filters = filters
+    || CASE WHEN jsonb_typeof(filters->'minRating') = 'number' THEN jsonb_build_object(
+        'minRating',
+        ROUND(LEAST(GREATEST((filters->>'minRating')::NUMERIC * 5 / scales.max_rating, 0), 5) * 4) / 4
+    ) ELSE '{}' END
+    || CASE WHEN jsonb_typeof(filters->'maxRating') = 'number' THEN jsonb_build_object(
+        'maxRating',
+        ROUND(LEAST(GREATEST((filters->>'maxRating')::NUMERIC * 5 / scales.max_rating, 0), 5) * 4) / 4
+    ) ELSE '{}' END
+FROM scales
+WHERE scales.user_id = smart_shelves.user_id AND scales.max_rating IS NOT NULL;
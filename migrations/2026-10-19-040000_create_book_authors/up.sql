@@ -0,0 +1,38 @@
+-- authors of the books of the shelves whose authors are overridden by the user,
+-- used instead of the authors of their volume
+CREATE TABLE book_authors (
+    book_id UUID NOT NULL REFERENCES books (id) ON DELETE CASCADE,
+    author_id UUID NOT NULL REFERENCES authors (id) ON DELETE CASCADE,
+    position INTEGER NOT NULL,
+    PRIMARY KEY (book_id, author_id)
+);
+
+CREATE INDEX book_authors_author_id_idx ON book_authors (author_id);
+
+-- link the books already overridden, like the volumes when the authors were created
+CREATE TEMPORARY TABLE book_author_names AS
+SELECT b.id AS book_id, btrim(a.name) AS name, a.position::INTEGER AS position,
+    btrim(lower(regexp_replace(a.name, '[^[:alnum:]]+', ' ', 'g'))) AS key
+FROM books b,
+    jsonb_array_elements_text(
+        CASE WHEN jsonb_typeof(b.metadata_overrides -> 'authors') = 'array'
+            THEN b.metadata_overrides -> 'authors'
+            ELSE '[]'::jsonb
+        END
+    ) WITH ORDINALITY AS a(name, position);
+
+DELETE FROM book_author_names WHERE key = '';
+
+INSERT INTO authors (id, name, key, variants)
+SELECT gen_random_uuid(), MIN(name), key, ARRAY_AGG(DISTINCT name)
+FROM book_author_names
+GROUP BY key
+ON CONFLICT (key) DO NOTHING;
+
+INSERT INTO book_authors (book_id, author_id, position)
+SELECT n.book_id, au.id, MIN(n.position)
+FROM book_author_names n
+JOIN authors au ON au.key = n.key
+GROUP BY n.book_id, au.id;
+
+DROP TABLE book_author_names;
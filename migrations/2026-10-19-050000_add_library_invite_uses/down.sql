@@ -0,0 +1,2 @@
+ALTER TABLE library_invites DROP COLUMN uses;
+ALTER TABLE library_invites DROP COLUMN max_uses;
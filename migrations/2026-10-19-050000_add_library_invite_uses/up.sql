@@ -0,0 +1,3 @@
+-- invites can be accepted a limited number of times, once by default
+ALTER TABLE library_invites ADD COLUMN max_uses INTEGER NOT NULL DEFAULT 1;
+ALTER TABLE library_invites ADD COLUMN uses INTEGER NOT NULL DEFAULT 0;
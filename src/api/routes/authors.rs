@@ -0,0 +1,81 @@
+use axum::{
+    extract::{Path, Query, State},
+    routing::get,
+    Json, Router,
+};
+use tracing::info;
+
+use crate::{
+    api::response::Response,
+    error::Result,
+    model::{
+        authors::{
+            get_author, get_author_books, get_author_stats, get_top_authors, get_user_authors,
+            AuthorStats, TopAuthorsParams,
+        },
+        books::UserBooks,
+        ModelManager,
+    },
+};
+
+pub fn authors_routes(model_manager: ModelManager) -> Router {
+    Router::new()
+        .route("/authors/:user_id", get(get_user_authors_list))
+        .route("/authors/:user_id/top", get(get_user_top_authors))
+        .route("/authors/:user_id/:id", get(get_user_author_by_id))
+        .route("/authors/:user_id/:id/books", get(get_user_author_books))
+        .with_state(model_manager)
+}
+
+/// Authors of the books of the user with the reading stats of each one
+async fn get_user_authors_list(
+    State(model_manager): State<ModelManager>,
+    Path(user_id): Path<String>,
+) -> Result<Json<Response<Vec<AuthorStats>>>> {
+    info!("{:<6} - get_user_authors_list", "GET");
+
+    let authors = get_user_authors(&model_manager, &user_id).await?;
+
+    let res = Response::new_success(200, None, Some(authors));
+    Ok(Json(res))
+}
+
+/// Authors the user read most, `?limit=` of them
+async fn get_user_top_authors(
+    State(model_manager): State<ModelManager>,
+    Path(user_id): Path<String>,
+    Query(params): Query<TopAuthorsParams>,
+) -> Result<Json<Response<Vec<AuthorStats>>>> {
+    info!("{:<6} - get_user_top_authors", "GET");
+
+    let authors = get_top_authors(&model_manager, &user_id, &params).await?;
+
+    let res = Response::new_success(200, None, Some(authors));
+    Ok(Json(res))
+}
+
+async fn get_user_author_by_id(
+    State(model_manager): State<ModelManager>,
+    Path((user_id, id)): Path<(String, String)>,
+) -> Result<Json<Response<AuthorStats>>> {
+    info!("{:<6} - get_user_author_by_id", "GET");
+
+    let author = get_author(&model_manager, &id).await?;
+    let stats = get_author_stats(&model_manager, &user_id, author).await?;
+
+    let res = Response::new_success(200, None, Some(stats));
+    Ok(Json(res))
+}
+
+async fn get_user_author_books(
+    State(model_manager): State<ModelManager>,
+    Path((user_id, id)): Path<(String, String)>,
+) -> Result<Json<Response<UserBooks>>> {
+    info!("{:<6} - get_user_author_books", "GET");
+
+    let author = get_author(&model_manager, &id).await?;
+    let books = get_author_books(&model_manager, &user_id, author.id).await?;
+
+    let res = Response::new_success(200, None, Some(books));
+    Ok(Json(res))
+}
@@ -1,12 +1,21 @@
+use std::collections::HashMap;
+
 use axum::{
-    extract::{Path, State},
+    body::Bytes,
+    extract::{Path, Query, State},
+    http::{header, HeaderMap},
+    response::{IntoResponse, Response as AxumResponse},
     routing::{delete, get, post},
     Json, Router,
 };
 
+use chrono::Utc;
 use sea_orm::{
-    entity::prelude::Uuid, ActiveModelTrait, ColumnTrait, EntityTrait, ModelTrait, QueryFilter,
+    entity::prelude::Uuid, ActiveModelTrait, ActiveValue, ColumnTrait, EntityTrait, ModelTrait,
+    QueryFilter,
 };
+use serde::Deserialize;
+use serde_json::Value;
 use tracing::info;
 
 use crate::{
@@ -14,8 +23,23 @@ use crate::{
     entities::books::{self},
     error::Result,
     model::{
-        books::{BookFull, BookId, BookToSave, BookToUpdate, UserBooks},
-        books_api::BooksApiResponse,
+        authors::link_book_authors,
+        books::{json_object, BookFull, BookId, BookToSave, BookToUpdate, UserBooks},
+        citation::CitationFormat,
+        covers::{
+            cover_url, delete_custom_cover, get_cover_palettes, remove_custom_cover_files,
+            save_custom_cover, CoverId,
+        },
+        custom_fields::{custom_field_filter, get_user_custom_fields, validate_custom_fields},
+        libraries::{
+            can_access_book, parse_library_id, validate_book_library, ROLE_EDITOR, ROLE_VIEWER,
+        },
+        metadata_overrides::{reset_metadata_overrides, ResetOverridesParams},
+        preferences::get_rating_scale,
+        reviews::get_books_reviews,
+        tags::tag_filter,
+        volumes::{get_user_manual_volume, get_volume, is_manual_volume},
+        works::{get_editions, switch_edition, Edition, EditionToSwitch},
         ModelManager,
     },
     Error,
@@ -25,23 +49,53 @@ pub fn books_routes(model_manager: ModelManager) -> Router {
     Router::new()
         .route("/books", post(save_book))
         .route("/books/:user_id", get(get_user_books))
+        .route("/books/:user_id/:id", get(get_user_book))
         .route("/books/:user_id/:id", post(update_book))
         .route("/books/:user_id/:id", delete(delete_book))
+        .route("/books/:user_id/:id/editions", get(get_book_editions))
+        .route("/books/:user_id/:id/edition", post(update_book_edition))
+        .route(
+            "/books/:user_id/:id/overrides",
+            delete(delete_book_overrides),
+        )
+        .route("/books/:user_id/:id/cover", post(save_book_cover))
+        .route("/books/:user_id/:id/cover", delete(delete_book_cover))
         .with_state(model_manager)
 }
 
 async fn save_book(
     State(model_manager): State<ModelManager>,
-    Json(book_to_save): Json<BookToSave>,
+    Json(mut book_to_save): Json<BookToSave>,
 ) -> Result<Json<Response<BookId>>> {
     info!("{:<6} - save_book", "POST");
 
-    let book = book_to_save.to_active_model();
+    validate_book_library(
+        &model_manager,
+        &book_to_save.user_id,
+        &book_to_save.library_id,
+    )
+    .await?;
+    // the manual volumes are private to the user who created them
+    if is_manual_volume(&book_to_save.book_id) {
+        get_user_manual_volume(&model_manager, &book_to_save.user_id, &book_to_save.book_id)
+            .await?;
+    }
+    // the rating is received in the scale preferred by the user
+    let rating_scale = get_rating_scale(&model_manager, &book_to_save.user_id).await?;
+    book_to_save.rating = rating_scale.canonical_book_rating(book_to_save.rating)?;
+    if let Some(values) = &book_to_save.custom_fields {
+        let fields = get_user_custom_fields(&model_manager, &book_to_save.user_id).await?;
+        book_to_save.custom_fields = Some(validate_custom_fields(&fields, values)?);
+    }
+    let book = book_to_save.to_active_model()?;
 
     // Save the book in the database
     let book = book.insert(model_manager.db()).await;
     match book {
         Ok(b) => {
+            if book_to_save.metadata_overrides.is_some() {
+                link_book_authors(&model_manager, &b).await?;
+            }
             // return only the id of the book created
             let res = Response::new_success(
                 201,
@@ -56,16 +110,47 @@ async fn save_book(
     }
 }
 
+/// Prefix of the filters on the custom fields, like `?field.signed_copy=true`
+const CUSTOM_FIELD_PARAM_PREFIX: &str = "field.";
+
+#[derive(Debug, Deserialize)]
+struct BooksParams {
+    /// Path of a tag, like `fiction/fantasy`, its descendants included
+    tag: Option<String>,
+    #[serde(flatten)]
+    others: HashMap<String, String>,
+}
+
+/// Responds with BibTeX, RIS or JSON-LD when requested in the Accept header
 async fn get_user_books(
     State(model_manager): State<ModelManager>,
     Path(user_id): Path<String>,
-) -> Result<Json<Response<UserBooks>>> {
+    Query(params): Query<BooksParams>,
+    headers: HeaderMap,
+) -> Result<AxumResponse> {
     info!("{:<6} - get_user_books", "GET");
 
-    let db_res = books::Entity::find()
-        .filter(books::Column::UserId.eq(user_id.clone()))
-        .all(model_manager.db())
-        .await;
+    let mut query = books::Entity::find().filter(books::Column::UserId.eq(user_id.clone()));
+    if let Some(tag) = params.tag.filter(|t| !t.is_empty()) {
+        query = query.filter(tag_filter(&tag));
+    }
+    let custom_field_params: Vec<(&str, &String)> = params
+        .others
+        .iter()
+        .filter_map(|(param, value)| {
+            param
+                .strip_prefix(CUSTOM_FIELD_PARAM_PREFIX)
+                .map(|key| (key, value))
+        })
+        .collect();
+    if !custom_field_params.is_empty() {
+        let fields = get_user_custom_fields(&model_manager, &user_id).await?;
+        for (key, value) in custom_field_params {
+            query = query.filter(custom_field_filter(&fields, key, value)?);
+        }
+    }
+
+    let db_res = query.all(model_manager.db()).await;
 
     let books = if let Ok(books) = db_res {
         books
@@ -73,56 +158,78 @@ async fn get_user_books(
         return Err(Error::InternalServerError);
     };
 
-    // loop through the books and get the full book info from the external API
-    let mut user_books = UserBooks::from_user_id(user_id.clone());
-    for book in books.iter() {
-        let book_id = book.book_id.clone();
-
-        let external_books_api_url = match std::env::var("EXTERNAL_BOOKS_API_URL") {
-            Ok(external_books_api_url) => external_books_api_url,
-            Err(_) => {
-                return Err(Error::MissingEnvVar(
-                    "missing env var: EXTERNAL_BOOKS_API_URL".to_string(),
-                ));
-            }
-        };
-        let external_books_api_key = match std::env::var("EXTERNAL_BOOKS_API_KEY") {
-            Ok(external_books_api_key) => external_books_api_key,
-            Err(_) => {
-                return Err(Error::MissingEnvVar(
-                    "missing env var: EXTERNAL_BOOKS_API_KEY".to_string(),
-                ));
-            }
-        };
-        let url = external_books_api_url + "/" + &book_id + "?key=" + &external_books_api_key;
-
-        let res = reqwest::get(&url).await;
-        match res {
-            Ok(res) => {
-                let book_api = res.json::<BooksApiResponse>().await;
-                match book_api {
-                    Ok(book_api) => {
-                        user_books.add_book(BookFull::from_db_and_api(book.clone(), book_api)?);
-                    }
-                    Err(e) => {
-                        return Err(Error::ParseError(e.to_string()));
-                    }
-                }
-            }
-            Err(e) => {
-                return Err(Error::ExternalApiError(e.to_string()));
-            }
-        }
+    // get the full book info from the volumes cache
+    let user_books = UserBooks::from_db(&model_manager, user_id, books).await?;
+
+    if let Some(format) = citation_format(&headers) {
+        let citation = format.render(user_books.get_books());
+        return Ok(([(header::CONTENT_TYPE, format.content_type())], citation).into_response());
     }
 
     let res = Response::new_success(200, None, Some(user_books));
-    Ok(Json(res))
+    Ok(Json(res).into_response())
+}
+
+/// Responds with BibTeX, RIS or JSON-LD when requested in the Accept header
+async fn get_user_book(
+    State(model_manager): State<ModelManager>,
+    Path((user_id, id)): Path<(String, String)>,
+    headers: HeaderMap,
+) -> Result<AxumResponse> {
+    info!("{:<6} - get_user_book", "GET");
+
+    // check if the id can be parsed into a Uuid
+    let id_to_search = match Uuid::parse_str(&id) {
+        Ok(id) => id,
+        Err(_) => return Err(Error::ParseError("Invalid id".to_string())),
+    };
+
+    let book = match books::Entity::find_by_id(id_to_search)
+        .one(model_manager.db())
+        .await
+    {
+        Ok(Some(book)) => book,
+        Ok(None) => return Err(Error::NotFound),
+        Err(e) => return Err(Error::DbError(e.to_string())),
+    };
+
+    // the owner of the book or a member of its library
+    if !can_access_book(&model_manager, &book, &user_id, ROLE_VIEWER).await? {
+        return Err(Error::Unathorized);
+    }
+
+    let book_api = get_volume(&model_manager, &book.book_id).await?;
+    let review = get_books_reviews(&model_manager, &user_id, vec![book.id])
+        .await?
+        .remove(&book.id);
+    let palette = get_cover_palettes(&model_manager, std::slice::from_ref(&book))
+        .await?
+        .remove(&book.id);
+    let mut book_full = BookFull::from_db_and_api(book, book_api)?;
+    book_full.set_review(review);
+    book_full.set_cover_palette(palette);
+    book_full.convert_rating(get_rating_scale(&model_manager, &user_id).await?);
+
+    if let Some(format) = citation_format(&headers) {
+        let citation = format.render_book(&book_full);
+        return Ok(([(header::CONTENT_TYPE, format.content_type())], citation).into_response());
+    }
+
+    let res = Response::new_success(200, None, Some(book_full));
+    Ok(Json(res).into_response())
+}
+
+fn citation_format(headers: &HeaderMap) -> Option<CitationFormat> {
+    headers
+        .get(header::ACCEPT)
+        .and_then(|accept| accept.to_str().ok())
+        .and_then(CitationFormat::from_accept)
 }
 
 async fn update_book(
     State(model_manager): State<ModelManager>,
     Path((user_id, id)): Path<(String, String)>,
-    Json(book_to_update): Json<BookToUpdate>,
+    Json(mut book_to_update): Json<BookToUpdate>,
 ) -> Result<Json<Response<String>>> {
     info!("{:<6} - update_book", "UPDATE");
 
@@ -144,17 +251,39 @@ async fn update_book(
     match book.clone() {
         None => return Err(Error::NotFound),
         Some(b) => {
-            // check if the user owner of the book is the same one of the call
-            if b.user_id != user_id {
+            // the owner of the book or an editor of its library
+            if !can_access_book(&model_manager, &b, &user_id, ROLE_EDITOR).await? {
                 return Err(Error::Unathorized);
             }
         }
     }
 
-    let b = book_to_update.to_active_model(book);
+    // only the owner of the book can move it to another library or out of its own
+    if let (Some(library_id), Some(b)) = (book_to_update.library_id.as_deref(), &book) {
+        let library_id = Some(library_id)
+            .filter(|l| !l.is_empty())
+            .map(parse_library_id)
+            .transpose()?;
+        if b.user_id != user_id && library_id != b.library_id {
+            return Err(Error::Unathorized);
+        }
+    }
+    validate_book_library(&model_manager, &user_id, &book_to_update.library_id).await?;
+    // the rating is received in the scale preferred by the user
+    let rating_scale = get_rating_scale(&model_manager, &user_id).await?;
+    book_to_update.rating = rating_scale.canonical_book_rating(book_to_update.rating)?;
+    // the custom fields are the ones of the user that saved the book
+    if let (Some(values), Some(b)) = (&book_to_update.custom_fields, &book) {
+        let fields = get_user_custom_fields(&model_manager, &b.user_id).await?;
+        book_to_update.custom_fields = Some(validate_custom_fields(&fields, values)?);
+    }
+    let b = book_to_update.to_active_model(book)?;
     let book = b.update(model_manager.db()).await;
     match book {
-        Ok(_) => {
+        Ok(b) => {
+            if book_to_update.metadata_overrides.is_some() {
+                link_book_authors(&model_manager, &b).await?;
+            }
             let res = Response::<String>::new_success(
                 200,
                 Some("Book updated successfully!".to_string()),
@@ -192,14 +321,18 @@ async fn delete_book(
         Err(e) => return Err(Error::DbError(e.to_string())),
     };
 
-    // check if the user owner of the book is the same one of the call
-    if book.user_id != user_id {
+    // the owner of the book or an editor of its library
+    if !can_access_book(&model_manager, &book, &user_id, ROLE_EDITOR).await? {
         return Err(Error::Unathorized);
     }
 
+    let custom_cover_id = book.custom_cover_id;
     let res = book.delete(model_manager.db()).await;
     match res {
         Ok(_) => {
+            if let Some(cover_id) = custom_cover_id {
+                remove_custom_cover_files(cover_id).await;
+            }
             let res = Response::<String>::new_success(
                 200,
                 Some("Book deleted successfully!".to_string()),
@@ -210,3 +343,156 @@ async fn delete_book(
         Err(e) => Err(Error::DbError(e.to_string())),
     }
 }
+
+/// Known editions of the work of the book
+async fn get_book_editions(
+    State(model_manager): State<ModelManager>,
+    Path((user_id, id)): Path<(String, String)>,
+) -> Result<Json<Response<Vec<Edition>>>> {
+    info!("{:<6} - get_book_editions", "GET");
+
+    let book = find_book(&model_manager, &id).await?;
+
+    // the owner of the book or a member of its library
+    if !can_access_book(&model_manager, &book, &user_id, ROLE_VIEWER).await? {
+        return Err(Error::Unathorized);
+    }
+
+    // caches the volume and its work if needed
+    get_volume(&model_manager, &book.book_id).await?;
+    let editions = get_editions(&model_manager, &book.book_id, &user_id).await?;
+
+    let res = Response::new_success(200, None, Some(editions));
+    Ok(Json(res))
+}
+
+async fn update_book_edition(
+    State(model_manager): State<ModelManager>,
+    Path((user_id, id)): Path<(String, String)>,
+    Json(edition_to_switch): Json<EditionToSwitch>,
+) -> Result<Json<Response<String>>> {
+    info!("{:<6} - update_book_edition", "UPDATE");
+
+    let book = find_book(&model_manager, &id).await?;
+
+    // the owner of the book or an editor of its library
+    if !can_access_book(&model_manager, &book, &user_id, ROLE_EDITOR).await? {
+        return Err(Error::Unathorized);
+    }
+
+    switch_edition(&model_manager, book, &user_id, &edition_to_switch).await?;
+
+    let res = Response::<String>::new_success(
+        200,
+        Some("Book edition updated successfully!".to_string()),
+        None,
+    );
+    Ok(Json(res))
+}
+
+/// Resets the metadata overridden by the user to the external API one,
+/// only the `?fields=` given if set
+async fn delete_book_overrides(
+    State(model_manager): State<ModelManager>,
+    Path((user_id, id)): Path<(String, String)>,
+    Query(params): Query<ResetOverridesParams>,
+) -> Result<Json<Response<String>>> {
+    info!("{:<6} - delete_book_overrides", "DELETE");
+
+    let book = find_book(&model_manager, &id).await?;
+
+    // the owner of the book or an editor of its library
+    if !can_access_book(&model_manager, &book, &user_id, ROLE_EDITOR).await? {
+        return Err(Error::Unathorized);
+    }
+
+    let overrides =
+        reset_metadata_overrides(json_object(book.metadata_overrides.clone()), &params)?;
+    let mut book_to_update: books::ActiveModel = book.into();
+    book_to_update.updated_at = ActiveValue::Set(Some(Utc::now().naive_utc()));
+    book_to_update.metadata_overrides = ActiveValue::Set(Value::Object(overrides));
+
+    let res = book_to_update.update(model_manager.db()).await;
+    match res {
+        Ok(b) => {
+            link_book_authors(&model_manager, &b).await?;
+            let res = Response::<String>::new_success(
+                200,
+                Some("Book metadata reset successfully!".to_string()),
+                None,
+            );
+            Ok(Json(res))
+        }
+        Err(e) => Err(Error::DbError(e.to_string())),
+    }
+}
+
+async fn find_book(model_manager: &ModelManager, id: &str) -> Result<books::Model> {
+    // check if the id can be parsed into a Uuid
+    let id_to_search = match Uuid::parse_str(id) {
+        Ok(id) => id,
+        Err(_) => return Err(Error::ParseError("Invalid id".to_string())),
+    };
+
+    match books::Entity::find_by_id(id_to_search)
+        .one(model_manager.db())
+        .await
+    {
+        Ok(Some(book)) => Ok(book),
+        Ok(None) => Err(Error::NotFound),
+        Err(e) => Err(Error::DbError(e.to_string())),
+    }
+}
+
+/// The body is the image, in any format. The cover is stored in every size
+/// and replaces the one of the volume for this book only
+async fn save_book_cover(
+    State(model_manager): State<ModelManager>,
+    Path((user_id, id)): Path<(String, String)>,
+    body: Bytes,
+) -> Result<Json<Response<CoverId>>> {
+    info!("{:<6} - save_book_cover", "POST");
+
+    let book = find_book(&model_manager, &id).await?;
+
+    // the owner of the book or an editor of its library
+    if !can_access_book(&model_manager, &book, &user_id, ROLE_EDITOR).await? {
+        return Err(Error::Unathorized);
+    }
+
+    let book_id = book.book_id.clone();
+    let cover_id = save_custom_cover(&model_manager, book, body.to_vec()).await?;
+
+    let res = Response::new_success(
+        201,
+        Some("Book cover uploaded successfully!".to_string()),
+        Some(CoverId {
+            id: cover_id.to_string(),
+            url: cover_url(&book_id, Some(cover_id), true),
+        }),
+    );
+    Ok(Json(res))
+}
+
+async fn delete_book_cover(
+    State(model_manager): State<ModelManager>,
+    Path((user_id, id)): Path<(String, String)>,
+) -> Result<Json<Response<String>>> {
+    info!("{:<6} - delete_book_cover", "DELETE");
+
+    let book = find_book(&model_manager, &id).await?;
+
+    // the owner of the book or an editor of its library
+    if !can_access_book(&model_manager, &book, &user_id, ROLE_EDITOR).await? {
+        return Err(Error::Unathorized);
+    }
+
+    delete_custom_cover(&model_manager, book).await?;
+
+    let res = Response::<String>::new_success(
+        200,
+        Some("Book cover deleted successfully!".to_string()),
+        None,
+    );
+    Ok(Json(res))
+}
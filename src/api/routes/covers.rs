@@ -0,0 +1,66 @@
+use axum::{
+    extract::{Path, State},
+    http::header,
+    response::{IntoResponse, Response as AxumResponse},
+    routing::get,
+    Router,
+};
+use tracing::info;
+
+use crate::{
+    error::Result,
+    model::{
+        covers::{get_custom_cover, get_volume_cover, CoverFile},
+        ModelManager,
+    },
+};
+
+/// The covers of the volumes follow the images of the external API
+const VOLUME_COVER_CACHE_CONTROL: &str = "public, max-age=86400";
+/// Every upload gets a new id, the files of a custom cover never change
+const CUSTOM_COVER_CACHE_CONTROL: &str = "public, max-age=31536000, immutable";
+
+/// Serves the covers in every size, like `/covers/:book_id/medium.webp`
+pub fn covers_routes(model_manager: ModelManager) -> Router {
+    Router::new()
+        .route("/covers/:book_id/:file", get(get_book_cover))
+        .route("/covers/custom/:cover_id/:file", get(get_book_custom_cover))
+        .with_state(model_manager)
+}
+
+async fn get_book_cover(
+    State(model_manager): State<ModelManager>,
+    Path((book_id, file)): Path<(String, String)>,
+) -> Result<AxumResponse> {
+    info!("{:<6} - get_book_cover", "GET");
+
+    let file = CoverFile::parse(&file)?;
+    let data = get_volume_cover(&model_manager, &book_id, &file).await?;
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, file.content_type()),
+            (header::CACHE_CONTROL, VOLUME_COVER_CACHE_CONTROL),
+        ],
+        data,
+    )
+        .into_response())
+}
+
+async fn get_book_custom_cover(
+    Path((cover_id, file)): Path<(String, String)>,
+) -> Result<AxumResponse> {
+    info!("{:<6} - get_book_custom_cover", "GET");
+
+    let file = CoverFile::parse(&file)?;
+    let data = get_custom_cover(&cover_id, &file).await?;
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, file.content_type()),
+            (header::CACHE_CONTROL, CUSTOM_COVER_CACHE_CONTROL),
+        ],
+        data,
+    )
+        .into_response())
+}
@@ -0,0 +1,122 @@
+use axum::{
+    extract::{Path, State},
+    routing::{delete, get, post},
+    Json, Router,
+};
+use sea_orm::ActiveModelTrait;
+use tracing::info;
+
+use crate::{
+    api::response::Response,
+    error::Result,
+    model::{
+        custom_fields::{
+            delete_custom_field, get_user_custom_field, get_user_custom_fields, CustomField,
+            CustomFieldId, CustomFieldToSave, CustomFieldToUpdate,
+        },
+        ModelManager,
+    },
+    Error,
+};
+
+pub fn custom_fields_routes(model_manager: ModelManager) -> Router {
+    Router::new()
+        .route("/custom-fields", post(save_custom_field))
+        .route("/custom-fields/:user_id", get(get_custom_fields))
+        .route("/custom-fields/:user_id/:id", post(update_custom_field))
+        .route(
+            "/custom-fields/:user_id/:id",
+            delete(delete_user_custom_field),
+        )
+        .with_state(model_manager)
+}
+
+async fn save_custom_field(
+    State(model_manager): State<ModelManager>,
+    Json(field_to_save): Json<CustomFieldToSave>,
+) -> Result<Json<Response<CustomFieldId>>> {
+    info!("{:<6} - save_custom_field", "POST");
+
+    let field = field_to_save.to_active_model()?;
+
+    // the keys are unique for each user
+    let fields = get_user_custom_fields(&model_manager, &field_to_save.user_id).await?;
+    if fields.iter().any(|f| &f.key == field.key.as_ref()) {
+        return Err(Error::InvalidField(format!(
+            "custom field already exists: {}",
+            field.key.as_ref()
+        )));
+    }
+
+    // Save the custom field in the database
+    let field = field.insert(model_manager.db()).await;
+    match field {
+        Ok(f) => {
+            // return only the id of the custom field created
+            let res = Response::new_success(
+                201,
+                Some("Custom field created successfully!".to_string()),
+                Some(CustomFieldId {
+                    id: f.id.to_string(),
+                }),
+            );
+            Ok(Json(res))
+        }
+        Err(e) => Err(Error::DbError(e.to_string())),
+    }
+}
+
+async fn get_custom_fields(
+    State(model_manager): State<ModelManager>,
+    Path(user_id): Path<String>,
+) -> Result<Json<Response<Vec<CustomField>>>> {
+    info!("{:<6} - get_custom_fields", "GET");
+
+    let fields = get_user_custom_fields(&model_manager, &user_id).await?;
+    let fields = fields.into_iter().map(CustomField::from).collect();
+
+    let res = Response::new_success(200, None, Some(fields));
+    Ok(Json(res))
+}
+
+async fn update_custom_field(
+    State(model_manager): State<ModelManager>,
+    Path((user_id, id)): Path<(String, String)>,
+    Json(field_to_update): Json<CustomFieldToUpdate>,
+) -> Result<Json<Response<String>>> {
+    info!("{:<6} - update_custom_field", "UPDATE");
+
+    let field = get_user_custom_field(&model_manager, &user_id, &id).await?;
+
+    let f = field_to_update.to_active_model(field)?;
+    let field = f.update(model_manager.db()).await;
+    match field {
+        Ok(_) => {
+            let res = Response::<String>::new_success(
+                200,
+                Some("Custom field updated successfully!".to_string()),
+                None,
+            );
+            Ok(Json(res))
+        }
+        Err(e) => Err(Error::DbError(e.to_string())),
+    }
+}
+
+/// The values of the field are removed from the books
+async fn delete_user_custom_field(
+    State(model_manager): State<ModelManager>,
+    Path((user_id, id)): Path<(String, String)>,
+) -> Result<Json<Response<String>>> {
+    info!("{:<6} - delete_user_custom_field", "DELETE");
+
+    let field = get_user_custom_field(&model_manager, &user_id, &id).await?;
+    delete_custom_field(&model_manager, field).await?;
+
+    let res = Response::<String>::new_success(
+        200,
+        Some("Custom field deleted successfully!".to_string()),
+        None,
+    );
+    Ok(Json(res))
+}
@@ -0,0 +1,161 @@
+use std::convert::Infallible;
+
+use axum::{
+    body::Body,
+    extract::{Path, Query, State},
+    http::header,
+    response::Response,
+    routing::get,
+    Router,
+};
+use futures::{stream, StreamExt};
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
+use serde::Deserialize;
+use tracing::{error as tracing_error, info};
+
+use crate::{
+    entities::books,
+    error::Result,
+    model::{
+        books::BookFull,
+        books_api::BooksApiResponse,
+        covers::get_cover_palettes,
+        export::ExportFormat,
+        libraries::{get_user_library, ROLE_VIEWER},
+        marc::{MarcFormat, MarcRecord},
+        preferences::get_rating_scale,
+        reviews::get_books_reviews,
+        volumes::get_volume,
+        ModelManager,
+    },
+    Error,
+};
+
+pub fn export_routes(model_manager: ModelManager) -> Router {
+    Router::new()
+        .route("/export/:user_id", get(export_books))
+        .route(
+            "/export/:user_id/libraries/:library_id/marc",
+            get(export_library_marc),
+        )
+        .with_state(model_manager)
+}
+
+#[derive(Debug, Deserialize)]
+struct ExportParams {
+    format: Option<ExportFormat>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MarcParams {
+    format: Option<MarcFormat>,
+}
+
+async fn export_books(
+    State(model_manager): State<ModelManager>,
+    Path(user_id): Path<String>,
+    Query(params): Query<ExportParams>,
+) -> Result<Response> {
+    info!("{:<6} - export_books", "GET");
+
+    let format = params.format.unwrap_or_default();
+
+    let books = books::Entity::find()
+        .filter(books::Column::UserId.eq(user_id.clone()))
+        .all(model_manager.db())
+        .await
+        .map_err(|e| Error::DbError(e.to_string()))?;
+    let mut reviews = get_books_reviews(
+        &model_manager,
+        &user_id,
+        books.iter().map(|b| b.id).collect(),
+    )
+    .await?;
+    let mut palettes = get_cover_palettes(&model_manager, &books).await?;
+    let rating_scale = format.rating_scale(get_rating_scale(&model_manager, &user_id).await?);
+
+    // stream the rows as soon as the book info is read from the volumes cache.
+    // If the external API fails for a book it is still exported with its database fields
+    let header = stream::iter(format.header().map(Ok::<_, Infallible>));
+    let books: Vec<_> = books
+        .into_iter()
+        .map(|book| {
+            let review = reviews.remove(&book.id);
+            let palette = palettes.remove(&book.id);
+            (book, review, palette)
+        })
+        .collect();
+    let rows = stream::iter(books).then(move |(book, review, palette)| {
+        let model_manager = model_manager.clone();
+        async move {
+            let mut book_full = match get_volume(&model_manager, &book.book_id).await {
+                Ok(book_api) => BookFull::from_db_and_api(book.clone(), book_api)
+                    .unwrap_or_else(|_| BookFull::from_db(book)),
+                Err(e) => {
+                    tracing_error!("{:<6} - export {}: {e:?}", "ERR", book.book_id);
+                    BookFull::from_db(book)
+                }
+            };
+            book_full.set_review(review);
+            book_full.set_cover_palette(palette);
+            book_full.convert_rating(rating_scale);
+            Ok::<_, Infallible>(format.row(&book_full))
+        }
+    });
+
+    Response::builder()
+        .status(200)
+        .header(header::CONTENT_TYPE, format.content_type())
+        .header(
+            header::CONTENT_DISPOSITION,
+            format!("attachment; filename=\"{}\"", format.file_name(&user_id)),
+        )
+        .body(Body::from_stream(header.chain(rows)))
+        .map_err(|_| Error::InternalServerError)
+}
+
+/// Exports the books of a library as MARC21 or MARCXML records, built from the
+/// cached volumes with the metadata overrides of the owners of the books
+async fn export_library_marc(
+    State(model_manager): State<ModelManager>,
+    Path((user_id, library_id)): Path<(String, String)>,
+    Query(params): Query<MarcParams>,
+) -> Result<Response> {
+    info!("{:<6} - export_library_marc", "GET");
+
+    let format = params.format.unwrap_or_default();
+
+    let library = get_user_library(&model_manager, &user_id, &library_id, ROLE_VIEWER).await?;
+
+    let books = books::Entity::find()
+        .filter(books::Column::LibraryId.eq(library.id))
+        .all(model_manager.db())
+        .await
+        .map_err(|e| Error::DbError(e.to_string()))?;
+
+    // if the external API fails for a book it is still exported with its database fields
+    let mut records = vec![];
+    for book in books {
+        let (book_full, book_api) = match get_volume(&model_manager, &book.book_id).await {
+            Ok(book_api) => match BookFull::from_db_and_api(book.clone(), book_api.clone()) {
+                Ok(book_full) => (book_full, book_api),
+                Err(_) => (BookFull::from_db(book), BooksApiResponse::default()),
+            },
+            Err(e) => {
+                tracing_error!("{:<6} - export_library_marc {}: {e:?}", "ERR", book.book_id);
+                (BookFull::from_db(book), BooksApiResponse::default())
+            }
+        };
+        records.push(MarcRecord::from_book(&book_full, &book_api));
+    }
+
+    Response::builder()
+        .status(200)
+        .header(header::CONTENT_TYPE, format.content_type())
+        .header(
+            header::CONTENT_DISPOSITION,
+            format!("attachment; filename=\"{}\"", format.file_name(&library_id)),
+        )
+        .body(Body::from(format.render(&records)))
+        .map_err(|_| Error::InternalServerError)
+}
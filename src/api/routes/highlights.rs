@@ -0,0 +1,177 @@
+use axum::{
+    extract::{Path, Query, State},
+    routing::{delete, get, post},
+    Json, Router,
+};
+use sea_orm::{ActiveModelTrait, EntityTrait, ModelTrait};
+use serde::Deserialize;
+use tracing::info;
+
+use crate::{
+    api::response::Response,
+    entities::books,
+    error::Result,
+    model::{
+        highlights::{
+            get_user_highlight, random_highlight, search_highlights, Highlight, HighlightId,
+            HighlightToSave, HighlightToUpdate, HighlightsParams,
+        },
+        libraries::{can_access_book, ROLE_VIEWER},
+        ModelManager,
+    },
+    Error,
+};
+
+pub fn highlights_routes(model_manager: ModelManager) -> Router {
+    Router::new()
+        .route("/highlights", post(save_highlight))
+        .route("/highlights/:user_id", get(get_user_highlights))
+        .route("/highlights/:user_id/random", get(get_random_highlight))
+        .route("/highlights/:user_id/:id", get(get_user_highlight_by_id))
+        .route("/highlights/:user_id/:id", post(update_highlight))
+        .route("/highlights/:user_id/:id", delete(delete_highlight))
+        .with_state(model_manager)
+}
+
+/// Highlights can be saved on the books the user can see, shared ones included
+async fn save_highlight(
+    State(model_manager): State<ModelManager>,
+    Json(highlight_to_save): Json<HighlightToSave>,
+) -> Result<Json<Response<HighlightId>>> {
+    info!("{:<6} - save_highlight", "POST");
+
+    let highlight = highlight_to_save.to_active_model()?;
+
+    let book = books::Entity::find_by_id(*highlight.book_id.as_ref())
+        .one(model_manager.db())
+        .await
+        .map_err(|e| Error::DbError(e.to_string()))?;
+    let book = match book {
+        Some(book) => book,
+        None => {
+            return Err(Error::InvalidField(format!(
+                "book not found: {}",
+                highlight_to_save.book_id
+            )))
+        }
+    };
+    if !can_access_book(
+        &model_manager,
+        &book,
+        &highlight_to_save.user_id,
+        ROLE_VIEWER,
+    )
+    .await?
+    {
+        return Err(Error::Unathorized);
+    }
+
+    // Save the highlight in the database
+    let highlight = highlight.insert(model_manager.db()).await;
+    match highlight {
+        Ok(h) => {
+            // return only the id of the highlight created
+            let res = Response::new_success(
+                201,
+                Some("Highlight created successfully!".to_string()),
+                Some(HighlightId {
+                    id: h.id.to_string(),
+                }),
+            );
+            Ok(Json(res))
+        }
+        Err(e) => Err(Error::DbError(e.to_string())),
+    }
+}
+
+/// Searches the highlights of the user with `?q=`, optionally of a single book with `?bookId=`
+async fn get_user_highlights(
+    State(model_manager): State<ModelManager>,
+    Path(user_id): Path<String>,
+    Query(params): Query<HighlightsParams>,
+) -> Result<Json<Response<Vec<Highlight>>>> {
+    info!("{:<6} - get_user_highlights", "GET");
+
+    let highlights = search_highlights(&model_manager, &user_id, &params).await?;
+
+    let res = Response::new_success(200, None, Some(highlights));
+    Ok(Json(res))
+}
+
+#[derive(Debug, Deserialize)]
+struct RandomParams {
+    /// Quote of the day, the same highlight for the whole day
+    daily: Option<bool>,
+}
+
+async fn get_random_highlight(
+    State(model_manager): State<ModelManager>,
+    Path(user_id): Path<String>,
+    Query(params): Query<RandomParams>,
+) -> Result<Json<Response<Highlight>>> {
+    info!("{:<6} - get_random_highlight", "GET");
+
+    let highlight =
+        random_highlight(&model_manager, &user_id, params.daily.unwrap_or_default()).await?;
+
+    let res = Response::new_success(200, None, Some(highlight));
+    Ok(Json(res))
+}
+
+async fn get_user_highlight_by_id(
+    State(model_manager): State<ModelManager>,
+    Path((user_id, id)): Path<(String, String)>,
+) -> Result<Json<Response<Highlight>>> {
+    info!("{:<6} - get_user_highlight_by_id", "GET");
+
+    let highlight = get_user_highlight(&model_manager, &user_id, &id).await?;
+
+    let res = Response::new_success(200, None, Some(Highlight::from(highlight)));
+    Ok(Json(res))
+}
+
+async fn update_highlight(
+    State(model_manager): State<ModelManager>,
+    Path((user_id, id)): Path<(String, String)>,
+    Json(highlight_to_update): Json<HighlightToUpdate>,
+) -> Result<Json<Response<String>>> {
+    info!("{:<6} - update_highlight", "UPDATE");
+
+    let highlight = get_user_highlight(&model_manager, &user_id, &id).await?;
+
+    let h = highlight_to_update.to_active_model(highlight)?;
+    let highlight = h.update(model_manager.db()).await;
+    match highlight {
+        Ok(_) => {
+            let res = Response::<String>::new_success(
+                200,
+                Some("Highlight updated successfully!".to_string()),
+                None,
+            );
+            Ok(Json(res))
+        }
+        Err(e) => Err(Error::DbError(e.to_string())),
+    }
+}
+
+async fn delete_highlight(
+    State(model_manager): State<ModelManager>,
+    Path((user_id, id)): Path<(String, String)>,
+) -> Result<Json<Response<String>>> {
+    info!("{:<6} - delete_highlight", "DELETE");
+
+    let highlight = get_user_highlight(&model_manager, &user_id, &id).await?;
+
+    let res = highlight.delete(model_manager.db()).await;
+    match res {
+        Ok(_) => {
+            let res = Response::<String>::new_success(
+                200,
+                Some("Highlight deleted successfully!".to_string()),
+                None,
+            );
+            Ok(Json(res))
+        }
+        Err(e) => Err(Error::DbError(e.to_string())),
+    }
+}
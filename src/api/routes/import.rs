@@ -0,0 +1,106 @@
+use axum::{
+    body::Bytes,
+    extract::{Path, Query, State},
+    routing::post,
+    Json, Router,
+};
+use tracing::info;
+
+use crate::{
+    api::response::Response,
+    error::Result,
+    model::{
+        import::{
+            calibre, goodreads, librarything, run_import, storygraph, ImportEntry, ImportParams,
+            ImportReport,
+        },
+        ModelManager,
+    },
+};
+
+pub fn import_routes(model_manager: ModelManager) -> Router {
+    Router::new()
+        .route("/import/:user_id/goodreads", post(import_goodreads))
+        .route("/import/:user_id/storygraph", post(import_storygraph))
+        .route("/import/:user_id/librarything", post(import_librarything))
+        .route("/import/:user_id/calibre", post(import_calibre))
+        .with_state(model_manager)
+}
+
+async fn import_goodreads(
+    State(model_manager): State<ModelManager>,
+    Path(user_id): Path<String>,
+    Query(params): Query<ImportParams>,
+    body: String,
+) -> Result<Json<Response<ImportReport>>> {
+    info!("{:<6} - import_goodreads", "POST");
+
+    import(&model_manager, user_id, params, &body, goodreads::parse).await
+}
+
+async fn import_storygraph(
+    State(model_manager): State<ModelManager>,
+    Path(user_id): Path<String>,
+    Query(params): Query<ImportParams>,
+    body: String,
+) -> Result<Json<Response<ImportReport>>> {
+    info!("{:<6} - import_storygraph", "POST");
+
+    import(&model_manager, user_id, params, &body, storygraph::parse).await
+}
+
+async fn import_librarything(
+    State(model_manager): State<ModelManager>,
+    Path(user_id): Path<String>,
+    Query(params): Query<ImportParams>,
+    body: String,
+) -> Result<Json<Response<ImportReport>>> {
+    info!("{:<6} - import_librarything", "POST");
+
+    import(&model_manager, user_id, params, &body, librarything::parse).await
+}
+
+/// Accepts either the `metadata.db` file of the Calibre library or a book OPF file
+async fn import_calibre(
+    State(model_manager): State<ModelManager>,
+    Path(user_id): Path<String>,
+    Query(params): Query<ImportParams>,
+    body: Bytes,
+) -> Result<Json<Response<ImportReport>>> {
+    info!("{:<6} - import_calibre", "POST");
+
+    let report = ImportReport::new(user_id, params.dry_run.unwrap_or(false));
+    let entries = calibre::parse(body.to_vec()).await?;
+
+    finish_import(&model_manager, entries, report).await
+}
+
+/// Parses the body with the given format and runs the common import pipeline
+async fn import(
+    model_manager: &ModelManager,
+    user_id: String,
+    params: ImportParams,
+    body: &str,
+    parse: fn(&str, &mut ImportReport) -> Vec<ImportEntry>,
+) -> Result<Json<Response<ImportReport>>> {
+    let mut report = ImportReport::new(user_id, params.dry_run.unwrap_or(false));
+    let entries = parse(body, &mut report);
+
+    finish_import(model_manager, entries, report).await
+}
+
+async fn finish_import(
+    model_manager: &ModelManager,
+    entries: Vec<ImportEntry>,
+    report: ImportReport,
+) -> Result<Json<Response<ImportReport>>> {
+    let report = run_import(model_manager, entries, report).await?;
+
+    let message = if report.dry_run {
+        "Import preview generated!"
+    } else {
+        "Library imported!"
+    };
+    let res = Response::new_success(200, Some(message.to_string()), Some(report));
+    Ok(Json(res))
+}
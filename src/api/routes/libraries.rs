@@ -0,0 +1,361 @@
+use axum::{
+    extract::{Path, State},
+    routing::{delete, get, post},
+    Json, Router,
+};
+use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, ModelTrait, QueryFilter, QueryOrder};
+use tracing::info;
+
+use crate::{
+    api::response::Response,
+    entities::{books, libraries, library_invites, library_members},
+    error::Result,
+    model::{
+        books::UserBooks,
+        libraries::{
+            get_library_role, get_user_library, validate_member_role, Library, LibraryId,
+            LibraryToSave, LibraryToUpdate, ROLE_OWNER, ROLE_VIEWER,
+        },
+        library_members::{
+            accept_invite, get_library_members, save_library_with_owner, InviteToSave,
+            LibraryInvite, LibraryMember, MemberToUpdate,
+        },
+        ModelManager,
+    },
+    Error,
+};
+
+pub fn libraries_routes(model_manager: ModelManager) -> Router {
+    Router::new()
+        .route("/libraries", post(save_library))
+        .route("/libraries/:user_id", get(get_user_libraries))
+        .route("/libraries/:user_id/:id", get(get_user_library_by_id))
+        .route("/libraries/:user_id/:id", post(update_library))
+        .route("/libraries/:user_id/:id", delete(delete_library))
+        .route("/libraries/:user_id/:id/books", get(get_library_books))
+        .route("/libraries/:user_id/:id/members", get(get_members))
+        .route(
+            "/libraries/:user_id/:id/members/:member_id",
+            post(update_member),
+        )
+        .route(
+            "/libraries/:user_id/:id/members/:member_id",
+            delete(delete_member),
+        )
+        .route("/libraries/:user_id/:id/invites", post(save_invite))
+        .route("/libraries/:user_id/:id/invites", get(get_invites))
+        .route(
+            "/libraries/:user_id/:id/invites/:code",
+            delete(delete_invite),
+        )
+        .route("/invites/:user_id/:code", post(accept_library_invite))
+        .with_state(model_manager)
+}
+
+async fn save_library(
+    State(model_manager): State<ModelManager>,
+    Json(library_to_save): Json<LibraryToSave>,
+) -> Result<Json<Response<LibraryId>>> {
+    info!("{:<6} - save_library", "POST");
+
+    let library = library_to_save.to_active_model()?;
+
+    // Save the library in the database, with its owner as member
+    let library = save_library_with_owner(&model_manager, library).await?;
+
+    // return only the id of the library created
+    let res = Response::new_success(
+        201,
+        Some("Library created successfully!".to_string()),
+        Some(LibraryId {
+            id: library.id.to_string(),
+        }),
+    );
+    Ok(Json(res))
+}
+
+async fn get_user_libraries(
+    State(model_manager): State<ModelManager>,
+    Path(user_id): Path<String>,
+) -> Result<Json<Response<Vec<Library>>>> {
+    info!("{:<6} - get_user_libraries", "GET");
+
+    // libraries the user is a member of, owned ones included
+    let libraries = libraries::Entity::find()
+        .find_also_related(library_members::Entity)
+        .filter(library_members::Column::UserId.eq(user_id))
+        .order_by_asc(libraries::Column::SortOrder)
+        .order_by_asc(libraries::Column::Name)
+        .all(model_manager.db())
+        .await
+        .map_err(|e| Error::DbError(e.to_string()))?;
+
+    let libraries = libraries
+        .into_iter()
+        .map(|(library, member)| {
+            Library::from_db(library, member.map(|m| m.role).unwrap_or_default())
+        })
+        .collect();
+
+    let res = Response::new_success(200, None, Some(libraries));
+    Ok(Json(res))
+}
+
+async fn get_user_library_by_id(
+    State(model_manager): State<ModelManager>,
+    Path((user_id, id)): Path<(String, String)>,
+) -> Result<Json<Response<Library>>> {
+    info!("{:<6} - get_user_library_by_id", "GET");
+
+    let library = get_user_library(&model_manager, &user_id, &id, ROLE_VIEWER).await?;
+    // the readers of a public library who are not members see it as viewers
+    let role = get_library_role(&model_manager, &library, &user_id)
+        .await?
+        .unwrap_or(ROLE_VIEWER.to_string());
+
+    let res = Response::new_success(200, None, Some(Library::from_db(library, role)));
+    Ok(Json(res))
+}
+
+async fn update_library(
+    State(model_manager): State<ModelManager>,
+    Path((user_id, id)): Path<(String, String)>,
+    Json(library_to_update): Json<LibraryToUpdate>,
+) -> Result<Json<Response<String>>> {
+    info!("{:<6} - update_library", "UPDATE");
+
+    let library = get_user_library(&model_manager, &user_id, &id, ROLE_OWNER).await?;
+
+    let l = library_to_update.to_active_model(library)?;
+    let library = l.update(model_manager.db()).await;
+    match library {
+        Ok(_) => {
+            let res = Response::<String>::new_success(
+                200,
+                Some("Library updated successfully!".to_string()),
+                None,
+            );
+            Ok(Json(res))
+        }
+        Err(e) => Err(Error::DbError(e.to_string())),
+    }
+}
+
+/// The books of the library are kept, without a library.
+/// Only the owner can delete the library
+async fn delete_library(
+    State(model_manager): State<ModelManager>,
+    Path((user_id, id)): Path<(String, String)>,
+) -> Result<Json<Response<String>>> {
+    info!("{:<6} - delete_library", "DELETE");
+
+    let library = get_user_library(&model_manager, &user_id, &id, ROLE_OWNER).await?;
+
+    let res = library.delete(model_manager.db()).await;
+    match res {
+        Ok(_) => {
+            let res = Response::<String>::new_success(
+                200,
+                Some("Library deleted successfully!".to_string()),
+                None,
+            );
+            Ok(Json(res))
+        }
+        Err(e) => Err(Error::DbError(e.to_string())),
+    }
+}
+
+async fn get_library_books(
+    State(model_manager): State<ModelManager>,
+    Path((user_id, id)): Path<(String, String)>,
+) -> Result<Json<Response<UserBooks>>> {
+    info!("{:<6} - get_library_books", "GET");
+
+    let library = get_user_library(&model_manager, &user_id, &id, ROLE_VIEWER).await?;
+
+    let books = library
+        .find_related(books::Entity)
+        .all(model_manager.db())
+        .await
+        .map_err(|e| Error::DbError(e.to_string()))?;
+
+    // get the full book info from the volumes cache
+    let user_books = UserBooks::from_db(&model_manager, user_id, books).await?;
+
+    let res = Response::new_success(200, None, Some(user_books));
+    Ok(Json(res))
+}
+
+async fn get_members(
+    State(model_manager): State<ModelManager>,
+    Path((user_id, id)): Path<(String, String)>,
+) -> Result<Json<Response<Vec<LibraryMember>>>> {
+    info!("{:<6} - get_members", "GET");
+
+    let library = get_user_library(&model_manager, &user_id, &id, ROLE_VIEWER).await?;
+    let members = get_library_members(&model_manager, library.id).await?;
+
+    let res = Response::new_success(200, None, Some(members));
+    Ok(Json(res))
+}
+
+/// Only the owner can change the role of the members
+async fn update_member(
+    State(model_manager): State<ModelManager>,
+    Path((user_id, id, member_id)): Path<(String, String, String)>,
+    Json(member_to_update): Json<MemberToUpdate>,
+) -> Result<Json<Response<String>>> {
+    info!("{:<6} - update_member", "UPDATE");
+
+    let library = get_user_library(&model_manager, &user_id, &id, ROLE_OWNER).await?;
+    validate_member_role(&member_to_update.role)?;
+    if member_id == library.owner_id {
+        return Err(Error::InvalidField(
+            "the role of the owner cannot be changed".to_string(),
+        ));
+    }
+
+    let member = library_members::Entity::find_by_id((library.id, member_id))
+        .one(model_manager.db())
+        .await
+        .map_err(|e| Error::DbError(e.to_string()))?
+        .ok_or(Error::NotFound)?;
+
+    let mut member: library_members::ActiveModel = member.into();
+    member.role = sea_orm::ActiveValue::Set(member_to_update.role);
+    member.updated_at = sea_orm::ActiveValue::Set(chrono::Utc::now().naive_utc());
+    member
+        .update(model_manager.db())
+        .await
+        .map_err(|e| Error::DbError(e.to_string()))?;
+
+    let res = Response::<String>::new_success(
+        200,
+        Some("Member updated successfully!".to_string()),
+        None,
+    );
+    Ok(Json(res))
+}
+
+/// The owner can remove any member, the other members can only leave the library
+async fn delete_member(
+    State(model_manager): State<ModelManager>,
+    Path((user_id, id, member_id)): Path<(String, String, String)>,
+) -> Result<Json<Response<String>>> {
+    info!("{:<6} - delete_member", "DELETE");
+
+    let required_role = if user_id == member_id {
+        ROLE_VIEWER
+    } else {
+        ROLE_OWNER
+    };
+    let library = get_user_library(&model_manager, &user_id, &id, required_role).await?;
+    if member_id == library.owner_id {
+        return Err(Error::InvalidField(
+            "the owner cannot be removed from the library".to_string(),
+        ));
+    }
+
+    let res = library_members::Entity::delete_by_id((library.id, member_id))
+        .exec(model_manager.db())
+        .await;
+    match res {
+        Ok(r) if r.rows_affected == 0 => Err(Error::NotFound),
+        Ok(_) => {
+            let res = Response::<String>::new_success(
+                200,
+                Some("Member removed successfully!".to_string()),
+                None,
+            );
+            Ok(Json(res))
+        }
+        Err(e) => Err(Error::DbError(e.to_string())),
+    }
+}
+
+async fn save_invite(
+    State(model_manager): State<ModelManager>,
+    Path((user_id, id)): Path<(String, String)>,
+    Json(invite_to_save): Json<InviteToSave>,
+) -> Result<Json<Response<LibraryInvite>>> {
+    info!("{:<6} - save_invite", "POST");
+
+    let library = get_user_library(&model_manager, &user_id, &id, ROLE_OWNER).await?;
+
+    let invite = invite_to_save
+        .to_active_model(library.id, user_id)?
+        .insert(model_manager.db())
+        .await
+        .map_err(|e| Error::DbError(e.to_string()))?;
+
+    let res = Response::new_success(
+        201,
+        Some("Invite created successfully!".to_string()),
+        Some(LibraryInvite::from(invite)),
+    );
+    Ok(Json(res))
+}
+
+async fn get_invites(
+    State(model_manager): State<ModelManager>,
+    Path((user_id, id)): Path<(String, String)>,
+) -> Result<Json<Response<Vec<LibraryInvite>>>> {
+    info!("{:<6} - get_invites", "GET");
+
+    let library = get_user_library(&model_manager, &user_id, &id, ROLE_OWNER).await?;
+
+    let invites = library
+        .find_related(library_invites::Entity)
+        .filter(library_invites::Column::ExpiresAt.gt(chrono::Utc::now().naive_utc()))
+        .all(model_manager.db())
+        .await
+        .map_err(|e| Error::DbError(e.to_string()))?;
+
+    let invites = invites.into_iter().map(LibraryInvite::from).collect();
+
+    let res = Response::new_success(200, None, Some(invites));
+    Ok(Json(res))
+}
+
+async fn delete_invite(
+    State(model_manager): State<ModelManager>,
+    Path((user_id, id, code)): Path<(String, String, String)>,
+) -> Result<Json<Response<String>>> {
+    info!("{:<6} - delete_invite", "DELETE");
+
+    let library = get_user_library(&model_manager, &user_id, &id, ROLE_OWNER).await?;
+
+    let res = library_invites::Entity::delete_many()
+        .filter(library_invites::Column::Code.eq(code))
+        .filter(library_invites::Column::LibraryId.eq(library.id))
+        .exec(model_manager.db())
+        .await;
+    match res {
+        Ok(r) if r.rows_affected == 0 => Err(Error::NotFound),
+        Ok(_) => {
+            let res = Response::<String>::new_success(
+                200,
+                Some("Invite deleted successfully!".to_string()),
+                None,
+            );
+            Ok(Json(res))
+        }
+        Err(e) => Err(Error::DbError(e.to_string())),
+    }
+}
+
+async fn accept_library_invite(
+    State(model_manager): State<ModelManager>,
+    Path((user_id, code)): Path<(String, String)>,
+) -> Result<Json<Response<Library>>> {
+    info!("{:<6} - accept_library_invite", "POST");
+
+    let (library, role) = accept_invite(&model_manager, &code, &user_id).await?;
+
+    let res = Response::new_success(
+        200,
+        Some("Invite accepted!".to_string()),
+        Some(Library::from_db(library, role)),
+    );
+    Ok(Json(res))
+}
@@ -0,0 +1,201 @@
+use axum::{
+    body::Bytes,
+    extract::{Path, Query, State},
+    routing::{delete, get, post},
+    Json, Router,
+};
+use sea_orm::{ActiveModelTrait, EntityTrait, ModelTrait};
+use tracing::info;
+
+use crate::{
+    api::response::Response,
+    entities::books,
+    error::Result,
+    model::{
+        libraries::{can_access_book, ROLE_EDITOR},
+        loans::{
+            get_active_loan, get_current_loans, get_overdue_loans, get_user_loan, get_user_loans,
+            Loan, LoanId, LoanReturn, LoanToSave, LoanToUpdate, LoansParams,
+        },
+        ModelManager,
+    },
+    Error,
+};
+
+pub fn loans_routes(model_manager: ModelManager) -> Router {
+    Router::new()
+        .route("/loans", post(lend_book))
+        .route("/loans/:user_id", get(get_user_loans_list))
+        .route("/loans/:user_id/current", get(get_user_current_loans))
+        .route("/loans/:user_id/overdue", get(get_user_overdue_loans))
+        .route("/loans/:user_id/:id", post(update_loan))
+        .route("/loans/:user_id/:id", delete(delete_loan))
+        .route("/loans/:user_id/:id/return", post(return_book))
+        .with_state(model_manager)
+}
+
+/// Books can be lent by the users that can edit them, a book is lent to one borrower at a time
+async fn lend_book(
+    State(model_manager): State<ModelManager>,
+    Json(loan_to_save): Json<LoanToSave>,
+) -> Result<Json<Response<LoanId>>> {
+    info!("{:<6} - lend_book", "POST");
+
+    let loan = loan_to_save.to_active_model()?;
+
+    let book = books::Entity::find_by_id(*loan.book_id.as_ref())
+        .one(model_manager.db())
+        .await
+        .map_err(|e| Error::DbError(e.to_string()))?;
+    let book = match book {
+        Some(book) => book,
+        None => {
+            return Err(Error::InvalidField(format!(
+                "book not found: {}",
+                loan_to_save.book_id
+            )))
+        }
+    };
+    if !can_access_book(&model_manager, &book, &loan_to_save.user_id, ROLE_EDITOR).await? {
+        return Err(Error::Unathorized);
+    }
+    if get_active_loan(&model_manager, book.id).await?.is_some() {
+        return Err(Error::InvalidField(format!(
+            "book already lent out: {}",
+            loan_to_save.book_id
+        )));
+    }
+
+    // Save the loan in the database
+    let loan = loan.insert(model_manager.db()).await;
+    match loan {
+        Ok(l) => {
+            // return only the id of the loan created
+            let res = Response::new_success(
+                201,
+                Some("Loan created successfully!".to_string()),
+                Some(LoanId {
+                    id: l.id.to_string(),
+                }),
+            );
+            Ok(Json(res))
+        }
+        Err(e) => Err(Error::DbError(e.to_string())),
+    }
+}
+
+/// All the loans of the user, optionally of a single book with `?bookId=`
+async fn get_user_loans_list(
+    State(model_manager): State<ModelManager>,
+    Path(user_id): Path<String>,
+    Query(params): Query<LoansParams>,
+) -> Result<Json<Response<Vec<Loan>>>> {
+    info!("{:<6} - get_user_loans_list", "GET");
+
+    let loans = get_user_loans(&model_manager, &user_id, &params).await?;
+
+    let res = Response::new_success(200, None, Some(loans));
+    Ok(Json(res))
+}
+
+async fn get_user_current_loans(
+    State(model_manager): State<ModelManager>,
+    Path(user_id): Path<String>,
+) -> Result<Json<Response<Vec<Loan>>>> {
+    info!("{:<6} - get_user_current_loans", "GET");
+
+    let loans = get_current_loans(&model_manager, &user_id).await?;
+
+    let res = Response::new_success(200, None, Some(loans));
+    Ok(Json(res))
+}
+
+async fn get_user_overdue_loans(
+    State(model_manager): State<ModelManager>,
+    Path(user_id): Path<String>,
+) -> Result<Json<Response<Vec<Loan>>>> {
+    info!("{:<6} - get_user_overdue_loans", "GET");
+
+    let loans = get_overdue_loans(&model_manager, &user_id).await?;
+
+    let res = Response::new_success(200, None, Some(loans));
+    Ok(Json(res))
+}
+
+async fn update_loan(
+    State(model_manager): State<ModelManager>,
+    Path((user_id, id)): Path<(String, String)>,
+    Json(loan_to_update): Json<LoanToUpdate>,
+) -> Result<Json<Response<String>>> {
+    info!("{:<6} - update_loan", "UPDATE");
+
+    let loan = get_user_loan(&model_manager, &user_id, &id).await?;
+
+    let l = loan_to_update.to_active_model(loan)?;
+    let loan = l.update(model_manager.db()).await;
+    match loan {
+        Ok(_) => {
+            let res = Response::<String>::new_success(
+                200,
+                Some("Loan updated successfully!".to_string()),
+                None,
+            );
+            Ok(Json(res))
+        }
+        Err(e) => Err(Error::DbError(e.to_string())),
+    }
+}
+
+/// The body is optional, the book is returned today unless `returnedAt` is set
+async fn return_book(
+    State(model_manager): State<ModelManager>,
+    Path((user_id, id)): Path<(String, String)>,
+    body: Bytes,
+) -> Result<Json<Response<String>>> {
+    info!("{:<6} - return_book", "POST");
+
+    // only an empty body means today, an invalid one is rejected
+    let loan_return: LoanReturn = if body.is_empty() {
+        LoanReturn::default()
+    } else {
+        serde_json::from_slice(&body).map_err(|e| Error::ParseError(e.to_string()))?
+    };
+
+    let loan = get_user_loan(&model_manager, &user_id, &id).await?;
+
+    let l = loan_return.to_active_model(loan)?;
+    let loan = l.update(model_manager.db()).await;
+    match loan {
+        Ok(_) => {
+            let res = Response::<String>::new_success(
+                200,
+                Some("Book returned successfully!".to_string()),
+                None,
+            );
+            Ok(Json(res))
+        }
+        Err(e) => Err(Error::DbError(e.to_string())),
+    }
+}
+
+async fn delete_loan(
+    State(model_manager): State<ModelManager>,
+    Path((user_id, id)): Path<(String, String)>,
+) -> Result<Json<Response<String>>> {
+    info!("{:<6} - delete_loan", "DELETE");
+
+    let loan = get_user_loan(&model_manager, &user_id, &id).await?;
+
+    let res = loan.delete(model_manager.db()).await;
+    match res {
+        Ok(_) => {
+            let res = Response::<String>::new_success(
+                200,
+                Some("Loan deleted successfully!".to_string()),
+                None,
+            );
+            Ok(Json(res))
+        }
+        Err(e) => Err(Error::DbError(e.to_string())),
+    }
+}
@@ -0,0 +1,114 @@
+use axum::{
+    extract::{Path, State},
+    routing::{delete, get, post},
+    Json, Router,
+};
+use tracing::info;
+
+use crate::{
+    api::response::Response,
+    error::Result,
+    model::{
+        manual_books::{
+            delete_manual_volume, ManualBook, ManualBookId, ManualBookToSave, ManualBookToUpdate,
+        },
+        volumes::{get_user_manual_volume, get_user_manual_volumes, save_manual_volume},
+        ModelManager,
+    },
+};
+
+/// Books missing from the external API. The id of a manual book is used as
+/// the bookId when saving it in a shelf
+pub fn manual_books_routes(model_manager: ModelManager) -> Router {
+    Router::new()
+        .route("/manual-books", post(save_manual_book))
+        .route("/manual-books/:user_id", get(get_manual_books))
+        .route("/manual-books/:user_id/:book_id", get(get_manual_book))
+        .route("/manual-books/:user_id/:book_id", post(update_manual_book))
+        .route(
+            "/manual-books/:user_id/:book_id",
+            delete(delete_manual_book),
+        )
+        .with_state(model_manager)
+}
+
+async fn save_manual_book(
+    State(model_manager): State<ModelManager>,
+    Json(book_to_save): Json<ManualBookToSave>,
+) -> Result<Json<Response<ManualBookId>>> {
+    info!("{:<6} - save_manual_book", "POST");
+
+    let (book_id, book_api) = book_to_save.to_book_api()?;
+    save_manual_volume(&model_manager, &book_to_save.user_id, &book_id, &book_api).await?;
+
+    // return only the id of the volume created
+    let res = Response::new_success(
+        201,
+        Some("Manual book created successfully!".to_string()),
+        Some(ManualBookId { book_id }),
+    );
+    Ok(Json(res))
+}
+
+async fn get_manual_books(
+    State(model_manager): State<ModelManager>,
+    Path(user_id): Path<String>,
+) -> Result<Json<Response<Vec<ManualBook>>>> {
+    info!("{:<6} - get_manual_books", "GET");
+
+    let volumes = get_user_manual_volumes(&model_manager, &user_id).await?;
+    let books = volumes.into_iter().map(ManualBook::from).collect();
+
+    let res = Response::new_success(200, None, Some(books));
+    Ok(Json(res))
+}
+
+async fn get_manual_book(
+    State(model_manager): State<ModelManager>,
+    Path((user_id, book_id)): Path<(String, String)>,
+) -> Result<Json<Response<ManualBook>>> {
+    info!("{:<6} - get_manual_book", "GET");
+
+    let volume = get_user_manual_volume(&model_manager, &user_id, &book_id).await?;
+
+    let res = Response::new_success(200, None, Some(ManualBook::from(volume)));
+    Ok(Json(res))
+}
+
+/// Only the user who created the manual book can edit it, the books of the
+/// shelves using it show the new metadata
+async fn update_manual_book(
+    State(model_manager): State<ModelManager>,
+    Path((user_id, book_id)): Path<(String, String)>,
+    Json(book_to_update): Json<ManualBookToUpdate>,
+) -> Result<Json<Response<String>>> {
+    info!("{:<6} - update_manual_book", "UPDATE");
+
+    let volume = get_user_manual_volume(&model_manager, &user_id, &book_id).await?;
+    let book_api = book_to_update.to_book_api(volume)?;
+    save_manual_volume(&model_manager, &user_id, &book_id, &book_api).await?;
+
+    let res = Response::<String>::new_success(
+        200,
+        Some("Manual book updated successfully!".to_string()),
+        None,
+    );
+    Ok(Json(res))
+}
+
+async fn delete_manual_book(
+    State(model_manager): State<ModelManager>,
+    Path((user_id, book_id)): Path<(String, String)>,
+) -> Result<Json<Response<String>>> {
+    info!("{:<6} - delete_manual_book", "DELETE");
+
+    let volume = get_user_manual_volume(&model_manager, &user_id, &book_id).await?;
+    delete_manual_volume(&model_manager, volume).await?;
+
+    let res = Response::<String>::new_success(
+        200,
+        Some("Manual book deleted successfully!".to_string()),
+        None,
+    );
+    Ok(Json(res))
+}
@@ -1 +1,17 @@
+pub mod authors;
 pub mod books;
+pub mod covers;
+pub mod custom_fields;
+pub mod export;
+pub mod highlights;
+pub mod import;
+pub mod libraries;
+pub mod loans;
+pub mod manual_books;
+pub mod opds;
+pub mod preferences;
+pub mod reviews;
+pub mod series;
+pub mod smart_shelves;
+pub mod tags;
+pub mod wishlist;
@@ -0,0 +1,255 @@
+use std::collections::BTreeMap;
+
+use axum::{
+    extract::{Path, State},
+    http::header,
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+use sea_orm::{ColumnTrait, EntityTrait, ModelTrait, PaginatorTrait, QueryFilter, QueryOrder};
+use tracing::info;
+
+use crate::{
+    entities::{books, libraries, library_members},
+    error::Result,
+    model::{
+        books::UserBooks,
+        libraries::{get_user_library, ROLE_VIEWER},
+        opds::{Feed, FeedKind, NavigationEntry, OPDS_JSON},
+        ModelManager,
+    },
+    Error,
+};
+
+/// OPDS 1.2 catalogs are served under /opds, OPDS 2.0 catalogs under /opds2
+pub fn opds_routes(model_manager: ModelManager) -> Router {
+    Router::new()
+        .route("/opds/:user_id", get(opds_root))
+        .route("/opds/:user_id/*path", get(opds_feed))
+        .route("/opds2/:user_id", get(opds2_root))
+        .route("/opds2/:user_id/*path", get(opds2_feed))
+        .with_state(model_manager)
+}
+
+async fn opds_root(
+    State(model_manager): State<ModelManager>,
+    Path(user_id): Path<String>,
+) -> Result<Response> {
+    info!("{:<6} - opds_root", "GET");
+
+    let feed = build_feed(&model_manager, &user_id, &[]).await?;
+    Ok(atom_response(&feed, &user_id))
+}
+
+async fn opds_feed(
+    State(model_manager): State<ModelManager>,
+    Path((user_id, path)): Path<(String, String)>,
+) -> Result<Response> {
+    info!("{:<6} - opds_feed", "GET");
+
+    let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+    let feed = build_feed(&model_manager, &user_id, &segments).await?;
+    Ok(atom_response(&feed, &user_id))
+}
+
+async fn opds2_root(
+    State(model_manager): State<ModelManager>,
+    Path(user_id): Path<String>,
+) -> Result<Response> {
+    info!("{:<6} - opds2_root", "GET");
+
+    let feed = build_feed(&model_manager, &user_id, &[]).await?;
+    Ok(json_response(&feed, &user_id))
+}
+
+async fn opds2_feed(
+    State(model_manager): State<ModelManager>,
+    Path((user_id, path)): Path<(String, String)>,
+) -> Result<Response> {
+    info!("{:<6} - opds2_feed", "GET");
+
+    let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+    let feed = build_feed(&model_manager, &user_id, &segments).await?;
+    Ok(json_response(&feed, &user_id))
+}
+
+fn atom_response(feed: &Feed, user_id: &str) -> Response {
+    let base = format!("/api/v0/opds/{}", user_id);
+    (
+        [(header::CONTENT_TYPE, feed.content_type_atom())],
+        feed.to_atom(&base),
+    )
+        .into_response()
+}
+
+fn json_response(feed: &Feed, user_id: &str) -> Response {
+    let base = format!("/api/v0/opds2/{}", user_id);
+    (
+        [(header::CONTENT_TYPE, OPDS_JSON)],
+        Json(feed.to_json(&base)),
+    )
+        .into_response()
+}
+
+/// Builds the feed at the given path of the user's catalog:
+/// - `/` navigation feed of the catalog
+/// - `/all` every book of the shelf
+/// - `/status`, `/status/:reading_status` books by reading status
+/// - `/tags`, `/tags/*tag` books by tag, the path of the tag can contain `/`
+/// - `/libraries`, `/libraries/:library_id` books by library
+async fn build_feed(
+    model_manager: &ModelManager,
+    user_id: &str,
+    segments: &[&str],
+) -> Result<Feed> {
+    let books = books::Entity::find()
+        .filter(books::Column::UserId.eq(user_id))
+        .all(model_manager.db())
+        .await
+        .map_err(|e| Error::DbError(e.to_string()))?;
+
+    let feed = match segments {
+        [] => Feed {
+            title: "Shelf".to_string(),
+            path: "".to_string(),
+            kind: FeedKind::Navigation(vec![
+                NavigationEntry::new("All books".to_string(), &["all"], Some(books.len())),
+                NavigationEntry::new("By reading status".to_string(), &["status"], None),
+                NavigationEntry::new("By tag".to_string(), &["tags"], None),
+                NavigationEntry::new("By library".to_string(), &["libraries"], None),
+            ]),
+        },
+        ["all"] => Feed {
+            title: "All books".to_string(),
+            path: "all".to_string(),
+            kind: FeedKind::Acquisition(
+                UserBooks::from_db(model_manager, user_id.to_string(), books).await?,
+            ),
+        },
+        ["status"] => navigation_feed(
+            "By reading status",
+            "status",
+            books.iter().filter_map(|b| b.reading_status.clone()),
+        ),
+        ["status", reading_status] => {
+            let books = books
+                .into_iter()
+                .filter(|b| b.reading_status.as_deref() == Some(*reading_status))
+                .collect();
+            acquisition_feed(
+                model_manager,
+                user_id,
+                reading_status,
+                &["status", reading_status],
+                books,
+            )
+            .await?
+        }
+        ["tags"] => navigation_feed(
+            "By tag",
+            "tags",
+            books
+                .iter()
+                .flat_map(|b| b.tags.clone().unwrap_or_default()),
+        ),
+        ["tags", tag @ ..] => {
+            // axum decodes the path before it is split, so the `/` of the
+            // hierarchical tags split them in several segments
+            let tag = tag.join("/");
+            let books = books
+                .into_iter()
+                .filter(|b| b.tags.as_ref().is_some_and(|tags| tags.contains(&tag)))
+                .collect();
+            acquisition_feed(model_manager, user_id, &tag, &["tags", &tag], books).await?
+        }
+        ["libraries"] => {
+            // libraries the user is a member of, owned ones included
+            let libraries = libraries::Entity::find()
+                .inner_join(library_members::Entity)
+                .filter(library_members::Column::UserId.eq(user_id))
+                .order_by_asc(libraries::Column::SortOrder)
+                .all(model_manager.db())
+                .await
+                .map_err(|e| Error::DbError(e.to_string()))?;
+
+            // the entries point to acquisition feeds, with the books of every member
+            let mut entries = Vec::with_capacity(libraries.len());
+            for library in libraries {
+                let count = books::Entity::find()
+                    .filter(books::Column::LibraryId.eq(library.id))
+                    .count(model_manager.db())
+                    .await
+                    .map_err(|e| Error::DbError(e.to_string()))?;
+                entries.push(NavigationEntry::new(
+                    library.name,
+                    &["libraries", &library.id.to_string()],
+                    Some(count as usize),
+                ));
+            }
+
+            Feed {
+                title: "By library".to_string(),
+                path: "libraries".to_string(),
+                kind: FeedKind::Navigation(entries),
+            }
+        }
+        ["libraries", library_id] => {
+            let library = get_user_library(model_manager, user_id, library_id, ROLE_VIEWER).await?;
+            // books of every member of the library
+            let books = library
+                .find_related(books::Entity)
+                .all(model_manager.db())
+                .await
+                .map_err(|e| Error::DbError(e.to_string()))?;
+            acquisition_feed(
+                model_manager,
+                user_id,
+                &library.name,
+                &["libraries", library_id],
+                books,
+            )
+            .await?
+        }
+        _ => return Err(Error::NotFound),
+    };
+
+    Ok(feed)
+}
+
+/// Navigation feed with an entry for each distinct value, with its number of books
+fn navigation_feed(title: &str, path: &str, values: impl Iterator<Item = String>) -> Feed {
+    let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+    for value in values {
+        *counts.entry(value).or_default() += 1;
+    }
+
+    Feed {
+        title: title.to_string(),
+        path: path.to_string(),
+        kind: FeedKind::Navigation(
+            counts
+                .into_iter()
+                .map(|(value, count)| {
+                    NavigationEntry::new(value.clone(), &[path, &value], Some(count))
+                })
+                .collect(),
+        ),
+    }
+}
+
+async fn acquisition_feed(
+    model_manager: &ModelManager,
+    user_id: &str,
+    title: &str,
+    segments: &[&str],
+    books: Vec<books::Model>,
+) -> Result<Feed> {
+    Ok(Feed {
+        title: title.to_string(),
+        path: NavigationEntry::new("".to_string(), segments, None).path,
+        kind: FeedKind::Acquisition(
+            UserBooks::from_db(model_manager, user_id.to_string(), books).await?,
+        ),
+    })
+}
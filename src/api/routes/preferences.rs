@@ -0,0 +1,51 @@
+use axum::{
+    extract::{Path, State},
+    routing::{get, post},
+    Json, Router,
+};
+use tracing::info;
+
+use crate::{
+    api::response::Response,
+    error::Result,
+    model::{
+        preferences::{get_preferences, save_preferences, Preferences, PreferencesToUpdate},
+        ModelManager,
+    },
+};
+
+pub fn preferences_routes(model_manager: ModelManager) -> Router {
+    Router::new()
+        .route("/preferences/:user_id", get(get_user_preferences))
+        .route("/preferences/:user_id", post(update_preferences))
+        .with_state(model_manager)
+}
+
+async fn get_user_preferences(
+    State(model_manager): State<ModelManager>,
+    Path(user_id): Path<String>,
+) -> Result<Json<Response<Preferences>>> {
+    info!("{:<6} - get_user_preferences", "GET");
+
+    let preferences = get_preferences(&model_manager, &user_id).await?;
+
+    let res = Response::new_success(200, None, Some(preferences));
+    Ok(Json(res))
+}
+
+async fn update_preferences(
+    State(model_manager): State<ModelManager>,
+    Path(user_id): Path<String>,
+    Json(preferences_to_update): Json<PreferencesToUpdate>,
+) -> Result<Json<Response<Preferences>>> {
+    info!("{:<6} - update_preferences", "UPDATE");
+
+    let preferences = save_preferences(&model_manager, &user_id, &preferences_to_update).await?;
+
+    let res = Response::new_success(
+        200,
+        Some("Preferences updated successfully!".to_string()),
+        Some(preferences),
+    );
+    Ok(Json(res))
+}
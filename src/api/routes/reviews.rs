@@ -0,0 +1,170 @@
+use axum::{
+    extract::{Path, State},
+    routing::{delete, get, post},
+    Json, Router,
+};
+use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, ModelTrait, QueryFilter, QueryOrder};
+use tracing::info;
+
+use crate::{
+    api::response::Response,
+    entities::{books, reviews},
+    error::Result,
+    model::{
+        preferences::get_rating_scale,
+        reviews::{get_user_review, Review, ReviewToSave, ReviewToUpdate},
+        ModelManager,
+    },
+    Error,
+};
+
+pub fn reviews_routes(model_manager: ModelManager) -> Router {
+    Router::new()
+        .route("/reviews", post(save_review))
+        .route("/reviews/:user_id", get(get_user_reviews))
+        .route("/reviews/:user_id/:id", get(get_user_review_by_id))
+        .route("/reviews/:user_id/:id", post(update_review))
+        .route("/reviews/:user_id/:id", delete(delete_review))
+        .with_state(model_manager)
+}
+
+/// Users can review the books of their own shelf, once for each book
+async fn save_review(
+    State(model_manager): State<ModelManager>,
+    Json(mut review_to_save): Json<ReviewToSave>,
+) -> Result<Json<Response<Review>>> {
+    info!("{:<6} - save_review", "POST");
+
+    // the ratings are received in the scale preferred by the user
+    let rating_scale = get_rating_scale(&model_manager, &review_to_save.user_id).await?;
+    review_to_save.canonical_ratings(rating_scale)?;
+    let review = review_to_save.to_active_model()?;
+
+    let book = books::Entity::find_by_id(*review.book_id.as_ref())
+        .one(model_manager.db())
+        .await
+        .map_err(|e| Error::DbError(e.to_string()))?;
+    match book {
+        None => {
+            return Err(Error::InvalidField(format!(
+                "book not found: {}",
+                review_to_save.book_id
+            )))
+        }
+        Some(b) => {
+            // check if the user owner of the book is the same one of the call
+            if b.user_id != review_to_save.user_id {
+                return Err(Error::Unathorized);
+            }
+        }
+    }
+
+    // Save the review in the database
+    let review = review.insert(model_manager.db()).await;
+    match review {
+        Ok(r) => {
+            let mut review = Review::from(r);
+            review.convert_ratings(rating_scale);
+            let res = Response::new_success(
+                201,
+                Some("Review created successfully!".to_string()),
+                Some(review),
+            );
+            Ok(Json(res))
+        }
+        Err(e) => Err(Error::DbError(e.to_string())),
+    }
+}
+
+async fn get_user_reviews(
+    State(model_manager): State<ModelManager>,
+    Path(user_id): Path<String>,
+) -> Result<Json<Response<Vec<Review>>>> {
+    info!("{:<6} - get_user_reviews", "GET");
+
+    let rating_scale = get_rating_scale(&model_manager, &user_id).await?;
+    let reviews = reviews::Entity::find()
+        .filter(reviews::Column::UserId.eq(user_id))
+        .order_by_desc(reviews::Column::CreatedAt)
+        .all(model_manager.db())
+        .await
+        .map_err(|e| Error::DbError(e.to_string()))?;
+
+    let reviews = reviews
+        .into_iter()
+        .map(|r| {
+            let mut review = Review::from(r);
+            review.convert_ratings(rating_scale);
+            review
+        })
+        .collect();
+
+    let res = Response::new_success(200, None, Some(reviews));
+    Ok(Json(res))
+}
+
+async fn get_user_review_by_id(
+    State(model_manager): State<ModelManager>,
+    Path((user_id, id)): Path<(String, String)>,
+) -> Result<Json<Response<Review>>> {
+    info!("{:<6} - get_user_review_by_id", "GET");
+
+    let review = get_user_review(&model_manager, &user_id, &id).await?;
+    let mut review = Review::from(review);
+    review.convert_ratings(get_rating_scale(&model_manager, &user_id).await?);
+
+    let res = Response::new_success(200, None, Some(review));
+    Ok(Json(res))
+}
+
+async fn update_review(
+    State(model_manager): State<ModelManager>,
+    Path((user_id, id)): Path<(String, String)>,
+    Json(mut review_to_update): Json<ReviewToUpdate>,
+) -> Result<Json<Response<Review>>> {
+    info!("{:<6} - update_review", "UPDATE");
+
+    let review = get_user_review(&model_manager, &user_id, &id).await?;
+
+    // the ratings are received in the scale preferred by the user
+    let rating_scale = get_rating_scale(&model_manager, &user_id).await?;
+    review_to_update.canonical_ratings(rating_scale)?;
+
+    let r = review_to_update.to_active_model(review)?;
+    let review = r.update(model_manager.db()).await;
+    match review {
+        Ok(r) => {
+            let mut review = Review::from(r);
+            review.convert_ratings(rating_scale);
+            let res = Response::new_success(
+                200,
+                Some("Review updated successfully!".to_string()),
+                Some(review),
+            );
+            Ok(Json(res))
+        }
+        Err(e) => Err(Error::DbError(e.to_string())),
+    }
+}
+
+async fn delete_review(
+    State(model_manager): State<ModelManager>,
+    Path((user_id, id)): Path<(String, String)>,
+) -> Result<Json<Response<String>>> {
+    info!("{:<6} - delete_review", "DELETE");
+
+    let review = get_user_review(&model_manager, &user_id, &id).await?;
+
+    let res = review.delete(model_manager.db()).await;
+    match res {
+        Ok(_) => {
+            let res = Response::<String>::new_success(
+                200,
+                Some("Review deleted successfully!".to_string()),
+                None,
+            );
+            Ok(Json(res))
+        }
+        Err(e) => Err(Error::DbError(e.to_string())),
+    }
+}
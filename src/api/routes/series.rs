@@ -0,0 +1,243 @@
+use axum::{
+    extract::{Path, State},
+    routing::{delete, get, post},
+    Json, Router,
+};
+use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, ModelTrait, QueryFilter, QueryOrder};
+use tracing::info;
+use uuid::Uuid;
+
+use crate::{
+    api::response::Response,
+    entities::{books, series, series_books},
+    error::Result,
+    model::{
+        libraries::{can_access_book, ROLE_VIEWER},
+        series::{
+            get_series_progress, get_user_series, save_series_book, validate_series_name, Series,
+            SeriesBook, SeriesBookToSave, SeriesId, SeriesToSave, SeriesToUpdate,
+        },
+        ModelManager,
+    },
+    Error,
+};
+
+pub fn series_routes(model_manager: ModelManager) -> Router {
+    Router::new()
+        .route("/series", post(save_series))
+        .route("/series/:user_id", get(get_user_series_list))
+        .route("/series/:user_id/:id", get(get_user_series_by_id))
+        .route("/series/:user_id/:id", post(update_series))
+        .route("/series/:user_id/:id", delete(delete_series))
+        .route("/series/:user_id/:id/next", get(get_next_unread))
+        .route("/series/:user_id/:id/books", post(add_series_book))
+        .route(
+            "/series/:user_id/:id/books/:book_id",
+            delete(remove_series_book),
+        )
+        .with_state(model_manager)
+}
+
+async fn save_series(
+    State(model_manager): State<ModelManager>,
+    Json(series_to_save): Json<SeriesToSave>,
+) -> Result<Json<Response<SeriesId>>> {
+    info!("{:<6} - save_series", "POST");
+
+    let series = series_to_save.to_active_model()?;
+    validate_series_name(
+        &model_manager,
+        &series_to_save.user_id,
+        &series_to_save.name,
+        None,
+    )
+    .await?;
+
+    // Save the series in the database
+    let series = series.insert(model_manager.db()).await;
+    match series {
+        Ok(s) => {
+            // return only the id of the series created
+            let res = Response::new_success(
+                201,
+                Some("Series created successfully!".to_string()),
+                Some(SeriesId {
+                    id: s.id.to_string(),
+                }),
+            );
+            Ok(Json(res))
+        }
+        Err(e) => Err(Error::DbError(e.to_string())),
+    }
+}
+
+/// Series of the user with their progress
+async fn get_user_series_list(
+    State(model_manager): State<ModelManager>,
+    Path(user_id): Path<String>,
+) -> Result<Json<Response<Vec<Series>>>> {
+    info!("{:<6} - get_user_series_list", "GET");
+
+    let series_list = series::Entity::find()
+        .filter(series::Column::UserId.eq(user_id))
+        .order_by_asc(series::Column::Name)
+        .all(model_manager.db())
+        .await
+        .map_err(|e| Error::DbError(e.to_string()))?;
+
+    let mut res = Vec::with_capacity(series_list.len());
+    for series in series_list {
+        res.push(get_series_progress(&model_manager, series).await?);
+    }
+
+    let res = Response::new_success(200, None, Some(res));
+    Ok(Json(res))
+}
+
+async fn get_user_series_by_id(
+    State(model_manager): State<ModelManager>,
+    Path((user_id, id)): Path<(String, String)>,
+) -> Result<Json<Response<Series>>> {
+    info!("{:<6} - get_user_series_by_id", "GET");
+
+    let series = get_user_series(&model_manager, &user_id, &id).await?;
+    let series = get_series_progress(&model_manager, series).await?;
+
+    let res = Response::new_success(200, None, Some(series));
+    Ok(Json(res))
+}
+
+/// First book of the series not read by the user, not found once the series is read
+async fn get_next_unread(
+    State(model_manager): State<ModelManager>,
+    Path((user_id, id)): Path<(String, String)>,
+) -> Result<Json<Response<SeriesBook>>> {
+    info!("{:<6} - get_next_unread", "GET");
+
+    let series = get_user_series(&model_manager, &user_id, &id).await?;
+    let series = get_series_progress(&model_manager, series).await?;
+
+    match series.next_unread {
+        Some(book) => {
+            let res = Response::new_success(200, None, Some(book));
+            Ok(Json(res))
+        }
+        None => Err(Error::NotFound),
+    }
+}
+
+async fn update_series(
+    State(model_manager): State<ModelManager>,
+    Path((user_id, id)): Path<(String, String)>,
+    Json(series_to_update): Json<SeriesToUpdate>,
+) -> Result<Json<Response<String>>> {
+    info!("{:<6} - update_series", "UPDATE");
+
+    let series = get_user_series(&model_manager, &user_id, &id).await?;
+
+    if let Some(name) = &series_to_update.name {
+        validate_series_name(&model_manager, &user_id, name, Some(series.id)).await?;
+    }
+    let s = series_to_update.to_active_model(series)?;
+    let series = s.update(model_manager.db()).await;
+    match series {
+        Ok(_) => {
+            let res = Response::<String>::new_success(
+                200,
+                Some("Series updated successfully!".to_string()),
+                None,
+            );
+            Ok(Json(res))
+        }
+        Err(e) => Err(Error::DbError(e.to_string())),
+    }
+}
+
+async fn delete_series(
+    State(model_manager): State<ModelManager>,
+    Path((user_id, id)): Path<(String, String)>,
+) -> Result<Json<Response<String>>> {
+    info!("{:<6} - delete_series", "DELETE");
+
+    let series = get_user_series(&model_manager, &user_id, &id).await?;
+
+    // the books stay in the shelf, only their membership is removed
+    let res = series.delete(model_manager.db()).await;
+    match res {
+        Ok(_) => {
+            let res = Response::<String>::new_success(
+                200,
+                Some("Series deleted successfully!".to_string()),
+                None,
+            );
+            Ok(Json(res))
+        }
+        Err(e) => Err(Error::DbError(e.to_string())),
+    }
+}
+
+/// Adds a book the user can see to the series, or moves it to the new position
+async fn add_series_book(
+    State(model_manager): State<ModelManager>,
+    Path((user_id, id)): Path<(String, String)>,
+    Json(series_book_to_save): Json<SeriesBookToSave>,
+) -> Result<Json<Response<String>>> {
+    info!("{:<6} - add_series_book", "POST");
+
+    let series = get_user_series(&model_manager, &user_id, &id).await?;
+    let series_book = series_book_to_save.to_active_model(series.id)?;
+
+    let book = books::Entity::find_by_id(*series_book.book_id.as_ref())
+        .one(model_manager.db())
+        .await
+        .map_err(|e| Error::DbError(e.to_string()))?;
+    let book = match book {
+        Some(book) => book,
+        None => {
+            return Err(Error::InvalidField(format!(
+                "book not found: {}",
+                series_book_to_save.book_id
+            )))
+        }
+    };
+    if !can_access_book(&model_manager, &book, &user_id, ROLE_VIEWER).await? {
+        return Err(Error::Unathorized);
+    }
+
+    save_series_book(&model_manager, series_book).await?;
+
+    let res = Response::<String>::new_success(
+        200,
+        Some("Book added to the series successfully!".to_string()),
+        None,
+    );
+    Ok(Json(res))
+}
+
+async fn remove_series_book(
+    State(model_manager): State<ModelManager>,
+    Path((user_id, id, book_id)): Path<(String, String, String)>,
+) -> Result<Json<Response<String>>> {
+    info!("{:<6} - remove_series_book", "DELETE");
+
+    let series = get_user_series(&model_manager, &user_id, &id).await?;
+    let book_id =
+        Uuid::parse_str(&book_id).map_err(|_| Error::ParseError("Invalid id".to_string()))?;
+
+    let res = series_books::Entity::delete_many()
+        .filter(series_books::Column::SeriesId.eq(series.id))
+        .filter(series_books::Column::BookId.eq(book_id))
+        .exec(model_manager.db())
+        .await
+        .map_err(|e| Error::DbError(e.to_string()))?;
+    if res.rows_affected == 0 {
+        return Err(Error::NotFound);
+    }
+
+    let res = Response::<String>::new_success(
+        200,
+        Some("Book removed from the series successfully!".to_string()),
+        None,
+    );
+    Ok(Json(res))
+}
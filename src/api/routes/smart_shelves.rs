@@ -0,0 +1,163 @@
+use axum::{
+    extract::{Path, State},
+    routing::{delete, get, post},
+    Json, Router,
+};
+use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, ModelTrait, QueryFilter, QueryOrder};
+use tracing::info;
+
+use crate::{
+    api::response::Response,
+    entities::smart_shelves,
+    error::Result,
+    model::{
+        books::UserBooks,
+        preferences::get_rating_scale,
+        smart_shelves::{
+            get_shelf_books, get_user_shelf, SmartShelf, SmartShelfId, SmartShelfToSave,
+            SmartShelfToUpdate,
+        },
+        ModelManager,
+    },
+    Error,
+};
+
+pub fn smart_shelves_routes(model_manager: ModelManager) -> Router {
+    Router::new()
+        .route("/shelves", post(save_shelf))
+        .route("/shelves/:user_id", get(get_user_shelves))
+        .route("/shelves/:user_id/:id", get(get_user_shelf_by_id))
+        .route("/shelves/:user_id/:id", post(update_shelf))
+        .route("/shelves/:user_id/:id", delete(delete_shelf))
+        .route("/shelves/:user_id/:id/books", get(get_user_shelf_books))
+        .with_state(model_manager)
+}
+
+async fn save_shelf(
+    State(model_manager): State<ModelManager>,
+    Json(shelf_to_save): Json<SmartShelfToSave>,
+) -> Result<Json<Response<SmartShelfId>>> {
+    info!("{:<6} - save_shelf", "POST");
+
+    let rating_scale = get_rating_scale(&model_manager, &shelf_to_save.user_id).await?;
+    let shelf = shelf_to_save.to_active_model(rating_scale)?;
+
+    // Save the shelf in the database
+    let shelf = shelf.insert(model_manager.db()).await;
+    match shelf {
+        Ok(s) => {
+            // return only the id of the shelf created
+            let res = Response::new_success(
+                201,
+                Some("Shelf created successfully!".to_string()),
+                Some(SmartShelfId {
+                    id: s.id.to_string(),
+                }),
+            );
+            Ok(Json(res))
+        }
+        Err(e) => Err(Error::DbError(e.to_string())),
+    }
+}
+
+async fn get_user_shelves(
+    State(model_manager): State<ModelManager>,
+    Path(user_id): Path<String>,
+) -> Result<Json<Response<Vec<SmartShelf>>>> {
+    info!("{:<6} - get_user_shelves", "GET");
+
+    let rating_scale = get_rating_scale(&model_manager, &user_id).await?;
+    let shelves = smart_shelves::Entity::find()
+        .filter(smart_shelves::Column::UserId.eq(user_id))
+        .order_by_asc(smart_shelves::Column::Name)
+        .all(model_manager.db())
+        .await
+        .map_err(|e| Error::DbError(e.to_string()))?;
+
+    let shelves = shelves
+        .into_iter()
+        .map(|shelf| {
+            let mut shelf = SmartShelf::from(shelf);
+            shelf.convert_ratings(rating_scale);
+            shelf
+        })
+        .collect();
+
+    let res = Response::new_success(200, None, Some(shelves));
+    Ok(Json(res))
+}
+
+async fn get_user_shelf_by_id(
+    State(model_manager): State<ModelManager>,
+    Path((user_id, id)): Path<(String, String)>,
+) -> Result<Json<Response<SmartShelf>>> {
+    info!("{:<6} - get_user_shelf_by_id", "GET");
+
+    let shelf = get_user_shelf(&model_manager, &user_id, &id).await?;
+    let mut shelf = SmartShelf::from(shelf);
+    shelf.convert_ratings(get_rating_scale(&model_manager, &user_id).await?);
+
+    let res = Response::new_success(200, None, Some(shelf));
+    Ok(Json(res))
+}
+
+async fn update_shelf(
+    State(model_manager): State<ModelManager>,
+    Path((user_id, id)): Path<(String, String)>,
+    Json(shelf_to_update): Json<SmartShelfToUpdate>,
+) -> Result<Json<Response<String>>> {
+    info!("{:<6} - update_shelf", "UPDATE");
+
+    let shelf = get_user_shelf(&model_manager, &user_id, &id).await?;
+
+    let rating_scale = get_rating_scale(&model_manager, &user_id).await?;
+    let s = shelf_to_update.to_active_model(shelf, rating_scale)?;
+    let shelf = s.update(model_manager.db()).await;
+    match shelf {
+        Ok(_) => {
+            let res = Response::<String>::new_success(
+                200,
+                Some("Shelf updated successfully!".to_string()),
+                None,
+            );
+            Ok(Json(res))
+        }
+        Err(e) => Err(Error::DbError(e.to_string())),
+    }
+}
+
+async fn delete_shelf(
+    State(model_manager): State<ModelManager>,
+    Path((user_id, id)): Path<(String, String)>,
+) -> Result<Json<Response<String>>> {
+    info!("{:<6} - delete_shelf", "DELETE");
+
+    let shelf = get_user_shelf(&model_manager, &user_id, &id).await?;
+
+    let res = shelf.delete(model_manager.db()).await;
+    match res {
+        Ok(_) => {
+            let res = Response::<String>::new_success(
+                200,
+                Some("Shelf deleted successfully!".to_string()),
+                None,
+            );
+            Ok(Json(res))
+        }
+        Err(e) => Err(Error::DbError(e.to_string())),
+    }
+}
+
+/// Evaluates the filters of the shelf on the current books of the user
+async fn get_user_shelf_books(
+    State(model_manager): State<ModelManager>,
+    Path((user_id, id)): Path<(String, String)>,
+) -> Result<Json<Response<UserBooks>>> {
+    info!("{:<6} - get_user_shelf_books", "GET");
+
+    let shelf = get_user_shelf(&model_manager, &user_id, &id).await?;
+    let user_books = get_shelf_books(&model_manager, &SmartShelf::from(shelf)).await?;
+
+    let res = Response::new_success(200, None, Some(user_books));
+    Ok(Json(res))
+}
@@ -0,0 +1,176 @@
+use axum::{
+    extract::{Path, State},
+    routing::{delete, get, post},
+    Json, Router,
+};
+use sea_orm::ModelTrait;
+use tracing::info;
+
+use crate::{
+    api::response::Response,
+    error::Result,
+    model::{
+        tags::{
+            delete_tag, find_tag, get_user_tag_models, get_user_tags, merge_tags, save_tag,
+            update_tag, Tag, TagCount, TagToRename, TagToSave, TagToUpdate, TagsToMerge,
+            TagsUpdated,
+        },
+        ModelManager,
+    },
+    Error,
+};
+
+/// A tag named "merge" can be renamed through the merge endpoint
+pub fn tags_routes(model_manager: ModelManager) -> Router {
+    Router::new()
+        .route("/tags/:user_id", get(get_tags))
+        .route("/tags/:user_id/merge", post(merge))
+        .route("/tags/:user_id/:tag", post(rename))
+        .route("/tags/:user_id/:tag", delete(delete_user_tag))
+        .route("/tag-definitions", post(save_tag_definition))
+        .route("/tag-definitions/:user_id", get(get_tag_definitions))
+        .route("/tag-definitions/:user_id/:id", post(update_tag_definition))
+        .route(
+            "/tag-definitions/:user_id/:id",
+            delete(delete_tag_definition),
+        )
+        .with_state(model_manager)
+}
+
+async fn get_tags(
+    State(model_manager): State<ModelManager>,
+    Path(user_id): Path<String>,
+) -> Result<Json<Response<Vec<TagCount>>>> {
+    info!("{:<6} - get_tags", "GET");
+
+    let tags = get_user_tags(&model_manager, &user_id).await?;
+
+    let res = Response::new_success(200, None, Some(tags));
+    Ok(Json(res))
+}
+
+async fn rename(
+    State(model_manager): State<ModelManager>,
+    Path((user_id, tag)): Path<(String, String)>,
+    Json(tag_to_rename): Json<TagToRename>,
+) -> Result<Json<Response<TagsUpdated>>> {
+    info!("{:<6} - rename", "UPDATE");
+
+    let updated = merge_tags(&model_manager, &user_id, &[tag], &tag_to_rename.name).await?;
+
+    let res = Response::new_success(
+        200,
+        Some("Tag renamed successfully!".to_string()),
+        Some(updated),
+    );
+    Ok(Json(res))
+}
+
+async fn merge(
+    State(model_manager): State<ModelManager>,
+    Path(user_id): Path<String>,
+    Json(tags_to_merge): Json<TagsToMerge>,
+) -> Result<Json<Response<TagsUpdated>>> {
+    info!("{:<6} - merge", "UPDATE");
+
+    let updated = merge_tags(
+        &model_manager,
+        &user_id,
+        &tags_to_merge.tags,
+        &tags_to_merge.into,
+    )
+    .await?;
+
+    let res = Response::new_success(
+        200,
+        Some("Tags merged successfully!".to_string()),
+        Some(updated),
+    );
+    Ok(Json(res))
+}
+
+async fn delete_user_tag(
+    State(model_manager): State<ModelManager>,
+    Path((user_id, tag)): Path<(String, String)>,
+) -> Result<Json<Response<TagsUpdated>>> {
+    info!("{:<6} - delete_user_tag", "DELETE");
+
+    let updated = delete_tag(&model_manager, &user_id, &tag).await?;
+
+    let res = Response::new_success(
+        200,
+        Some("Tag deleted successfully!".to_string()),
+        Some(updated),
+    );
+    Ok(Json(res))
+}
+
+async fn save_tag_definition(
+    State(model_manager): State<ModelManager>,
+    Json(tag_to_save): Json<TagToSave>,
+) -> Result<Json<Response<Tag>>> {
+    info!("{:<6} - save_tag_definition", "POST");
+
+    let tag = save_tag(&model_manager, &tag_to_save).await?;
+
+    let res = Response::new_success(
+        201,
+        Some("Tag created successfully!".to_string()),
+        Some(tag),
+    );
+    Ok(Json(res))
+}
+
+/// Returns the tags of the user as trees, with their colors and icons
+async fn get_tag_definitions(
+    State(model_manager): State<ModelManager>,
+    Path(user_id): Path<String>,
+) -> Result<Json<Response<Vec<Tag>>>> {
+    info!("{:<6} - get_tag_definitions", "GET");
+
+    let tags = get_user_tag_models(&model_manager, &user_id).await?;
+
+    let res = Response::new_success(200, None, Some(Tag::tree(&tags)));
+    Ok(Json(res))
+}
+
+async fn update_tag_definition(
+    State(model_manager): State<ModelManager>,
+    Path((user_id, id)): Path<(String, String)>,
+    Json(tag_to_update): Json<TagToUpdate>,
+) -> Result<Json<Response<Tag>>> {
+    info!("{:<6} - update_tag_definition", "UPDATE");
+
+    let tag = update_tag(&model_manager, &user_id, &id, &tag_to_update).await?;
+
+    let res = Response::new_success(
+        200,
+        Some("Tag updated successfully!".to_string()),
+        Some(tag),
+    );
+    Ok(Json(res))
+}
+
+/// Deletes the tag with its descendants, the tags of the books are kept
+async fn delete_tag_definition(
+    State(model_manager): State<ModelManager>,
+    Path((user_id, id)): Path<(String, String)>,
+) -> Result<Json<Response<String>>> {
+    info!("{:<6} - delete_tag_definition", "DELETE");
+
+    let tags = get_user_tag_models(&model_manager, &user_id).await?;
+    let tag = find_tag(&tags, &id)?;
+
+    let res = tag.delete(model_manager.db()).await;
+    match res {
+        Ok(_) => {
+            let res = Response::<String>::new_success(
+                200,
+                Some("Tag deleted successfully!".to_string()),
+                None,
+            );
+            Ok(Json(res))
+        }
+        Err(e) => Err(Error::DbError(e.to_string())),
+    }
+}
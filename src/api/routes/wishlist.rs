@@ -0,0 +1,70 @@
+use axum::{
+    extract::{Path, State},
+    routing::get,
+    Json, Router,
+};
+use sea_orm::EntityTrait;
+use tracing::info;
+use uuid::Uuid;
+
+use crate::{
+    api::response::Response,
+    entities::books,
+    error::Result,
+    model::{
+        libraries::{can_access_book, ROLE_VIEWER},
+        wishlist::{get_price_history, get_wishlist, PricePoint, Wishlist},
+        ModelManager,
+    },
+    Error,
+};
+
+pub fn wishlist_routes(model_manager: ModelManager) -> Router {
+    Router::new()
+        .route("/wishlist/:user_id", get(get_user_wishlist))
+        .route("/wishlist/:user_id/:id/prices", get(get_book_prices))
+        .with_state(model_manager)
+}
+
+async fn get_user_wishlist(
+    State(model_manager): State<ModelManager>,
+    Path(user_id): Path<String>,
+) -> Result<Json<Response<Wishlist>>> {
+    info!("{:<6} - get_user_wishlist", "GET");
+
+    let wishlist = get_wishlist(&model_manager, &user_id).await?;
+
+    let res = Response::new_success(200, None, Some(wishlist));
+    Ok(Json(res))
+}
+
+/// Prices checked for the book, the oldest first
+async fn get_book_prices(
+    State(model_manager): State<ModelManager>,
+    Path((user_id, id)): Path<(String, String)>,
+) -> Result<Json<Response<Vec<PricePoint>>>> {
+    info!("{:<6} - get_book_prices", "GET");
+
+    // check if the id can be parsed into a Uuid
+    let id_to_search = match Uuid::parse_str(&id) {
+        Ok(id) => id,
+        Err(_) => return Err(Error::ParseError("Invalid id".to_string())),
+    };
+
+    let book = match books::Entity::find_by_id(id_to_search)
+        .one(model_manager.db())
+        .await
+    {
+        Ok(Some(book)) => book,
+        Ok(None) => return Err(Error::NotFound),
+        Err(e) => return Err(Error::DbError(e.to_string())),
+    };
+    if !can_access_book(&model_manager, &book, &user_id, ROLE_VIEWER).await? {
+        return Err(Error::Unathorized);
+    }
+
+    let prices = get_price_history(&model_manager, &book.book_id).await?;
+
+    let res = Response::new_success(200, None, Some(prices));
+    Ok(Json(res))
+}
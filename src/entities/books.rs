@@ -23,13 +23,81 @@ pub struct Model {
     pub rating: Option<f64>,
     #[sea_orm(column_type = "Text", nullable)]
     pub notes: Option<String>,
-    #[sea_orm(column_type = "Text", nullable)]
-    pub library_id: Option<String>,
+    pub library_id: Option<Uuid>,
     pub reading_start_date: Option<Date>,
     pub reading_end_date: Option<Date>,
+    #[sea_orm(column_type = "Double", nullable)]
+    pub target_price: Option<f64>,
+    pub price_alert_at: Option<DateTime>,
+    #[sea_orm(column_type = "JsonBinary")]
+    pub custom_fields: Json,
+    #[sea_orm(column_type = "JsonBinary")]
+    pub metadata_overrides: Json,
+    pub custom_cover_id: Option<Uuid>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub custom_cover_blurhash: Option<String>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub custom_cover_dominant_color: Option<String>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub custom_cover_accent_color: Option<String>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
-pub enum Relation {}
+pub enum Relation {
+    #[sea_orm(has_many = "super::book_authors::Entity")]
+    BookAuthors,
+    #[sea_orm(has_many = "super::highlights::Entity")]
+    Highlights,
+    #[sea_orm(
+        belongs_to = "super::libraries::Entity",
+        from = "Column::LibraryId",
+        to = "super::libraries::Column::Id",
+        on_update = "NoAction",
+        on_delete = "SetNull"
+    )]
+    Libraries,
+    #[sea_orm(has_many = "super::loans::Entity")]
+    Loans,
+    #[sea_orm(has_one = "super::reviews::Entity")]
+    Reviews,
+    #[sea_orm(has_many = "super::series_books::Entity")]
+    SeriesBooks,
+}
+
+impl Related<super::book_authors::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::BookAuthors.def()
+    }
+}
+
+impl Related<super::highlights::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Highlights.def()
+    }
+}
+
+impl Related<super::libraries::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Libraries.def()
+    }
+}
+
+impl Related<super::loans::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Loans.def()
+    }
+}
+
+impl Related<super::reviews::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Reviews.def()
+    }
+}
+
+impl Related<super::series_books::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::SeriesBooks.def()
+    }
+}
 
 impl ActiveModelBehavior for ActiveModel {}
@@ -2,4 +2,23 @@
 
 pub mod prelude;
 
+pub mod authors;
+pub mod book_authors;
 pub mod books;
+pub mod covers;
+pub mod custom_fields;
+pub mod highlights;
+pub mod libraries;
+pub mod library_invites;
+pub mod library_members;
+pub mod loans;
+pub mod price_history;
+pub mod reviews;
+pub mod series;
+pub mod series_books;
+pub mod smart_shelves;
+pub mod tags;
+pub mod user_preferences;
+pub mod volume_authors;
+pub mod volumes;
+pub mod works;
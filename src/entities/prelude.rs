@@ -1,3 +1,22 @@
 //! `SeaORM` Entity, @generated by sea-orm-codegen 1.0.0
 #![allow(unused_imports)]
+pub use super::authors::Entity as Authors;
+pub use super::book_authors::Entity as BookAuthors;
 pub use super::books::Entity as Books;
+pub use super::covers::Entity as Covers;
+pub use super::custom_fields::Entity as CustomFields;
+pub use super::highlights::Entity as Highlights;
+pub use super::libraries::Entity as Libraries;
+pub use super::library_invites::Entity as LibraryInvites;
+pub use super::library_members::Entity as LibraryMembers;
+pub use super::loans::Entity as Loans;
+pub use super::price_history::Entity as PriceHistory;
+pub use super::reviews::Entity as Reviews;
+pub use super::series::Entity as Series;
+pub use super::series_books::Entity as SeriesBooks;
+pub use super::smart_shelves::Entity as SmartShelves;
+pub use super::tags::Entity as Tags;
+pub use super::user_preferences::Entity as UserPreferences;
+pub use super::volume_authors::Entity as VolumeAuthors;
+pub use super::volumes::Entity as Volumes;
+pub use super::works::Entity as Works;
@@ -23,6 +23,9 @@ pub enum Error {
     ParseError(String),
     MissingFields(String),
 
+    // Validation Error
+    InvalidField(String),
+
     // Database Error
     DbError(String),
 
@@ -58,6 +61,8 @@ impl Error {
             Self::ParseError(_) => (StatusCode::BAD_REQUEST, ClientError::BAD_REQUEST),
             Self::MissingFields(_) => (StatusCode::BAD_REQUEST, ClientError::BAD_REQUEST),
 
+            Self::InvalidField(_) => (StatusCode::BAD_REQUEST, ClientError::BAD_REQUEST),
+
             Self::DbError(_) => (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 ClientError::INTERNAL_SERVER_ERROR,
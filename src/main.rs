@@ -7,9 +7,16 @@ mod error;
 mod model;
 mod server;
 
-use api::routes::books::books_routes;
+use api::routes::{
+    authors::authors_routes, books::books_routes, covers::covers_routes,
+    custom_fields::custom_fields_routes, export::export_routes, highlights::highlights_routes,
+    import::import_routes, libraries::libraries_routes, loans::loans_routes,
+    manual_books::manual_books_routes, opds::opds_routes, preferences::preferences_routes,
+    reviews::reviews_routes, series::series_routes, smart_shelves::smart_shelves_routes,
+    tags::tags_routes, wishlist::wishlist_routes,
+};
 use axum::Router;
-use model::ModelManager;
+use model::{wishlist::spawn_price_tracker, ModelManager};
 use server::cors::set_cors;
 use tracing::info;
 use tracing_subscriber::EnvFilter;
@@ -30,12 +37,31 @@ async fn main() {
         }
     };
 
+    // Check the prices of the wishlists in the background
+    spawn_price_tracker(model_manager.clone());
+
     // Initialize Cors
     let cors = set_cors();
 
     // Initialize the routes
     let mirabel_routes: Router = Router::new()
         .nest("/api/v0/", books_routes(model_manager.clone()))
+        .nest("/api/v0/", libraries_routes(model_manager.clone()))
+        .nest("/api/v0/", import_routes(model_manager.clone()))
+        .nest("/api/v0/", export_routes(model_manager.clone()))
+        .nest("/api/v0/", opds_routes(model_manager.clone()))
+        .nest("/api/v0/", tags_routes(model_manager.clone()))
+        .nest("/api/v0/", smart_shelves_routes(model_manager.clone()))
+        .nest("/api/v0/", highlights_routes(model_manager.clone()))
+        .nest("/api/v0/", reviews_routes(model_manager.clone()))
+        .nest("/api/v0/", preferences_routes(model_manager.clone()))
+        .nest("/api/v0/", wishlist_routes(model_manager.clone()))
+        .nest("/api/v0/", loans_routes(model_manager.clone()))
+        .nest("/api/v0/", series_routes(model_manager.clone()))
+        .nest("/api/v0/", authors_routes(model_manager.clone()))
+        .nest("/api/v0/", custom_fields_routes(model_manager.clone()))
+        .nest("/api/v0/", manual_books_routes(model_manager.clone()))
+        .nest("/api/v0/", covers_routes(model_manager.clone()))
         .layer(cors);
 
     // Start the Axum server
@@ -0,0 +1,421 @@
+use std::collections::HashSet;
+
+use sea_orm::{
+    ColumnTrait, Condition, ConnectionTrait, DatabaseTransaction, DbBackend, EntityTrait,
+    FromQueryResult, QueryFilter, QuerySelect, Statement, TransactionTrait, Value,
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    entities::{
+        authors::{self, Model},
+        book_authors, books, volume_authors,
+    },
+    error::{Error, Result},
+};
+
+use super::{
+    books::{json_object, UserBooks},
+    metadata_overrides::FIELD_AUTHORS,
+    preferences::get_rating_scale,
+    ModelManager,
+};
+
+/// Number of authors returned by the most read authors, unless set with `?limit=`
+const DEFAULT_TOP_AUTHORS: u64 = 10;
+const MAX_TOP_AUTHORS: u64 = 100;
+
+/// Key grouping the variants of the name of an author: lowercase, with the
+/// punctuation and the spaces collapsed. Has to match the one of the migration
+pub fn author_key(name: &str) -> String {
+    let mut key = String::new();
+    let mut separator = false;
+    for c in name.chars() {
+        if c.is_alphanumeric() {
+            if separator && !key.is_empty() {
+                key.push(' ');
+            }
+            separator = false;
+            key.extend(c.to_lowercase());
+        } else {
+            separator = true;
+        }
+    }
+    key
+}
+
+// region - AuthorStats
+/// Reading stats of the user for the books of an author
+#[derive(Debug, Deserialize, Serialize, FromQueryResult)]
+#[serde(rename_all = "camelCase")]
+pub struct AuthorStats {
+    pub id: Uuid,
+    pub name: String,
+    pub variants: Vec<String>,
+    /// Works in the shelf, the editions of a work count once
+    pub books: i64,
+    pub read: i64,
+    pub reading: i64,
+    /// Pages of the books read
+    pub pages_read: i64,
+    /// In the scale preferred by the user
+    pub average_rating: Option<f64>,
+}
+
+impl AuthorStats {
+    /// Stats of an author without books in the shelf of the user
+    fn empty(author_db: Model) -> Self {
+        Self {
+            id: author_db.id,
+            name: author_db.name,
+            variants: author_db.variants,
+            books: 0,
+            read: 0,
+            reading: 0,
+            pages_read: 0,
+            average_rating: None,
+        }
+    }
+}
+// endregion - AuthorStats
+
+// region - TopAuthorsParams
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TopAuthorsParams {
+    pub limit: Option<u64>,
+}
+// endregion - TopAuthorsParams
+
+/// Links the cached volume to its authors
+pub async fn link_volume_authors(
+    model_manager: &ModelManager,
+    book_id: &str,
+    names: &[String],
+) -> Result<()> {
+    let txn = model_manager
+        .db()
+        .begin()
+        .await
+        .map_err(|e| Error::DbError(e.to_string()))?;
+
+    txn.execute(Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        "DELETE FROM volume_authors WHERE book_id = $1",
+        [book_id.into()],
+    ))
+    .await
+    .map_err(|e| Error::DbError(e.to_string()))?;
+
+    for (position, author_id) in save_authors(&txn, names).await? {
+        txn.execute(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            "INSERT INTO volume_authors (book_id, author_id, position) VALUES ($1, $2, $3)",
+            [
+                book_id.into(),
+                author_id.into(),
+                Value::from(position as i32),
+            ],
+        ))
+        .await
+        .map_err(|e| Error::DbError(e.to_string()))?;
+    }
+
+    txn.commit()
+        .await
+        .map_err(|e| Error::DbError(e.to_string()))?;
+
+    Ok(())
+}
+
+/// Links the book of the shelf to the authors overridden by the user, which
+/// replace the ones of its volume. Removes the links if they are not overridden
+pub async fn link_book_authors(model_manager: &ModelManager, book: &books::Model) -> Result<()> {
+    let names: Option<Vec<String>> = json_object(book.metadata_overrides.clone())
+        .remove(FIELD_AUTHORS)
+        .and_then(|authors| serde_json::from_value(authors).ok());
+
+    let txn = model_manager
+        .db()
+        .begin()
+        .await
+        .map_err(|e| Error::DbError(e.to_string()))?;
+
+    txn.execute(Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        "DELETE FROM book_authors WHERE book_id = $1",
+        [book.id.into()],
+    ))
+    .await
+    .map_err(|e| Error::DbError(e.to_string()))?;
+
+    if let Some(names) = names {
+        for (position, author_id) in save_authors(&txn, &names).await? {
+            txn.execute(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                "INSERT INTO book_authors (book_id, author_id, position) VALUES ($1, $2, $3)",
+                [
+                    book.id.into(),
+                    author_id.into(),
+                    Value::from(position as i32),
+                ],
+            ))
+            .await
+            .map_err(|e| Error::DbError(e.to_string()))?;
+        }
+    }
+
+    txn.commit()
+        .await
+        .map_err(|e| Error::DbError(e.to_string()))?;
+
+    Ok(())
+}
+
+/// Creates the authors of the names not known yet and adds the new variants of
+/// the names of the others. Returns the ids of the authors with the position of
+/// their name, starting at 1 like the ones linked by the migration
+async fn save_authors(txn: &DatabaseTransaction, names: &[String]) -> Result<Vec<(usize, Uuid)>> {
+    let mut authors = vec![];
+    let mut keys = HashSet::new();
+    for (position, name) in (1..).zip(names.iter().map(|n| n.trim())) {
+        let key = author_key(name);
+        // the same author written twice is linked once, at its first position
+        if key.is_empty() || !keys.insert(key.clone()) {
+            continue;
+        }
+
+        let author = txn
+            .query_one(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                r#"INSERT INTO authors (id, name, key, variants)
+                VALUES ($1, $2, $3, ARRAY[$2])
+                ON CONFLICT (key) DO UPDATE SET
+                    variants = CASE
+                        WHEN $2 = ANY(authors.variants) THEN authors.variants
+                        ELSE array_append(authors.variants, $2)
+                    END
+                RETURNING id"#,
+                [Uuid::new_v4().into(), name.into(), key.into()],
+            ))
+            .await
+            .map_err(|e| Error::DbError(e.to_string()))?
+            .ok_or(Error::InternalServerError)?;
+        let author_id: Uuid = author
+            .try_get("", "id")
+            .map_err(|e| Error::DbError(e.to_string()))?;
+        authors.push((position, author_id));
+    }
+
+    Ok(authors)
+}
+
+/// Returns the author with the given id. Authors are shared by all the users
+pub async fn get_author(model_manager: &ModelManager, author_id: &str) -> Result<Model> {
+    let author_id =
+        Uuid::parse_str(author_id).map_err(|_| Error::ParseError("Invalid id".to_string()))?;
+
+    authors::Entity::find_by_id(author_id)
+        .one(model_manager.db())
+        .await
+        .map_err(|e| Error::DbError(e.to_string()))?
+        .ok_or(Error::NotFound)
+}
+
+/// Returns the stats of the authors of the user's books, sorted by name
+pub async fn get_user_authors(
+    model_manager: &ModelManager,
+    user_id: &str,
+) -> Result<Vec<AuthorStats>> {
+    query_authors_stats(model_manager, user_id, "", "ORDER BY a.name", vec![]).await
+}
+
+/// Returns the authors with the most books read by the user
+pub async fn get_top_authors(
+    model_manager: &ModelManager,
+    user_id: &str,
+    params: &TopAuthorsParams,
+) -> Result<Vec<AuthorStats>> {
+    let limit = params
+        .limit
+        .unwrap_or(DEFAULT_TOP_AUTHORS)
+        .clamp(1, MAX_TOP_AUTHORS) as i64;
+
+    query_authors_stats(
+        model_manager,
+        user_id,
+        "",
+        r#"HAVING COUNT(*) FILTER (WHERE b.reading_status = 'read') > 0
+        ORDER BY read DESC, books DESC, a.name
+        LIMIT $2"#,
+        vec![limit.into()],
+    )
+    .await
+}
+
+/// Returns the stats of the user for the books of the author
+pub async fn get_author_stats(
+    model_manager: &ModelManager,
+    user_id: &str,
+    author_db: Model,
+) -> Result<AuthorStats> {
+    let stats = query_authors_stats(
+        model_manager,
+        user_id,
+        "AND a.id = $2",
+        "",
+        vec![author_db.id.into()],
+    )
+    .await?
+    .pop();
+
+    Ok(stats.unwrap_or_else(|| AuthorStats::empty(author_db)))
+}
+
+/// Groups the works of the user by author. The condition is added to the WHERE
+/// clause and the grouping after the GROUP BY, their values start at `$2`
+async fn query_authors_stats(
+    model_manager: &ModelManager,
+    user_id: &str,
+    condition: &str,
+    grouping: &str,
+    values: Vec<Value>,
+) -> Result<Vec<AuthorStats>> {
+    let sql = format!(
+        r#"WITH user_works AS (
+            -- the editions of a work count once, with the furthest reading status
+            SELECT DISTINCT ON (COALESCE(v.work_id::TEXT, b.book_id))
+                b.id, b.book_id, b.reading_status, b.rating,
+                -- the authors set by the user replace the ones of the volume
+                b.metadata_overrides ? 'authors' AS authors_overridden,
+                -- the page count set by the user wins over the external API one
+                COALESCE(b.metadata_overrides -> 'pageCount', v.data -> 'volumeInfo' -> 'pageCount')
+                    AS page_count
+            FROM books b
+            JOIN volumes v ON v.book_id = b.book_id
+            WHERE b.user_id = $1
+            ORDER BY COALESCE(v.work_id::TEXT, b.book_id),
+                b.reading_status = 'read' DESC,
+                b.reading_status = 'reading' DESC,
+                b.rating DESC NULLS LAST
+        )
+        SELECT a.id, a.name, a.variants,
+            COUNT(*) AS books,
+            COUNT(*) FILTER (WHERE b.reading_status = 'read') AS read,
+            COUNT(*) FILTER (WHERE b.reading_status = 'reading') AS reading,
+            COALESCE(SUM(
+                CASE WHEN jsonb_typeof(b.page_count) = 'number'
+                    THEN (b.page_count::TEXT)::BIGINT
+                END
+            ) FILTER (WHERE b.reading_status = 'read'), 0)::BIGINT AS pages_read,
+            AVG(b.rating) AS average_rating
+        FROM user_works b
+        JOIN LATERAL (
+            SELECT ba.author_id FROM book_authors ba WHERE ba.book_id = b.id
+            UNION ALL
+            SELECT va.author_id FROM volume_authors va
+            WHERE va.book_id = b.book_id AND NOT b.authors_overridden
+        ) ba ON TRUE
+        JOIN authors a ON a.id = ba.author_id
+        WHERE TRUE {}
+        GROUP BY a.id
+        {}"#,
+        condition, grouping
+    );
+    let mut statement_values: Vec<Value> = vec![user_id.into()];
+    statement_values.extend(values);
+
+    let mut authors = AuthorStats::find_by_statement(Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        sql,
+        statement_values,
+    ))
+    .all(model_manager.db())
+    .await
+    .map_err(|e| Error::DbError(e.to_string()))?;
+
+    // ratings are stored in the canonical scale
+    let rating_scale = get_rating_scale(model_manager, user_id).await?;
+    for author in authors.iter_mut() {
+        author.average_rating = author
+            .average_rating
+            .map(|r| rating_scale.display_rating(r));
+    }
+
+    Ok(authors)
+}
+
+/// Returns the books of the user written by the author, according to the
+/// authors overridden by the user or else to the ones of their volume
+pub async fn get_author_books(
+    model_manager: &ModelManager,
+    user_id: &str,
+    author_id: Uuid,
+) -> Result<UserBooks> {
+    let volume_ids: Vec<String> = volume_authors::Entity::find()
+        .select_only()
+        .column(volume_authors::Column::BookId)
+        .filter(volume_authors::Column::AuthorId.eq(author_id))
+        .into_tuple()
+        .all(model_manager.db())
+        .await
+        .map_err(|e| Error::DbError(e.to_string()))?;
+    let book_ids: Vec<Uuid> = book_authors::Entity::find()
+        .select_only()
+        .column(book_authors::Column::BookId)
+        .filter(book_authors::Column::AuthorId.eq(author_id))
+        .into_tuple()
+        .all(model_manager.db())
+        .await
+        .map_err(|e| Error::DbError(e.to_string()))?;
+
+    let books = books::Entity::find()
+        .filter(books::Column::UserId.eq(user_id))
+        .filter(
+            Condition::any()
+                .add(books::Column::BookId.is_in(volume_ids.clone()))
+                .add(books::Column::Id.is_in(book_ids.clone())),
+        )
+        .all(model_manager.db())
+        .await
+        .map_err(|e| Error::DbError(e.to_string()))?
+        .into_iter()
+        .filter(|book| {
+            match json_object(book.metadata_overrides.clone()).contains_key(FIELD_AUTHORS) {
+                true => book_ids.contains(&book.id),
+                false => volume_ids.contains(&book.book_id),
+            }
+        })
+        .collect();
+
+    UserBooks::from_db(model_manager, user_id.to_string(), books).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn author_key_ignores_case_and_punctuation() {
+        assert_eq!(author_key("J.R.R. Tolkien"), "j r r tolkien");
+        assert_eq!(author_key("J. R. R. Tolkien"), "j r r tolkien");
+        assert_eq!(author_key("  URSULA  K. LE GUIN "), "ursula k le guin");
+        assert_eq!(author_key("Le Guin, Ursula K."), "le guin ursula k");
+    }
+
+    #[test]
+    fn author_key_keeps_accents_and_non_latin_names() {
+        assert_eq!(
+            author_key("Gabriel García Márquez"),
+            "gabriel garcía márquez"
+        );
+        assert_eq!(author_key("村上 春樹"), "村上 春樹");
+    }
+
+    #[test]
+    fn author_key_of_blank_name_is_empty() {
+        assert_eq!(author_key(""), "");
+        assert_eq!(author_key(" .-, "), "");
+    }
+}
@@ -1,6 +1,7 @@
 use chrono::{NaiveDate, Utc};
 use sea_orm::ActiveValue;
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
 use uuid::Uuid;
 
 use crate::{
@@ -8,7 +9,31 @@ use crate::{
     error::{Error, Result},
 };
 
-use super::books_api::BooksApiResponse;
+use super::{
+    books_api::BooksApiResponse,
+    covers::{cover_url, get_cover_palettes, CoverPalette},
+    libraries::parse_library_id,
+    metadata_overrides::{apply_metadata_overrides, validate_metadata_overrides, FIELD_COVER},
+    preferences::get_rating_scale,
+    ratings::{normalize_rating, RatingScale},
+    reviews::{get_books_reviews, Review},
+    volumes::get_volume,
+    ModelManager,
+};
+
+// region - ReadingStatus
+pub const READING_STATUS_READ: &str = "read";
+pub const READING_STATUS_READING: &str = "reading";
+pub const READING_STATUS_TO_READ: &str = "to-read";
+pub const READING_STATUS_DID_NOT_FINISH: &str = "did-not-finish";
+pub const READING_STATUS_WISHLIST: &str = "wishlist";
+// endregion - ReadingStatus
+
+// region - BookType
+pub const BOOK_TYPE_PHYSICAL: &str = "physical";
+pub const BOOK_TYPE_EBOOK: &str = "ebook";
+pub const BOOK_TYPE_AUDIOBOOK: &str = "audiobook";
+// endregion - BookType
 
 // region - BookToSave
 #[derive(Debug, Deserialize, Serialize)]
@@ -31,10 +56,16 @@ pub struct BookToSave {
     pub rating: Option<f32>,
     pub notes: Option<String>,
     pub library_id: Option<String>,
+    /// Price under which the book of the wishlist is flagged
+    pub target_price: Option<f64>,
+    /// Values of the custom fields of the user, by key
+    pub custom_fields: Option<Map<String, Value>>,
+    /// Metadata replacing the one of the external API, by field of BookFull
+    pub metadata_overrides: Option<Map<String, Value>>,
 }
 
 impl BookToSave {
-    pub fn to_active_model(&self) -> ActiveModel {
+    pub fn to_active_model(&self) -> Result<ActiveModel> {
         let mut book_to_save = ActiveModel {
             id: ActiveValue::Set(Uuid::new_v4()),
             created_at: ActiveValue::set(Some(Utc::now().naive_utc())),
@@ -60,16 +91,31 @@ impl BookToSave {
             book_to_save.tags = ActiveValue::Set(Some(tags));
         };
         if let Some(rating) = self.rating {
-            book_to_save.rating = ActiveValue::Set(Some(rating as f64));
+            book_to_save.rating = ActiveValue::Set(Some(normalize_rating(rating as f64)?));
         };
         if let Some(notes) = self.notes.clone() {
             book_to_save.notes = ActiveValue::Set(Some(notes));
         };
-        if let Some(library_id) = self.library_id.clone() {
-            book_to_save.library_id = ActiveValue::Set(Some(library_id));
+        if let Some(library_id) = self.library_id.as_deref().filter(|l| !l.is_empty()) {
+            book_to_save.library_id = ActiveValue::Set(Some(parse_library_id(library_id)?));
+        };
+        if let Some(target_price) = self.target_price {
+            book_to_save.target_price = ActiveValue::Set(parse_target_price(target_price)?);
+        };
+        if let Some(custom_fields) = self.custom_fields.clone() {
+            // null values are not stored
+            let custom_fields = custom_fields.into_iter().filter(|(_, v)| !v.is_null());
+            book_to_save.custom_fields = ActiveValue::Set(Value::Object(custom_fields.collect()));
+        };
+        if let Some(overrides) = self.metadata_overrides.as_ref() {
+            // null values are not stored
+            let overrides = validate_metadata_overrides(overrides)?
+                .into_iter()
+                .filter(|(_, v)| !v.is_null());
+            book_to_save.metadata_overrides = ActiveValue::Set(Value::Object(overrides.collect()));
         };
 
-        book_to_save
+        Ok(book_to_save)
     }
 }
 // endregion - BookToSave
@@ -86,12 +132,21 @@ pub struct BookToUpdate {
     pub rating: Option<f32>,
     pub notes: Option<String>,
     pub library_id: Option<String>,
+    /// 0 removes the target price
+    pub target_price: Option<f64>,
+    /// Values of the custom fields to change, null removes a value
+    pub custom_fields: Option<Map<String, Value>>,
+    /// Metadata fields to override, null resets a field to the external API one
+    pub metadata_overrides: Option<Map<String, Value>>,
 }
 
 impl BookToUpdate {
-    pub fn to_active_model(&self, db_book: Option<Model>) -> ActiveModel {
+    pub fn to_active_model(&self, db_book: Option<Model>) -> Result<ActiveModel> {
+        let db_book = db_book.unwrap();
+        let mut custom_fields = json_object(db_book.custom_fields.clone());
+        let mut metadata_overrides = json_object(db_book.metadata_overrides.clone());
         // transofrm the book into an ActiveModel so it can be updated
-        let mut book_to_update: ActiveModel = db_book.unwrap().into();
+        let mut book_to_update: ActiveModel = db_book.into();
 
         book_to_update.updated_at = ActiveValue::set(Some(Utc::now().naive_utc()));
         // check if the optional fields are set and update the active model accordingly
@@ -111,20 +166,66 @@ impl BookToUpdate {
             book_to_update.tags = ActiveValue::Set(Some(tags));
         };
         if let Some(rating) = self.rating {
-            book_to_update.rating = ActiveValue::Set(Some(rating as f64));
+            book_to_update.rating = ActiveValue::Set(Some(normalize_rating(rating as f64)?));
         };
         if let Some(notes) = self.notes.clone() {
             book_to_update.notes = ActiveValue::Set(Some(notes));
         };
-        if let Some(library_id) = self.library_id.clone() {
-            book_to_update.library_id = ActiveValue::Set(Some(library_id));
+        // an empty library id removes the book from its library
+        if let Some(library_id) = self.library_id.as_deref() {
+            book_to_update.library_id = match library_id {
+                "" => ActiveValue::Set(None),
+                library_id => ActiveValue::Set(Some(parse_library_id(library_id)?)),
+            };
+        };
+        // the alert is checked again with the new target
+        if let Some(target_price) = self.target_price {
+            book_to_update.target_price = ActiveValue::Set(parse_target_price(target_price)?);
+            book_to_update.price_alert_at = ActiveValue::Set(None);
+        };
+        if let Some(values) = self.custom_fields.clone() {
+            for (key, value) in values {
+                if value.is_null() {
+                    custom_fields.remove(&key);
+                } else {
+                    custom_fields.insert(key, value);
+                }
+            }
+            book_to_update.custom_fields = ActiveValue::Set(Value::Object(custom_fields));
+        };
+        if let Some(overrides) = self.metadata_overrides.as_ref() {
+            for (field, value) in validate_metadata_overrides(overrides)? {
+                if value.is_null() {
+                    metadata_overrides.remove(&field);
+                } else {
+                    metadata_overrides.insert(field, value);
+                }
+            }
+            book_to_update.metadata_overrides = ActiveValue::Set(Value::Object(metadata_overrides));
         };
 
-        book_to_update
+        Ok(book_to_update)
     }
 }
 // endregion - BookToUpdate
 
+/// Returns the object stored in a JSONB column, like the custom fields of a book
+pub fn json_object(value: Value) -> Map<String, Value> {
+    match value {
+        Value::Object(object) => object,
+        _ => Map::new(),
+    }
+}
+
+fn parse_target_price(target_price: f64) -> Result<Option<f64>> {
+    if target_price < 0.0 {
+        return Err(Error::InvalidField(
+            "targetPrice cannot be negative".to_string(),
+        ));
+    }
+    Ok(Some(target_price).filter(|p| *p > 0.0))
+}
+
 // region - BookFull
 #[derive(Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -144,6 +245,17 @@ pub struct BookFull {
     pub categories: Vec<String>,
     pub language: String,
     pub cover: String,
+    /// Url of the cover served by the API, empty if the book has no image.
+    /// A cover overridden by the user is not cached, its url is used instead,
+    /// unless the user uploaded one
+    pub local_cover: String,
+    /// Placeholder and colors of the local cover, once the image is cached
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cover_palette: Option<CoverPalette>,
+    /// Page of the external API to preview the book, empty if there is none
+    pub preview_link: String,
+    /// Page of the external API to buy the book, empty if it is not for sale
+    pub buy_link: String,
     pub reading_status: String,
     pub reading_start_date: NaiveDate,
     pub reading_end_date: NaiveDate,
@@ -152,6 +264,15 @@ pub struct BookFull {
     pub rating: f32,
     pub notes: String,
     pub library_id: String,
+    pub target_price: f64,
+    /// The retail price dropped below the target price
+    pub price_alert: bool,
+    /// Values of the custom fields of the user that saved the book, by key
+    pub custom_fields: Map<String, Value>,
+    /// Metadata fields set by the user instead of the external API
+    pub overridden_fields: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub review: Option<Review>,
 }
 
 impl Default for BookFull {
@@ -171,6 +292,10 @@ impl Default for BookFull {
             categories: vec![],
             language: "".to_string(),
             cover: "".to_string(),
+            local_cover: "".to_string(),
+            cover_palette: None,
+            preview_link: "".to_string(),
+            buy_link: "".to_string(),
             reading_status: "".to_string(),
             reading_start_date: NaiveDate::from_ymd_opt(1970, 1, 1).unwrap(),
             reading_end_date: NaiveDate::from_ymd_opt(1970, 1, 1).unwrap(),
@@ -179,15 +304,52 @@ impl Default for BookFull {
             rating: 0.0,
             notes: "".to_string(),
             library_id: "".to_string(),
+            target_price: 0.0,
+            price_alert: false,
+            custom_fields: Map::new(),
+            overridden_fields: vec![],
+            review: None,
         }
     }
 }
 
+#[allow(dead_code)]
 impl BookFull {
     pub fn new() -> Self {
         Self::default()
     }
 
+    /// Builds the book with only the fields stored in the database, used when the
+    /// external API is not reachable
+    pub fn from_db(book_db: Model) -> Self {
+        let overrides = json_object(book_db.metadata_overrides.clone());
+        // the cover of the volume is unknown without the external API
+        let local_cover = cover_url(&book_db.book_id, book_db.custom_cover_id, false);
+        let mut book = Self {
+            id: book_db.id.to_string(),
+            book_id: book_db.book_id,
+            user_id: book_db.user_id,
+            reading_status: book_db.reading_status.unwrap_or_default(),
+            reading_start_date: book_db.reading_start_date.unwrap_or_default(),
+            reading_end_date: book_db.reading_end_date.unwrap_or_default(),
+            book_type: book_db.book_type.unwrap_or_default(),
+            tags: book_db.tags.unwrap_or_default(),
+            rating: book_db.rating.unwrap_or_default() as f32,
+            notes: book_db.notes.unwrap_or_default(),
+            library_id: book_db
+                .library_id
+                .map(|id| id.to_string())
+                .unwrap_or_default(),
+            target_price: book_db.target_price.unwrap_or_default(),
+            price_alert: book_db.price_alert_at.is_some(),
+            local_cover,
+            custom_fields: json_object(book_db.custom_fields),
+            ..Default::default()
+        };
+        book.apply_overrides(&overrides, book_db.custom_cover_id);
+        book
+    }
+
     pub fn from_db_and_api(book_db: Model, book_api_response: BooksApiResponse) -> Result<Self> {
         // check that at least the id is set or return error
         let book_id = if let Some(book_id) = book_api_response.get_id() {
@@ -196,7 +358,13 @@ impl BookFull {
             return Err(Error::MissingFields("missing fields: book_id".to_string()));
         };
 
-        Ok(Self {
+        let overrides = json_object(book_db.metadata_overrides.clone());
+        let local_cover = cover_url(
+            &book_id,
+            book_db.custom_cover_id,
+            !book_api_response.get_cover().is_empty(),
+        );
+        let mut book = Self {
             id: book_db.id.to_string(),
             book_id,
             user_id: book_db.user_id,
@@ -211,6 +379,10 @@ impl BookFull {
             categories: book_api_response.get_categories(),
             language: book_api_response.get_language(),
             cover: book_api_response.get_cover(),
+            local_cover,
+            cover_palette: None,
+            preview_link: book_api_response.get_preview_link(),
+            buy_link: book_api_response.get_buy_link(),
             reading_status: book_db.reading_status.unwrap_or_default(),
             reading_start_date: book_db.reading_start_date.unwrap_or_default(),
             reading_end_date: book_db.reading_end_date.unwrap_or_default(),
@@ -218,8 +390,42 @@ impl BookFull {
             tags: book_db.tags.unwrap_or_default(),
             rating: book_db.rating.unwrap_or_default() as f32,
             notes: book_db.notes.unwrap_or_default(),
-            library_id: book_db.library_id.unwrap_or_default(),
-        })
+            library_id: book_db
+                .library_id
+                .map(|id| id.to_string())
+                .unwrap_or_default(),
+            target_price: book_db.target_price.unwrap_or_default(),
+            price_alert: book_db.price_alert_at.is_some(),
+            custom_fields: json_object(book_db.custom_fields),
+            overridden_fields: vec![],
+            review: None,
+        };
+        book.apply_overrides(&overrides, book_db.custom_cover_id);
+        Ok(book)
+    }
+
+    fn apply_overrides(&mut self, overrides: &Map<String, Value>, custom_cover_id: Option<Uuid>) {
+        apply_metadata_overrides(self, overrides);
+        if custom_cover_id.is_none() && overrides.contains_key(FIELD_COVER) {
+            self.local_cover = self.cover.clone();
+        }
+    }
+
+    pub fn set_review(&mut self, review: Option<Review>) {
+        self.review = review;
+    }
+
+    pub fn set_cover_palette(&mut self, cover_palette: Option<CoverPalette>) {
+        self.cover_palette = cover_palette;
+    }
+
+    /// Converts the rating and the ones of the review from the canonical scale
+    /// to the given one
+    pub fn convert_rating(&mut self, rating_scale: RatingScale) {
+        self.rating = rating_scale.display_rating(self.rating as f64) as f32;
+        if let Some(review) = self.review.as_mut() {
+            review.convert_ratings(rating_scale);
+        }
     }
 }
 // endregion - BookFull
@@ -229,6 +435,8 @@ impl BookFull {
 #[serde(rename_all = "camelCase")]
 pub struct UserBooks {
     user_id: String,
+    /// Scale of the ratings of the books
+    rating_scale: RatingScale,
     books: Vec<BookFull>,
 }
 
@@ -236,11 +444,13 @@ impl Default for UserBooks {
     fn default() -> Self {
         Self {
             user_id: "".to_string(),
+            rating_scale: RatingScale::default(),
             books: vec![],
         }
     }
 }
 
+#[allow(dead_code)]
 impl UserBooks {
     pub fn new() -> Self {
         Self::default()
@@ -249,8 +459,39 @@ impl UserBooks {
     pub fn from_user_id(user_id: String) -> Self {
         Self {
             user_id,
-            books: vec![],
+            ..Default::default()
+        }
+    }
+
+    /// Gets the full book info from the volumes cache for every book, with the
+    /// reviews visible to the user and the ratings in the scale preferred by the user
+    pub async fn from_db(
+        model_manager: &ModelManager,
+        user_id: String,
+        books: Vec<Model>,
+    ) -> Result<Self> {
+        let mut reviews = get_books_reviews(
+            model_manager,
+            &user_id,
+            books.iter().map(|b| b.id).collect(),
+        )
+        .await?;
+        let mut palettes = get_cover_palettes(model_manager, &books).await?;
+
+        let mut user_books = Self::from_user_id(user_id);
+        user_books.rating_scale = get_rating_scale(model_manager, &user_books.user_id).await?;
+        for book in books {
+            let review = reviews.remove(&book.id);
+            let palette = palettes.remove(&book.id);
+            let book_api = get_volume(model_manager, &book.book_id).await?;
+            let mut book_full = BookFull::from_db_and_api(book, book_api)?;
+            book_full.set_review(review);
+            book_full.set_cover_palette(palette);
+            book_full.convert_rating(user_books.rating_scale);
+            user_books.add_book(book_full);
         }
+
+        Ok(user_books)
     }
 
     pub fn get_user_id(&self) -> &str {
@@ -268,5 +509,10 @@ impl UserBooks {
     pub fn add_book(&mut self, book: BookFull) {
         self.books.push(book);
     }
+
+    /// Keeps only the books matching the predicate
+    pub fn retain_books(&mut self, f: impl FnMut(&BookFull) -> bool) {
+        self.books.retain(f);
+    }
 }
 // endregion - UserBooks
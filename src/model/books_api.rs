@@ -1,5 +1,74 @@
 use serde::{Deserialize, Serialize};
 
+use crate::error::{Error, Result};
+
+// region - External API
+/// Returns the base url and the key of the external books API
+fn external_books_api() -> Result<(String, String)> {
+    let external_books_api_url = match std::env::var("EXTERNAL_BOOKS_API_URL") {
+        Ok(external_books_api_url) => external_books_api_url,
+        Err(_) => {
+            return Err(Error::MissingEnvVar(
+                "missing env var: EXTERNAL_BOOKS_API_URL".to_string(),
+            ));
+        }
+    };
+    let external_books_api_key = match std::env::var("EXTERNAL_BOOKS_API_KEY") {
+        Ok(external_books_api_key) => external_books_api_key,
+        Err(_) => {
+            return Err(Error::MissingEnvVar(
+                "missing env var: EXTERNAL_BOOKS_API_KEY".to_string(),
+            ));
+        }
+    };
+
+    Ok((external_books_api_url, external_books_api_key))
+}
+
+/// Fetches a single volume from the external books API
+pub async fn fetch_volume(book_id: &str) -> Result<BooksApiResponse> {
+    let (external_books_api_url, external_books_api_key) = external_books_api()?;
+    let url = external_books_api_url + "/" + book_id + "?key=" + &external_books_api_key;
+
+    let res = match reqwest::get(&url).await {
+        Ok(res) => res,
+        Err(e) => return Err(Error::ExternalApiError(e.to_string())),
+    };
+
+    match res.json::<BooksApiResponse>().await {
+        Ok(book_api) => Ok(book_api),
+        Err(e) => Err(Error::ParseError(e.to_string())),
+    }
+}
+
+/// Searches the external books API for a volume with the given ISBN (10 or 13).
+/// Returns None if no volume matches
+pub async fn search_volume_by_isbn(isbn: &str) -> Result<Option<BooksApiResponse>> {
+    let (external_books_api_url, external_books_api_key) = external_books_api()?;
+    let url = external_books_api_url + "?q=isbn:" + isbn + "&key=" + &external_books_api_key;
+
+    let res = match reqwest::get(&url).await {
+        Ok(res) => res,
+        Err(e) => return Err(Error::ExternalApiError(e.to_string())),
+    };
+
+    match res.json::<BooksApiSearchResponse>().await {
+        Ok(search) => Ok(search.items.unwrap_or_default().into_iter().next()),
+        Err(e) => Err(Error::ParseError(e.to_string())),
+    }
+}
+// endregion - External API
+
+// region - BooksApiSearchResponse
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BooksApiSearchResponse {
+    pub kind: Option<String>,
+    pub total_items: Option<i64>,
+    pub items: Option<Vec<BooksApiResponse>>,
+}
+// endregion - BooksApiSearchResponse
+
 // region - BooksApiResponse
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -168,6 +237,34 @@ impl BooksApiResponse {
             None => "".to_string(),
         }
     }
+
+    pub fn get_preview_link(&self) -> String {
+        match &self.volume_info {
+            Some(volume_info) => match &volume_info.preview_link {
+                Some(preview_link) => preview_link.clone(),
+                None => "".to_string(),
+            },
+            None => "".to_string(),
+        }
+    }
+
+    pub fn get_buy_link(&self) -> String {
+        match &self.sale_info {
+            Some(sale_info) => match &sale_info.buy_link {
+                Some(buy_link) => buy_link.clone(),
+                None => "".to_string(),
+            },
+            None => "".to_string(),
+        }
+    }
+
+    pub fn get_list_price(&self) -> Option<ListPrice> {
+        self.sale_info.as_ref()?.list_price.clone()
+    }
+
+    pub fn get_retail_price(&self) -> Option<RetailPrice> {
+        self.sale_info.as_ref()?.retail_price.clone()
+    }
 }
 // endregion - BooksApiResponse
 
@@ -0,0 +1,212 @@
+use serde_json::{json, Value};
+
+use super::books::BookFull;
+
+// region - CitationFormat
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CitationFormat {
+    Bibtex,
+    Ris,
+    JsonLd,
+}
+
+impl CitationFormat {
+    /// Returns the first citation format requested in the Accept header, if any
+    pub fn from_accept(accept: &str) -> Option<Self> {
+        accept
+            .split(',')
+            .map(|media_type| media_type.split(';').next().unwrap_or("").trim())
+            .find_map(|media_type| match media_type {
+                "application/x-bibtex" | "text/x-bibtex" => Some(Self::Bibtex),
+                "application/x-research-info-systems" => Some(Self::Ris),
+                "application/ld+json" => Some(Self::JsonLd),
+                _ => None,
+            })
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Self::Bibtex => "application/x-bibtex; charset=utf-8",
+            Self::Ris => "application/x-research-info-systems; charset=utf-8",
+            Self::JsonLd => "application/ld+json",
+        }
+    }
+
+    pub fn render_book(&self, book: &BookFull) -> String {
+        match self {
+            Self::Bibtex => to_bibtex(book),
+            Self::Ris => to_ris(book),
+            Self::JsonLd => to_json_ld(book).to_string(),
+        }
+    }
+
+    /// Renders the books as a single document of the format
+    pub fn render(&self, books: &[BookFull]) -> String {
+        match self {
+            Self::Bibtex => books.iter().map(to_bibtex).collect::<Vec<_>>().join("\n"),
+            Self::Ris => books.iter().map(to_ris).collect(),
+            Self::JsonLd => json!({
+                "@context": "https://schema.org",
+                "@graph": books.iter().map(to_json_ld).collect::<Vec<Value>>(),
+            })
+            .to_string(),
+        }
+    }
+}
+// endregion - CitationFormat
+
+// region - BibTeX
+fn to_bibtex(book: &BookFull) -> String {
+    let mut fields = vec![("title", book.title.clone())];
+    if !book.authors.is_empty() {
+        fields.push(("author", book.authors.join(" and ")));
+    }
+    if !book.publisher.is_empty() {
+        fields.push(("publisher", book.publisher.clone()));
+    }
+    let year = year(&book.published_date);
+    if !year.is_empty() {
+        fields.push(("year", year.clone()));
+    }
+    if !book.isbn13.is_empty() {
+        fields.push(("isbn", book.isbn13.clone()));
+    } else if !book.isbn10.is_empty() {
+        fields.push(("isbn", book.isbn10.clone()));
+    }
+    if book.page_count > 0 {
+        fields.push(("pages", book.page_count.to_string()));
+    }
+    if !book.language.is_empty() {
+        fields.push(("language", book.language.clone()));
+    }
+
+    let fields = fields
+        .iter()
+        .map(|(name, value)| format!("  {} = {{{}}}", name, escape_bibtex(value)))
+        .collect::<Vec<String>>()
+        .join(",\n");
+
+    format!("@book{{{},\n{}\n}}\n", bibtex_key(book, &year), fields)
+}
+
+/// Key like `herbert1965dune`, falling back to the book id
+fn bibtex_key(book: &BookFull, year: &str) -> String {
+    let last_name = book
+        .authors
+        .first()
+        .and_then(|author| author.split_whitespace().last())
+        .unwrap_or("");
+    let first_word = book.title.split_whitespace().next().unwrap_or("");
+
+    let key: String = format!("{}{}{}", last_name, year, first_word)
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .collect::<String>()
+        .to_lowercase();
+
+    if key.is_empty() {
+        book.book_id.clone()
+    } else {
+        key
+    }
+}
+
+fn escape_bibtex(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' | '%' | '$' | '#' | '_' | '{' | '}' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+// endregion - BibTeX
+
+// region - RIS
+fn to_ris(book: &BookFull) -> String {
+    let mut lines = vec![ris_line("TY", "BOOK"), ris_line("TI", &book.title)];
+    for author in &book.authors {
+        lines.push(ris_line("AU", author));
+    }
+    if !book.publisher.is_empty() {
+        lines.push(ris_line("PB", &book.publisher));
+    }
+    let year = year(&book.published_date);
+    if !year.is_empty() {
+        lines.push(ris_line("PY", &year));
+        lines.push(ris_line("DA", &book.published_date.replace('-', "/")));
+    }
+    if !book.isbn13.is_empty() {
+        lines.push(ris_line("SN", &book.isbn13));
+    } else if !book.isbn10.is_empty() {
+        lines.push(ris_line("SN", &book.isbn10));
+    }
+    if !book.language.is_empty() {
+        lines.push(ris_line("LA", &book.language));
+    }
+    for category in &book.categories {
+        lines.push(ris_line("KW", category));
+    }
+    lines.push(ris_line("ER", ""));
+
+    lines.concat()
+}
+
+fn ris_line(tag: &str, value: &str) -> String {
+    // RIS values cannot span multiple lines
+    format!("{}  - {}\r\n", tag, value.replace(['\r', '\n'], " "))
+}
+// endregion - RIS
+
+// region - JSON-LD
+fn to_json_ld(book: &BookFull) -> Value {
+    let mut json_ld = json!({
+        "@context": "https://schema.org",
+        "@type": "Book",
+        "@id": format!("urn:mirabel:book:{}", book.id),
+        "name": book.title,
+        "author": book
+            .authors
+            .iter()
+            .map(|author| json!({ "@type": "Person", "name": author }))
+            .collect::<Vec<Value>>(),
+    });
+    if !book.publisher.is_empty() {
+        json_ld["publisher"] = json!({ "@type": "Organization", "name": book.publisher });
+    }
+    if !book.published_date.is_empty() {
+        json_ld["datePublished"] = json!(book.published_date);
+    }
+    if !book.isbn13.is_empty() {
+        json_ld["isbn"] = json!(book.isbn13);
+    } else if !book.isbn10.is_empty() {
+        json_ld["isbn"] = json!(book.isbn10);
+    }
+    if book.page_count > 0 {
+        json_ld["numberOfPages"] = json!(book.page_count);
+    }
+    if !book.language.is_empty() {
+        json_ld["inLanguage"] = json!(book.language);
+    }
+    if !book.categories.is_empty() {
+        json_ld["genre"] = json!(book.categories);
+    }
+    if !book.description.is_empty() {
+        json_ld["description"] = json!(book.description);
+    }
+    if !book.cover.is_empty() {
+        json_ld["image"] = json!(book.cover);
+    }
+
+    json_ld
+}
+// endregion - JSON-LD
+
+/// The external API returns dates as `YYYY`, `YYYY-MM` or `YYYY-MM-DD`
+fn year(published_date: &str) -> String {
+    published_date.chars().take(4).collect()
+}
@@ -0,0 +1,674 @@
+use std::{
+    cmp::Reverse,
+    collections::{BTreeMap, HashMap},
+    net::{IpAddr, SocketAddr},
+    path::PathBuf,
+    time::Duration,
+};
+
+use chrono::Utc;
+use image::{codecs::jpeg::JpegEncoder, imageops::FilterType, DynamicImage};
+use reqwest::{redirect::Policy, Url};
+use sea_orm::{
+    sea_query::OnConflict, ActiveModelTrait, ActiveValue, ColumnTrait, EntityTrait, QueryFilter,
+};
+use serde::{Deserialize, Serialize};
+use tracing::error as tracing_error;
+use uuid::Uuid;
+
+use crate::{
+    entities::{books, covers},
+    error::{Error, Result},
+};
+
+use super::{
+    books_api::BooksApiResponse, metadata_overrides::FIELD_COVER, volumes::get_volume, ModelManager,
+};
+
+/// Directory of the covers, unless set with COVERS_DIR
+const DEFAULT_COVERS_DIR: &str = "covers";
+
+const JPEG_QUALITY: u8 = 85;
+const WEBP_QUALITY: f32 = 80.0;
+
+/// Largest image downloaded for a cover, in bytes
+const MAX_COVER_BYTES: usize = 10 * 1024 * 1024;
+const DOWNLOAD_TIMEOUT_SECS: u64 = 30;
+
+/// Path of the covers endpoint, the sizes are appended to it
+const COVERS_PATH: &str = "/api/v0/covers";
+
+// region - CoverSize
+/// Widths of the sizes generated for every cover, smaller images are not enlarged
+const COVER_SIZES: [(&str, u32); 3] = [("small", 128), ("medium", 320), ("large", 640)];
+
+/// Size used in the urls of the covers of BookFull
+const DEFAULT_COVER_SIZE: &str = "medium";
+// endregion - CoverSize
+
+/// Width and height of the thumbnail the palette is computed from
+const PALETTE_IMAGE_SIZE: u32 = 64;
+const BLURHASH_COMPONENTS_X: u32 = 4;
+const BLURHASH_COMPONENTS_Y: u32 = 3;
+/// Minimum distance between the dominant color and the accent one, in RGB
+const ACCENT_MIN_DISTANCE: f64 = 60.0;
+
+// region - CoverPalette
+/// Placeholder shown while the cover loads, and colors to theme the book pages
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CoverPalette {
+    pub blurhash: String,
+    /// Most common color of the cover, like `#1a2b3c`
+    pub dominant_color: String,
+    /// Most vivid color of the cover that stands out from the dominant one
+    pub accent_color: String,
+}
+
+impl CoverPalette {
+    fn from_columns(
+        blurhash: Option<String>,
+        dominant_color: Option<String>,
+        accent_color: Option<String>,
+    ) -> Option<Self> {
+        Some(Self {
+            blurhash: blurhash?,
+            dominant_color: dominant_color?,
+            accent_color: accent_color?,
+        })
+    }
+}
+// endregion - CoverPalette
+
+// region - CoverId
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CoverId {
+    pub id: String,
+    /// Url of the cover in the default size
+    pub url: String,
+}
+// endregion - CoverId
+
+// region - CoverFile
+/// One of the files of a cover, like `medium.webp`
+#[derive(Debug, Clone, PartialEq)]
+pub struct CoverFile {
+    name: String,
+    content_type: &'static str,
+}
+
+impl CoverFile {
+    pub fn parse(file: &str) -> Result<Self> {
+        let (size, extension) = file.split_once('.').ok_or(Error::NotFound)?;
+        if !COVER_SIZES.iter().any(|(name, _)| *name == size) {
+            return Err(Error::NotFound);
+        }
+        let content_type = match extension {
+            "jpg" => "image/jpeg",
+            "webp" => "image/webp",
+            _ => return Err(Error::NotFound),
+        };
+
+        Ok(Self {
+            name: file.to_string(),
+            content_type,
+        })
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        self.content_type
+    }
+}
+// endregion - CoverFile
+
+fn covers_dir() -> PathBuf {
+    let dir = std::env::var("COVERS_DIR").unwrap_or(DEFAULT_COVERS_DIR.to_string());
+    PathBuf::from(dir)
+}
+
+/// The ids of the manual volumes contain a colon, only the characters safe
+/// in a file name are kept
+fn volume_cover_dir(book_id: &str) -> PathBuf {
+    let name: String = book_id
+        .chars()
+        .map(|c| match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '-' | '_' => c,
+            _ => '_',
+        })
+        .collect();
+    covers_dir().join("volumes").join(name)
+}
+
+fn custom_cover_dir(cover_id: Uuid) -> PathBuf {
+    covers_dir().join("custom").join(cover_id.to_string())
+}
+
+/// Url of the cover served by the API: the one uploaded by the user, or the
+/// one of the volume when the external API has an image. Covers overridden
+/// in the metadata of the book are not served by the API
+pub fn cover_url(book_id: &str, custom_cover_id: Option<Uuid>, has_cover: bool) -> String {
+    match custom_cover_id {
+        Some(cover_id) => format!(
+            "{}/custom/{}/{}.jpg",
+            COVERS_PATH, cover_id, DEFAULT_COVER_SIZE
+        ),
+        None if has_cover => format!("{}/{}/{}.jpg", COVERS_PATH, book_id, DEFAULT_COVER_SIZE),
+        None => "".to_string(),
+    }
+}
+
+/// Returns the largest image of the volume, over https
+fn best_image_link(book_api: &BooksApiResponse) -> Option<String> {
+    let image_links = book_api.volume_info.as_ref()?.image_links.clone()?;
+    let link = image_links
+        .extra_large
+        .or(image_links.large)
+        .or(image_links.medium)
+        .or(image_links.small)
+        .or(image_links.thumbnail)
+        .or(image_links.small_thumbnail)?;
+
+    match link.strip_prefix("http://") {
+        Some(link) => Some(format!("https://{}", link)),
+        None => Some(link),
+    }
+}
+
+/// Addresses reachable from the internet only: the covers of the manual books
+/// are urls typed by the users, they must not reach the private network
+fn is_public_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            !(ip.is_private()
+                || ip.is_loopback()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.is_multicast()
+                || ip.is_documentation()
+                // 0.0.0.0/8 and the carrier-grade NAT 100.64.0.0/10
+                || a == 0
+                || (a == 100 && (64..128).contains(&b)))
+        }
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_public_ip(IpAddr::V4(ip)),
+            None => {
+                let first = ip.segments()[0];
+                !(ip.is_loopback()
+                    || ip.is_unspecified()
+                    || ip.is_multicast()
+                    // unique local fc00::/7 and link-local fe80::/10
+                    || (first & 0xfe00) == 0xfc00
+                    || (first & 0xffc0) == 0xfe80)
+            }
+        },
+    }
+}
+
+/// Checks that the url is an http(s) url with a host, on the default port
+pub fn parse_cover_url(url: &str) -> Result<Url> {
+    let invalid = || Error::InvalidField(format!("invalid cover url: {}", url));
+
+    let url = Url::parse(url).map_err(|_| invalid())?;
+    if !matches!(url.scheme(), "http" | "https") || url.port().is_some() {
+        return Err(invalid());
+    }
+    if url.host_str().is_none_or(|host| host.is_empty()) {
+        return Err(invalid());
+    }
+    Ok(url)
+}
+
+/// Checks the url of the cover and that its host resolves to public addresses
+/// only. Returns the url and the address to connect to
+async fn resolve_cover_url(url: &str) -> Result<(Url, SocketAddr)> {
+    let invalid = || Error::InvalidField(format!("invalid cover url: {}", url));
+
+    let url = parse_cover_url(url)?;
+    let host = url.host_str().ok_or_else(invalid)?.to_string();
+    let port = url.port_or_known_default().ok_or_else(invalid)?;
+
+    let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host.as_str(), port))
+        .await
+        .map_err(|_| invalid())?
+        .collect();
+    if addrs.is_empty() || !addrs.iter().all(|addr| is_public_ip(addr.ip())) {
+        return Err(invalid());
+    }
+
+    Ok((url, addrs[0]))
+}
+
+/// Downloads the image without following redirects, up to MAX_COVER_BYTES.
+/// The connection goes to the address checked, not to a new resolution of the host
+async fn download_image(url: &str) -> Result<Vec<u8>> {
+    let (url, addr) = resolve_cover_url(url).await?;
+    let host = url.host_str().unwrap_or_default().to_string();
+
+    let client = reqwest::Client::builder()
+        .redirect(Policy::none())
+        .timeout(Duration::from_secs(DOWNLOAD_TIMEOUT_SECS))
+        .resolve(&host, addr)
+        .build()
+        .map_err(|e| Error::ExternalApiError(e.to_string()))?;
+    let mut res = client
+        .get(url.clone())
+        .send()
+        .await
+        .map_err(|e| Error::ExternalApiError(e.to_string()))?;
+    if !res.status().is_success() {
+        return Err(Error::ExternalApiError(format!(
+            "cover download failed with status {}: {}",
+            res.status(),
+            url
+        )));
+    }
+
+    let too_large = || Error::ExternalApiError(format!("cover too large: {}", url));
+    if res
+        .content_length()
+        .is_some_and(|length| length > MAX_COVER_BYTES as u64)
+    {
+        return Err(too_large());
+    }
+    // the length announced can be missing or wrong
+    let mut data = vec![];
+    while let Some(chunk) = res
+        .chunk()
+        .await
+        .map_err(|e| Error::ExternalApiError(e.to_string()))?
+    {
+        if data.len() + chunk.len() > MAX_COVER_BYTES {
+            return Err(too_large());
+        }
+        data.extend_from_slice(&chunk);
+    }
+
+    Ok(data)
+}
+
+fn encode_sizes(image: &DynamicImage) -> Result<Vec<(String, Vec<u8>)>> {
+    let mut files = vec![];
+    for (size, width) in COVER_SIZES {
+        let resized = if image.width() > width {
+            image.resize(width, u32::MAX, FilterType::Lanczos3)
+        } else {
+            image.clone()
+        };
+
+        let mut jpeg = vec![];
+        resized
+            .to_rgb8()
+            .write_with_encoder(JpegEncoder::new_with_quality(&mut jpeg, JPEG_QUALITY))
+            .map_err(|_| Error::InternalServerError)?;
+        files.push((format!("{}.jpg", size), jpeg));
+
+        // lossy, the lossless encoding is larger than the JPEG
+        let rgba = resized.to_rgba8();
+        let webp =
+            webp::Encoder::from_rgba(&rgba, rgba.width(), rgba.height()).encode(WEBP_QUALITY);
+        files.push((format!("{}.webp", size), webp.to_vec()));
+    }
+
+    Ok(files)
+}
+
+fn hex_color(color: [u8; 3]) -> String {
+    format!("#{:02x}{:02x}{:02x}", color[0], color[1], color[2])
+}
+
+fn color_distance(a: [u8; 3], b: [u8; 3]) -> f64 {
+    a.iter()
+        .zip(b.iter())
+        .map(|(a, b)| (*a as f64 - *b as f64).powi(2))
+        .sum::<f64>()
+        .sqrt()
+}
+
+fn saturation(color: [u8; 3]) -> f64 {
+    let max = *color.iter().max().unwrap_or(&0) as f64;
+    let min = *color.iter().min().unwrap_or(&0) as f64;
+    if max == 0.0 {
+        0.0
+    } else {
+        (max - min) / max
+    }
+}
+
+/// The colors are grouped in buckets of 16 shades per channel: the dominant
+/// color is the average of the largest bucket, the accent one the average of the
+/// bucket with the best mix of size and saturation, far enough from the dominant
+fn compute_palette(image: &DynamicImage) -> Result<CoverPalette> {
+    let thumbnail = image
+        .thumbnail(PALETTE_IMAGE_SIZE, PALETTE_IMAGE_SIZE)
+        .to_rgba8();
+    let blurhash = blurhash::encode(
+        BLURHASH_COMPONENTS_X,
+        BLURHASH_COMPONENTS_Y,
+        thumbnail.width(),
+        thumbnail.height(),
+        thumbnail.as_raw(),
+    )
+    .map_err(|_| Error::InternalServerError)?;
+
+    let mut buckets: BTreeMap<[u8; 3], (u64, [u64; 3])> = BTreeMap::new();
+    // transparent pixels are not part of the cover
+    for pixel in thumbnail.pixels().filter(|p| p[3] >= 128) {
+        let (count, sums) = buckets
+            .entry([pixel[0] >> 4, pixel[1] >> 4, pixel[2] >> 4])
+            .or_default();
+        *count += 1;
+        for (sum, channel) in sums.iter_mut().zip(pixel.0.iter()) {
+            *sum += *channel as u64;
+        }
+    }
+
+    let mut colors: Vec<(u64, [u8; 3])> = buckets
+        .into_values()
+        .map(|(count, sums)| (count, sums.map(|sum| (sum / count) as u8)))
+        .collect();
+    colors.sort_by_key(|(count, _)| Reverse(*count));
+
+    let dominant = colors.first().map(|(_, color)| *color).unwrap_or_default();
+    let score = |(count, color): &(u64, [u8; 3])| *count as f64 * (0.1 + saturation(*color));
+    let accent = colors
+        .iter()
+        .filter(|(_, color)| color_distance(*color, dominant) >= ACCENT_MIN_DISTANCE)
+        .max_by(|a, b| score(a).total_cmp(&score(b)))
+        .map(|(_, color)| *color)
+        .unwrap_or(dominant);
+
+    Ok(CoverPalette {
+        blurhash,
+        dominant_color: hex_color(dominant),
+        accent_color: hex_color(accent),
+    })
+}
+
+// region - SavedCover
+struct SavedCover {
+    width: u32,
+    height: u32,
+    palette: CoverPalette,
+}
+// endregion - SavedCover
+
+/// Decodes the image and writes every size of it in the directory, in JPEG and
+/// WebP. Returns the dimensions and the palette of the image
+async fn save_cover_files(dir: PathBuf, data: Vec<u8>) -> Result<SavedCover> {
+    // decoding and resizing are blocking
+    let (saved, files) = tokio::task::spawn_blocking(move || {
+        let image = image::load_from_memory(&data)
+            .map_err(|e| Error::InvalidField(format!("invalid cover image: {}", e)))?;
+        let saved = SavedCover {
+            width: image.width(),
+            height: image.height(),
+            palette: compute_palette(&image)?,
+        };
+        Ok::<_, Error>((saved, encode_sizes(&image)?))
+    })
+    .await
+    .map_err(|_| Error::InternalServerError)??;
+
+    tokio::fs::create_dir_all(&dir)
+        .await
+        .map_err(|_| Error::InternalServerError)?;
+    for (name, content) in files {
+        // the files being served are replaced at once
+        let tmp = dir.join(format!("{}.{}.tmp", name, Uuid::new_v4()));
+        tokio::fs::write(&tmp, content)
+            .await
+            .map_err(|_| Error::InternalServerError)?;
+        tokio::fs::rename(&tmp, dir.join(name))
+            .await
+            .map_err(|_| Error::InternalServerError)?;
+    }
+
+    Ok(saved)
+}
+
+async fn remove_cover_files(dir: PathBuf) {
+    if let Err(e) = tokio::fs::remove_dir_all(&dir).await {
+        tracing_error!("{:<6} - removing {:?}: {e:?}", "ERR", dir);
+    }
+}
+
+async fn read_cover_file(dir: PathBuf, file: &CoverFile) -> Result<Vec<u8>> {
+    tokio::fs::read(dir.join(&file.name))
+        .await
+        .map_err(|_| Error::NotFound)
+}
+
+/// Returns the file of the cover of the volume, downloading the largest image
+/// of the external API the first time and when the volume links another one
+pub async fn get_volume_cover(
+    model_manager: &ModelManager,
+    book_id: &str,
+    file: &CoverFile,
+) -> Result<Vec<u8>> {
+    let book_api = get_volume(model_manager, book_id).await?;
+    let source_url = best_image_link(&book_api).ok_or(Error::NotFound)?;
+    let dir = volume_cover_dir(book_id);
+
+    let cached = covers::Entity::find_by_id(book_id.to_string())
+        .one(model_manager.db())
+        .await
+        .map_err(|e| Error::DbError(e.to_string()))?;
+    // the covers cached without a palette are generated again
+    if cached.is_some_and(|cover| cover.source_url == source_url && cover.blurhash.is_some()) {
+        if let Ok(data) = read_cover_file(dir.clone(), file).await {
+            return Ok(data);
+        }
+    }
+
+    let data = download_image(&source_url).await?;
+    let saved = save_cover_files(dir.clone(), data).await?;
+
+    let now = Utc::now().naive_utc();
+    let cover = covers::ActiveModel {
+        book_id: ActiveValue::Set(book_id.to_string()),
+        created_at: ActiveValue::Set(now),
+        updated_at: ActiveValue::Set(now),
+        source_url: ActiveValue::Set(source_url),
+        width: ActiveValue::Set(saved.width as i32),
+        height: ActiveValue::Set(saved.height as i32),
+        blurhash: ActiveValue::Set(Some(saved.palette.blurhash)),
+        dominant_color: ActiveValue::Set(Some(saved.palette.dominant_color)),
+        accent_color: ActiveValue::Set(Some(saved.palette.accent_color)),
+    };
+    covers::Entity::insert(cover)
+        .on_conflict(
+            OnConflict::column(covers::Column::BookId)
+                .update_columns([
+                    covers::Column::SourceUrl,
+                    covers::Column::Width,
+                    covers::Column::Height,
+                    covers::Column::Blurhash,
+                    covers::Column::DominantColor,
+                    covers::Column::AccentColor,
+                    covers::Column::UpdatedAt,
+                ])
+                .to_owned(),
+        )
+        .exec(model_manager.db())
+        .await
+        .map_err(|e| Error::DbError(e.to_string()))?;
+
+    read_cover_file(dir, file).await
+}
+
+/// Returns the file of a cover uploaded by a user
+pub async fn get_custom_cover(cover_id: &str, file: &CoverFile) -> Result<Vec<u8>> {
+    let cover_id = Uuid::parse_str(cover_id).map_err(|_| Error::NotFound)?;
+    read_cover_file(custom_cover_dir(cover_id), file).await
+}
+
+/// Replaces the cover of the book with the image uploaded. Every upload gets a
+/// new id, so the urls of the covers never change content
+pub async fn save_custom_cover(
+    model_manager: &ModelManager,
+    book: books::Model,
+    data: Vec<u8>,
+) -> Result<Uuid> {
+    if data.is_empty() {
+        return Err(Error::MissingFields("missing fields: cover".to_string()));
+    }
+
+    let cover_id = Uuid::new_v4();
+    let saved = save_cover_files(custom_cover_dir(cover_id), data).await?;
+
+    let previous = book.custom_cover_id;
+    let mut book_to_update: books::ActiveModel = book.into();
+    book_to_update.updated_at = ActiveValue::Set(Some(Utc::now().naive_utc()));
+    book_to_update.custom_cover_id = ActiveValue::Set(Some(cover_id));
+    book_to_update.custom_cover_blurhash = ActiveValue::Set(Some(saved.palette.blurhash));
+    book_to_update.custom_cover_dominant_color =
+        ActiveValue::Set(Some(saved.palette.dominant_color));
+    book_to_update.custom_cover_accent_color = ActiveValue::Set(Some(saved.palette.accent_color));
+    if let Err(e) = book_to_update.update(model_manager.db()).await {
+        remove_cover_files(custom_cover_dir(cover_id)).await;
+        return Err(Error::DbError(e.to_string()));
+    }
+
+    if let Some(previous) = previous {
+        remove_cover_files(custom_cover_dir(previous)).await;
+    }
+    Ok(cover_id)
+}
+
+/// Removes the files of a cover uploaded by a user, when its book is deleted
+pub async fn remove_custom_cover_files(cover_id: Uuid) {
+    remove_cover_files(custom_cover_dir(cover_id)).await;
+}
+
+/// Removes the cover uploaded for the book, the one of the volume is used again
+pub async fn delete_custom_cover(model_manager: &ModelManager, book: books::Model) -> Result<()> {
+    let cover_id = match book.custom_cover_id {
+        Some(cover_id) => cover_id,
+        None => return Err(Error::NotFound),
+    };
+
+    let mut book_to_update: books::ActiveModel = book.into();
+    book_to_update.updated_at = ActiveValue::Set(Some(Utc::now().naive_utc()));
+    book_to_update.custom_cover_id = ActiveValue::Set(None);
+    book_to_update.custom_cover_blurhash = ActiveValue::Set(None);
+    book_to_update.custom_cover_dominant_color = ActiveValue::Set(None);
+    book_to_update.custom_cover_accent_color = ActiveValue::Set(None);
+    book_to_update
+        .update(model_manager.db())
+        .await
+        .map_err(|e| Error::DbError(e.to_string()))?;
+
+    remove_cover_files(custom_cover_dir(cover_id)).await;
+    Ok(())
+}
+
+/// Returns the palettes of the covers of the books, by id of the book. The
+/// covers not cached yet have no palette
+pub async fn get_cover_palettes(
+    model_manager: &ModelManager,
+    books: &[books::Model],
+) -> Result<HashMap<Uuid, CoverPalette>> {
+    let mut palettes = HashMap::new();
+    let mut volume_books: HashMap<String, Vec<Uuid>> = HashMap::new();
+    for book in books {
+        if book.custom_cover_id.is_some() {
+            let palette = CoverPalette::from_columns(
+                book.custom_cover_blurhash.clone(),
+                book.custom_cover_dominant_color.clone(),
+                book.custom_cover_accent_color.clone(),
+            );
+            if let Some(palette) = palette {
+                palettes.insert(book.id, palette);
+            }
+        } else if book.metadata_overrides.get(FIELD_COVER).is_none() {
+            // the palette of the volume is not the one of the overridden cover
+            volume_books
+                .entry(book.book_id.clone())
+                .or_default()
+                .push(book.id);
+        }
+    }
+    if volume_books.is_empty() {
+        return Ok(palettes);
+    }
+
+    let covers = covers::Entity::find()
+        .filter(covers::Column::BookId.is_in(volume_books.keys().cloned()))
+        .all(model_manager.db())
+        .await
+        .map_err(|e| Error::DbError(e.to_string()))?;
+    for cover in covers {
+        let book_ids = volume_books.remove(&cover.book_id).unwrap_or_default();
+        let palette =
+            CoverPalette::from_columns(cover.blurhash, cover.dominant_color, cover.accent_color);
+        if let Some(palette) = palette {
+            for book_id in book_ids {
+                palettes.insert(book_id, palette.clone());
+            }
+        }
+    }
+
+    Ok(palettes)
+}
+
+#[cfg(test)]
+mod tests {
+    use image::{Rgba, RgbaImage};
+
+    use super::*;
+
+    const NAVY: Rgba<u8> = Rgba([16, 32, 96, 255]);
+    const RED: Rgba<u8> = Rgba([224, 16, 16, 255]);
+
+    /// 100x150 cover in `left` before the column `split`, in `right` after it
+    fn two_colors(split: u32, left: Rgba<u8>, right: Rgba<u8>) -> DynamicImage {
+        let image = RgbaImage::from_fn(100, 150, |x, _| if x < split { left } else { right });
+        DynamicImage::ImageRgba8(image)
+    }
+
+    #[test]
+    fn encode_sizes_writes_jpeg_and_lossy_webp() {
+        let files = encode_sizes(&two_colors(75, NAVY, RED)).unwrap();
+        assert_eq!(files.len(), COVER_SIZES.len() * 2);
+        for (name, data) in &files {
+            let file = CoverFile::parse(name).unwrap();
+            let image = image::load_from_memory(data).unwrap();
+            assert_eq!((image.width(), image.height()), (100, 150), "{}", name);
+            if file.content_type() == "image/webp" {
+                let jpeg = &files
+                    .iter()
+                    .find(|(n, _)| *n == name.replace("webp", "jpg"))
+                    .unwrap()
+                    .1;
+                assert!(data.len() < jpeg.len(), "{} is larger than the JPEG", name);
+            }
+        }
+    }
+
+    #[test]
+    fn compute_palette_finds_dominant_and_accent_colors() {
+        let palette = compute_palette(&two_colors(75, NAVY, RED)).unwrap();
+        assert_eq!(palette.dominant_color, "#102060");
+        assert_eq!(palette.accent_color, "#e01010");
+        // 1 + 1 + 4 characters for the header and the DC, 2 per AC component
+        let components = BLURHASH_COMPONENTS_X * BLURHASH_COMPONENTS_Y;
+        assert_eq!(palette.blurhash.len() as u32, 6 + 2 * (components - 1));
+    }
+
+    #[test]
+    fn compute_palette_of_plain_cover_uses_the_dominant_as_accent() {
+        let palette = compute_palette(&two_colors(100, NAVY, RED)).unwrap();
+        assert_eq!(palette.dominant_color, "#102060");
+        assert_eq!(palette.accent_color, palette.dominant_color);
+    }
+
+    #[test]
+    fn compute_palette_ignores_transparent_pixels() {
+        let palette = compute_palette(&two_colors(75, Rgba([255, 255, 255, 0]), RED)).unwrap();
+        assert_eq!(palette.dominant_color, "#e01010");
+        assert_eq!(palette.accent_color, "#e01010");
+    }
+}
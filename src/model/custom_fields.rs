@@ -0,0 +1,449 @@
+use chrono::{NaiveDate, Utc};
+use sea_orm::{
+    sea_query::{Expr, SimpleExpr},
+    ActiveValue, ColumnTrait, ConnectionTrait, DbBackend, EntityTrait, ModelTrait, QueryFilter,
+    QueryOrder, Statement, TransactionTrait,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use uuid::Uuid;
+
+use crate::{
+    entities::custom_fields::{self, ActiveModel, Model},
+    error::{Error, Result},
+};
+
+use super::ModelManager;
+
+// region - FieldType
+pub const FIELD_TYPE_TEXT: &str = "text";
+pub const FIELD_TYPE_NUMBER: &str = "number";
+pub const FIELD_TYPE_DATE: &str = "date";
+pub const FIELD_TYPE_BOOLEAN: &str = "boolean";
+pub const FIELD_TYPE_ENUM: &str = "enum";
+// endregion - FieldType
+
+/// Format of the values of the date fields
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+/// Keys are used in the JSON of the books and in the filters of the listing,
+/// like `signed_copy`
+fn validate_key(key: &str) -> Result<String> {
+    let key = key.trim();
+    if key.is_empty() {
+        return Err(Error::MissingFields("missing fields: key".to_string()));
+    }
+    if !key
+        .chars()
+        .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+    {
+        return Err(Error::InvalidField(format!(
+            "invalid key: {}, only lowercase letters, digits and underscores are allowed",
+            key
+        )));
+    }
+    Ok(key.to_string())
+}
+
+fn validate_name(name: &str) -> Result<String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(Error::MissingFields("missing fields: name".to_string()));
+    }
+    Ok(name.to_string())
+}
+
+fn validate_options(field_type: &str, options: &Option<Vec<String>>) -> Result<Vec<String>> {
+    let mut valid_options: Vec<String> = vec![];
+    for option in options.iter().flatten().map(|o| o.trim()) {
+        if !option.is_empty() && !valid_options.iter().any(|o| o == option) {
+            valid_options.push(option.to_string());
+        }
+    }
+
+    match field_type {
+        FIELD_TYPE_ENUM if valid_options.is_empty() => {
+            Err(Error::MissingFields("missing fields: options".to_string()))
+        }
+        FIELD_TYPE_ENUM => Ok(valid_options),
+        // only the enum fields have options
+        _ => Ok(vec![]),
+    }
+}
+
+fn validate_field_type(field_type: &str) -> Result<()> {
+    match field_type {
+        FIELD_TYPE_TEXT | FIELD_TYPE_NUMBER | FIELD_TYPE_DATE | FIELD_TYPE_BOOLEAN
+        | FIELD_TYPE_ENUM => Ok(()),
+        _ => Err(Error::InvalidField(format!(
+            "invalid field type: {}",
+            field_type
+        ))),
+    }
+}
+
+// region - CustomFieldId
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CustomFieldId {
+    pub id: String,
+}
+// endregion - CustomFieldId
+
+// region - CustomFieldToSave
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CustomFieldToSave {
+    pub user_id: String,
+    pub key: String,
+    pub name: String,
+    pub field_type: String,
+    /// Allowed values of the enum fields
+    pub options: Option<Vec<String>>,
+}
+
+impl CustomFieldToSave {
+    pub fn to_active_model(&self) -> Result<ActiveModel> {
+        validate_field_type(&self.field_type)?;
+
+        let now = Utc::now().naive_utc();
+        Ok(ActiveModel {
+            id: ActiveValue::Set(Uuid::new_v4()),
+            created_at: ActiveValue::Set(now),
+            updated_at: ActiveValue::Set(now),
+            user_id: ActiveValue::Set(self.user_id.clone()),
+            key: ActiveValue::Set(validate_key(&self.key)?),
+            name: ActiveValue::Set(validate_name(&self.name)?),
+            field_type: ActiveValue::Set(self.field_type.clone()),
+            options: ActiveValue::Set(validate_options(&self.field_type, &self.options)?),
+        })
+    }
+}
+// endregion - CustomFieldToSave
+
+// region - CustomFieldToUpdate
+/// The key and the type cannot change, they would not match the values of the books
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CustomFieldToUpdate {
+    pub name: Option<String>,
+    /// Replaces the options, the books keep the values removed
+    pub options: Option<Vec<String>>,
+}
+
+impl CustomFieldToUpdate {
+    pub fn to_active_model(&self, db_field: Model) -> Result<ActiveModel> {
+        let field_type = db_field.field_type.clone();
+        let mut field_to_update: ActiveModel = db_field.into();
+
+        field_to_update.updated_at = ActiveValue::Set(Utc::now().naive_utc());
+        // check if the optional fields are set and update the active model accordingly
+        if let Some(name) = self.name.clone() {
+            field_to_update.name = ActiveValue::Set(validate_name(&name)?);
+        };
+        if self.options.is_some() {
+            field_to_update.options =
+                ActiveValue::Set(validate_options(&field_type, &self.options)?);
+        };
+
+        Ok(field_to_update)
+    }
+}
+// endregion - CustomFieldToUpdate
+
+// region - CustomField
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CustomField {
+    pub id: String,
+    pub user_id: String,
+    pub key: String,
+    pub name: String,
+    pub field_type: String,
+    pub options: Vec<String>,
+}
+
+impl From<Model> for CustomField {
+    fn from(field_db: Model) -> Self {
+        Self {
+            id: field_db.id.to_string(),
+            user_id: field_db.user_id,
+            key: field_db.key,
+            name: field_db.name,
+            field_type: field_db.field_type,
+            options: field_db.options,
+        }
+    }
+}
+// endregion - CustomField
+
+/// Checks the value against the type of the field, returning it as stored in the books
+fn validate_value(field: &Model, value: &Value) -> Result<Value> {
+    let invalid = || {
+        Error::InvalidField(format!(
+            "invalid value for the {} field {}: {}",
+            field.field_type, field.key, value
+        ))
+    };
+
+    match (field.field_type.as_str(), value) {
+        (FIELD_TYPE_TEXT, Value::String(text)) => Ok(Value::String(text.clone())),
+        (FIELD_TYPE_NUMBER, Value::Number(number)) => Ok(Value::Number(number.clone())),
+        (FIELD_TYPE_DATE, Value::String(date)) => NaiveDate::parse_from_str(date, DATE_FORMAT)
+            .map(|date| Value::String(date.format(DATE_FORMAT).to_string()))
+            .map_err(|_| invalid()),
+        (FIELD_TYPE_BOOLEAN, Value::Bool(boolean)) => Ok(Value::Bool(*boolean)),
+        (FIELD_TYPE_ENUM, Value::String(option)) if field.options.contains(option) => {
+            Ok(Value::String(option.clone()))
+        }
+        _ => Err(invalid()),
+    }
+}
+
+/// Validates the values of the custom fields of a book against the fields of
+/// the user. Null values are kept, they remove the value when updating a book
+pub fn validate_custom_fields(
+    fields: &[Model],
+    values: &Map<String, Value>,
+) -> Result<Map<String, Value>> {
+    let mut valid_values = Map::new();
+    for (key, value) in values {
+        let field = fields
+            .iter()
+            .find(|f| &f.key == key)
+            .ok_or(Error::InvalidField(format!(
+                "unknown custom field: {}",
+                key
+            )))?;
+
+        let value = match value {
+            Value::Null => Value::Null,
+            value => validate_value(field, value)?,
+        };
+        valid_values.insert(key.clone(), value);
+    }
+    Ok(valid_values)
+}
+
+/// Filter of the books on the value of a custom field, the value is read from
+/// the query string and converted to the type of the field
+pub fn custom_field_filter(fields: &[Model], key: &str, value: &str) -> Result<SimpleExpr> {
+    let field = fields
+        .iter()
+        .find(|f| f.key == key)
+        .ok_or(Error::InvalidField(format!(
+            "unknown custom field: {}",
+            key
+        )))?;
+
+    let value = match field.field_type.as_str() {
+        FIELD_TYPE_NUMBER => value
+            .parse::<f64>()
+            .ok()
+            .and_then(|n| serde_json::Number::from_f64(n).map(Value::Number))
+            .ok_or(Error::InvalidField(format!(
+                "invalid value for the {} field {}: {}",
+                field.field_type, field.key, value
+            )))?,
+        FIELD_TYPE_BOOLEAN => Value::Bool(value.parse::<bool>().map_err(|_| {
+            Error::InvalidField(format!(
+                "invalid value for the {} field {}: {}",
+                field.field_type, field.key, value
+            ))
+        })?),
+        _ => validate_value(field, &Value::String(value.to_string()))?,
+    };
+
+    // numbers are compared as numbers, 3 matches 3.0
+    if field.field_type == FIELD_TYPE_NUMBER {
+        return Ok(Expr::cust_with_values(
+            "(custom_fields ->> $1)::NUMERIC = $2::NUMERIC",
+            [key.to_string(), value.to_string()],
+        ));
+    }
+    Ok(Expr::cust_with_values(
+        "custom_fields @> $1",
+        [json!({ key: value })],
+    ))
+}
+
+/// Returns the custom fields of the user, sorted by name
+pub async fn get_user_custom_fields(
+    model_manager: &ModelManager,
+    user_id: &str,
+) -> Result<Vec<Model>> {
+    custom_fields::Entity::find()
+        .filter(custom_fields::Column::UserId.eq(user_id))
+        .order_by_asc(custom_fields::Column::Name)
+        .all(model_manager.db())
+        .await
+        .map_err(|e| Error::DbError(e.to_string()))
+}
+
+/// Returns the custom field with the given id if it belongs to the user
+pub async fn get_user_custom_field(
+    model_manager: &ModelManager,
+    user_id: &str,
+    field_id: &str,
+) -> Result<Model> {
+    let field_id =
+        Uuid::parse_str(field_id).map_err(|_| Error::ParseError("Invalid id".to_string()))?;
+
+    let field = custom_fields::Entity::find_by_id(field_id)
+        .one(model_manager.db())
+        .await
+        .map_err(|e| Error::DbError(e.to_string()))?
+        .ok_or(Error::NotFound)?;
+
+    if field.user_id != user_id {
+        return Err(Error::Unathorized);
+    }
+    Ok(field)
+}
+
+/// Deletes the custom field and its values from the books of the user
+pub async fn delete_custom_field(model_manager: &ModelManager, field: Model) -> Result<()> {
+    let txn = model_manager
+        .db()
+        .begin()
+        .await
+        .map_err(|e| Error::DbError(e.to_string()))?;
+
+    txn.execute(Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        r#"UPDATE books SET custom_fields = custom_fields - $2, updated_at = NOW()
+        WHERE user_id = $1 AND custom_fields ? $2"#,
+        [field.user_id.clone().into(), field.key.clone().into()],
+    ))
+    .await
+    .map_err(|e| Error::DbError(e.to_string()))?;
+
+    field
+        .delete(&txn)
+        .await
+        .map_err(|e| Error::DbError(e.to_string()))?;
+
+    txn.commit()
+        .await
+        .map_err(|e| Error::DbError(e.to_string()))?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn field(key: &str, field_type: &str, options: &[&str]) -> Model {
+        let now = Utc::now().naive_utc();
+        Model {
+            id: Uuid::new_v4(),
+            created_at: now,
+            updated_at: now,
+            user_id: "user".to_string(),
+            key: key.to_string(),
+            name: key.to_string(),
+            field_type: field_type.to_string(),
+            options: options.iter().map(|o| o.to_string()).collect(),
+        }
+    }
+
+    fn fields() -> Vec<Model> {
+        vec![
+            field("edition", FIELD_TYPE_TEXT, &[]),
+            field("price", FIELD_TYPE_NUMBER, &[]),
+            field("bought", FIELD_TYPE_DATE, &[]),
+            field("signed", FIELD_TYPE_BOOLEAN, &[]),
+            field("format", FIELD_TYPE_ENUM, &["paperback", "hardcover"]),
+        ]
+    }
+
+    fn values(value: Value) -> Map<String, Value> {
+        value.as_object().unwrap().clone()
+    }
+
+    #[test]
+    fn validate_custom_fields_accepts_the_types_of_the_fields() {
+        let book_values = values(json!({
+            "edition": "First",
+            "price": 12.5,
+            "bought": "2024-03-01",
+            "signed": true,
+            "format": "hardcover",
+        }));
+        assert_eq!(
+            validate_custom_fields(&fields(), &book_values).unwrap(),
+            book_values
+        );
+    }
+
+    #[test]
+    fn validate_custom_fields_keeps_null_values() {
+        let book_values = values(json!({ "price": null, "format": null }));
+        assert_eq!(
+            validate_custom_fields(&fields(), &book_values).unwrap(),
+            book_values
+        );
+    }
+
+    #[test]
+    fn validate_custom_fields_rejects_invalid_values() {
+        for invalid in [
+            json!({ "edition": 1 }),
+            json!({ "price": "12" }),
+            json!({ "bought": "01/03/2024" }),
+            json!({ "signed": "yes" }),
+            json!({ "format": "ebook" }),
+            json!({ "unknown": "value" }),
+        ] {
+            assert!(
+                matches!(
+                    validate_custom_fields(&fields(), &values(invalid.clone())),
+                    Err(Error::InvalidField(_))
+                ),
+                "{} was accepted",
+                invalid
+            );
+        }
+    }
+
+    #[test]
+    fn custom_field_filter_converts_the_query_value() {
+        assert_eq!(
+            custom_field_filter(&fields(), "signed", "true").unwrap(),
+            Expr::cust_with_values("custom_fields @> $1", [json!({ "signed": true })])
+        );
+        assert_eq!(
+            custom_field_filter(&fields(), "bought", "2024-03-01").unwrap(),
+            Expr::cust_with_values("custom_fields @> $1", [json!({ "bought": "2024-03-01" })])
+        );
+        assert_eq!(
+            custom_field_filter(&fields(), "price", "3").unwrap(),
+            Expr::cust_with_values(
+                "(custom_fields ->> $1)::NUMERIC = $2::NUMERIC",
+                ["price", "3.0"]
+            )
+        );
+    }
+
+    #[test]
+    fn custom_field_filter_rejects_invalid_values() {
+        for (key, value) in [
+            ("price", "cheap"),
+            ("signed", "yes"),
+            ("bought", "yesterday"),
+            ("format", "ebook"),
+            ("unknown", "value"),
+        ] {
+            assert!(
+                matches!(
+                    custom_field_filter(&fields(), key, value),
+                    Err(Error::InvalidField(_))
+                ),
+                "{}={} was accepted",
+                key,
+                value
+            );
+        }
+    }
+}
@@ -0,0 +1,170 @@
+use chrono::NaiveDate;
+use serde::Deserialize;
+
+use super::{
+    books::{BookFull, READING_STATUS_READ, READING_STATUS_READING},
+    ratings::RatingScale,
+};
+
+// region - ExportFormat
+#[derive(Debug, Default, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    #[default]
+    Csv,
+    Ndjson,
+    Goodreads,
+}
+
+/// Custom fields are exported as a JSON object, their keys differ between users.
+/// The ratings are in the scale preferred by the user, like in the listing of the books
+const CSV_HEADER: [&str; 22] = [
+    "id",
+    "bookId",
+    "title",
+    "authors",
+    "publisher",
+    "publishedDate",
+    "description",
+    "isbn10",
+    "isbn13",
+    "pageCount",
+    "categories",
+    "language",
+    "cover",
+    "readingStatus",
+    "readingStartDate",
+    "readingEndDate",
+    "bookType",
+    "tags",
+    "rating",
+    "notes",
+    "libraryId",
+    "customFields",
+];
+
+/// Same columns read by the Goodreads import
+const GOODREADS_HEADER: [&str; 13] = [
+    "Title",
+    "Author",
+    "ISBN",
+    "ISBN13",
+    "My Rating",
+    "Publisher",
+    "Number of Pages",
+    "Year Published",
+    "Date Read",
+    "Date Added",
+    "Bookshelves",
+    "Exclusive Shelf",
+    "Private Notes",
+];
+
+impl ExportFormat {
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Self::Csv | Self::Goodreads => "text/csv; charset=utf-8",
+            Self::Ndjson => "application/x-ndjson",
+        }
+    }
+
+    pub fn file_name(&self, user_id: &str) -> String {
+        match self {
+            Self::Csv => format!("mirabel-{}.csv", user_id),
+            Self::Ndjson => format!("mirabel-{}.ndjson", user_id),
+            Self::Goodreads => format!("mirabel-{}-goodreads.csv", user_id),
+        }
+    }
+
+    /// Scale of the ratings of the export: the one preferred by the user, except
+    /// for Goodreads which reads stars
+    pub fn rating_scale(&self, user_rating_scale: RatingScale) -> RatingScale {
+        match self {
+            Self::Csv | Self::Ndjson => user_rating_scale,
+            Self::Goodreads => RatingScale::Stars,
+        }
+    }
+
+    /// Returns the first line of the export, if the format has one
+    pub fn header(&self) -> Option<String> {
+        match self {
+            Self::Csv => Some(csv_line(&CSV_HEADER)),
+            Self::Ndjson => None,
+            Self::Goodreads => Some(csv_line(&GOODREADS_HEADER)),
+        }
+    }
+
+    /// Returns the line of the export for the book, newline included
+    pub fn row(&self, book: &BookFull) -> String {
+        match self {
+            Self::Csv => csv_line(&[
+                &book.id,
+                &book.book_id,
+                &book.title,
+                &book.authors.join("; "),
+                &book.publisher,
+                &book.published_date,
+                &book.description,
+                &book.isbn10,
+                &book.isbn13,
+                &book.page_count.to_string(),
+                &book.categories.join("; "),
+                &book.language,
+                &book.cover,
+                &book.reading_status,
+                &format_date(book.reading_start_date, "%Y-%m-%d"),
+                &format_date(book.reading_end_date, "%Y-%m-%d"),
+                &book.book_type,
+                &book.tags.join("; "),
+                &book.rating.to_string(),
+                &book.notes,
+                &book.library_id,
+                &serde_json::to_string(&book.custom_fields).unwrap_or_default(),
+            ]),
+            Self::Ndjson => serde_json::to_string(book).unwrap_or_default() + "\n",
+            Self::Goodreads => csv_line(&[
+                &book.title,
+                &book.authors.first().cloned().unwrap_or_default(),
+                &book.isbn10,
+                &book.isbn13,
+                // Goodreads only has whole stars
+                &(book.rating.round() as i64).to_string(),
+                &book.publisher,
+                &book.page_count.to_string(),
+                &book.published_date.chars().take(4).collect::<String>(),
+                &format_date(book.reading_end_date, "%Y/%m/%d"),
+                &format_date(book.reading_start_date, "%Y/%m/%d"),
+                &book.tags.join(", "),
+                goodreads_exclusive_shelf(&book.reading_status),
+                &book.notes,
+            ]),
+        }
+    }
+}
+// endregion - ExportFormat
+
+fn csv_line<T: AsRef<[u8]>>(fields: &[T]) -> String {
+    let mut writer = csv::Writer::from_writer(vec![]);
+    // writing to a Vec cannot fail
+    let _ = writer.write_record(fields);
+    let line = writer.into_inner().unwrap_or_default();
+    String::from_utf8(line).unwrap_or_default()
+}
+
+/// Missing dates are stored in BookFull as the default date
+fn format_date(date: NaiveDate, format: &str) -> String {
+    if date == NaiveDate::default() {
+        "".to_string()
+    } else {
+        date.format(format).to_string()
+    }
+}
+
+fn goodreads_exclusive_shelf(reading_status: &str) -> &'static str {
+    match reading_status {
+        READING_STATUS_READ => "read",
+        READING_STATUS_READING => "currently-reading",
+        // Goodreads has no other default shelf
+        _ => "to-read",
+    }
+}
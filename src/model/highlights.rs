@@ -0,0 +1,231 @@
+use chrono::{NaiveDateTime, Utc};
+use sea_orm::{
+    sea_query::{extension::postgres::PgExpr, Expr},
+    ActiveValue, ColumnTrait, Condition, EntityTrait, Order, QueryFilter, QueryOrder, QuerySelect,
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    entities::highlights::{self, ActiveModel, Model},
+    error::{Error, Result},
+};
+
+use super::ModelManager;
+
+// region - HighlightId
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HighlightId {
+    pub id: String,
+}
+// endregion - HighlightId
+
+// region - HighlightToSave
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HighlightToSave {
+    pub user_id: String,
+    /// Id of the book in the shelf
+    pub book_id: String,
+    pub text: String,
+    pub page: Option<i32>,
+    pub location: Option<String>,
+    pub chapter: Option<String>,
+    pub comment: Option<String>,
+}
+
+impl HighlightToSave {
+    pub fn to_active_model(&self) -> Result<ActiveModel> {
+        if self.text.trim().is_empty() {
+            return Err(Error::MissingFields("missing fields: text".to_string()));
+        }
+        let book_id = Uuid::parse_str(&self.book_id)
+            .map_err(|_| Error::InvalidField(format!("invalid book id: {}", self.book_id)))?;
+
+        let now = Utc::now().naive_utc();
+        Ok(ActiveModel {
+            id: ActiveValue::Set(Uuid::new_v4()),
+            created_at: ActiveValue::Set(now),
+            updated_at: ActiveValue::Set(now),
+            book_id: ActiveValue::Set(book_id),
+            user_id: ActiveValue::Set(self.user_id.clone()),
+            text: ActiveValue::Set(self.text.trim().to_string()),
+            page: ActiveValue::Set(self.page),
+            location: ActiveValue::Set(self.location.clone()),
+            chapter: ActiveValue::Set(self.chapter.clone()),
+            comment: ActiveValue::Set(self.comment.clone()),
+        })
+    }
+}
+// endregion - HighlightToSave
+
+// region - HighlightToUpdate
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HighlightToUpdate {
+    pub text: Option<String>,
+    pub page: Option<i32>,
+    pub location: Option<String>,
+    pub chapter: Option<String>,
+    pub comment: Option<String>,
+}
+
+impl HighlightToUpdate {
+    pub fn to_active_model(&self, db_highlight: Model) -> Result<ActiveModel> {
+        let mut highlight_to_update: ActiveModel = db_highlight.into();
+
+        highlight_to_update.updated_at = ActiveValue::Set(Utc::now().naive_utc());
+        // check if the optional fields are set and update the active model accordingly
+        if let Some(text) = self.text.clone() {
+            if text.trim().is_empty() {
+                return Err(Error::MissingFields("missing fields: text".to_string()));
+            }
+            highlight_to_update.text = ActiveValue::Set(text.trim().to_string());
+        };
+        if let Some(page) = self.page {
+            highlight_to_update.page = ActiveValue::Set(Some(page));
+        };
+        if let Some(location) = self.location.clone() {
+            highlight_to_update.location = ActiveValue::Set(Some(location));
+        };
+        if let Some(chapter) = self.chapter.clone() {
+            highlight_to_update.chapter = ActiveValue::Set(Some(chapter));
+        };
+        if let Some(comment) = self.comment.clone() {
+            highlight_to_update.comment = ActiveValue::Set(Some(comment));
+        };
+
+        Ok(highlight_to_update)
+    }
+}
+// endregion - HighlightToUpdate
+
+// region - Highlight
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Highlight {
+    pub id: String,
+    pub book_id: String,
+    pub user_id: String,
+    pub text: String,
+    pub page: Option<i32>,
+    pub location: Option<String>,
+    pub chapter: Option<String>,
+    pub comment: Option<String>,
+    pub created_at: NaiveDateTime,
+}
+
+impl From<Model> for Highlight {
+    fn from(highlight_db: Model) -> Self {
+        Self {
+            id: highlight_db.id.to_string(),
+            book_id: highlight_db.book_id.to_string(),
+            user_id: highlight_db.user_id,
+            text: highlight_db.text,
+            page: highlight_db.page,
+            location: highlight_db.location,
+            chapter: highlight_db.chapter,
+            comment: highlight_db.comment,
+            created_at: highlight_db.created_at,
+        }
+    }
+}
+// endregion - Highlight
+
+// region - HighlightsParams
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HighlightsParams {
+    /// Searched in the text, the chapter and the comment of the highlights
+    pub q: Option<String>,
+    /// Id of the book in the shelf
+    pub book_id: Option<String>,
+}
+// endregion - HighlightsParams
+
+/// Returns the highlights of the user, the newest first
+pub async fn search_highlights(
+    model_manager: &ModelManager,
+    user_id: &str,
+    params: &HighlightsParams,
+) -> Result<Vec<Highlight>> {
+    let mut query = highlights::Entity::find().filter(highlights::Column::UserId.eq(user_id));
+
+    if let Some(book_id) = params.book_id.as_deref().filter(|b| !b.is_empty()) {
+        let book_id = Uuid::parse_str(book_id)
+            .map_err(|_| Error::InvalidField(format!("invalid book id: {}", book_id)))?;
+        query = query.filter(highlights::Column::BookId.eq(book_id));
+    }
+    if let Some(q) = params.q.as_deref().map(str::trim).filter(|q| !q.is_empty()) {
+        let pattern = format!("%{}%", escape_like(q));
+        query = query.filter(
+            Condition::any()
+                .add(Expr::col(highlights::Column::Text).ilike(&pattern))
+                .add(Expr::col(highlights::Column::Chapter).ilike(&pattern))
+                .add(Expr::col(highlights::Column::Comment).ilike(&pattern)),
+        );
+    }
+
+    let highlights = query
+        .order_by_desc(highlights::Column::CreatedAt)
+        .all(model_manager.db())
+        .await
+        .map_err(|e| Error::DbError(e.to_string()))?;
+
+    Ok(highlights.into_iter().map(Highlight::from).collect())
+}
+
+/// Returns a random highlight of the user.
+/// The quote of the day is the same for the whole day, and changes every day
+pub async fn random_highlight(
+    model_manager: &ModelManager,
+    user_id: &str,
+    daily: bool,
+) -> Result<Highlight> {
+    let order = if daily {
+        Expr::cust("md5(id::text || current_date::text)")
+    } else {
+        Expr::cust("random()")
+    };
+
+    let highlight = highlights::Entity::find()
+        .filter(highlights::Column::UserId.eq(user_id))
+        .order_by(order, Order::Asc)
+        .limit(1)
+        .one(model_manager.db())
+        .await
+        .map_err(|e| Error::DbError(e.to_string()))?
+        .ok_or(Error::NotFound)?;
+
+    Ok(Highlight::from(highlight))
+}
+
+/// Returns the highlight with the given id if it belongs to the user
+pub async fn get_user_highlight(
+    model_manager: &ModelManager,
+    user_id: &str,
+    highlight_id: &str,
+) -> Result<Model> {
+    let highlight_id =
+        Uuid::parse_str(highlight_id).map_err(|_| Error::ParseError("Invalid id".to_string()))?;
+
+    let highlight = highlights::Entity::find_by_id(highlight_id)
+        .one(model_manager.db())
+        .await
+        .map_err(|e| Error::DbError(e.to_string()))?
+        .ok_or(Error::NotFound)?;
+
+    if highlight.user_id != user_id {
+        return Err(Error::Unathorized);
+    }
+    Ok(highlight)
+}
+
+/// Escapes the wildcards of LIKE patterns
+fn escape_like(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
@@ -0,0 +1,202 @@
+use std::collections::HashMap;
+
+use quick_xml::events::{BytesStart, Event};
+use rusqlite::{Connection, OpenFlags};
+use uuid::Uuid;
+
+use crate::{
+    error::{Error, Result},
+    model::books::BOOK_TYPE_EBOOK,
+};
+
+use super::{clean_isbn, ImportEntry};
+
+/// Every SQLite database starts with this header
+const SQLITE_HEADER: &[u8] = b"SQLite format 3\0";
+
+/// Parses a Calibre library, either its `metadata.db` SQLite file or the OPF
+/// file Calibre writes next to each book
+pub async fn parse(data: Vec<u8>) -> Result<Vec<ImportEntry>> {
+    if data.starts_with(SQLITE_HEADER) {
+        // rusqlite is blocking and can only open files
+        tokio::task::spawn_blocking(move || parse_metadata_db(&data))
+            .await
+            .map_err(|_| Error::InternalServerError)?
+    } else {
+        let opf = String::from_utf8(data).map_err(|e| Error::ParseError(e.to_string()))?;
+        Ok(vec![parse_opf(&opf)?])
+    }
+}
+
+// region - metadata.db
+fn parse_metadata_db(data: &[u8]) -> Result<Vec<ImportEntry>> {
+    let path = std::env::temp_dir().join(format!("calibre-{}.db", Uuid::new_v4()));
+    std::fs::write(&path, data).map_err(|_| Error::InternalServerError)?;
+
+    let entries = read_metadata_db(&path);
+
+    if let Err(e) = std::fs::remove_file(&path) {
+        tracing::error!("{:<6} - removing {:?}: {e:?}", "ERR", path);
+    }
+
+    entries.map_err(|e| Error::ParseError(e.to_string()))
+}
+
+fn read_metadata_db(path: &std::path::Path) -> rusqlite::Result<Vec<ImportEntry>> {
+    let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+
+    // author, tags, rating and identifiers are linked to the book in their own tables
+    let mut authors: HashMap<i64, String> = HashMap::new();
+    let mut stmt = conn.prepare(
+        "SELECT l.book, a.name FROM books_authors_link l
+         JOIN authors a ON a.id = l.author ORDER BY l.id",
+    )?;
+    for row in stmt.query_map([], |r| Ok((r.get::<_, i64>(0)?, r.get::<_, String>(1)?)))? {
+        let (book, name) = row?;
+        authors.entry(book).or_insert(name);
+    }
+
+    let mut tags: HashMap<i64, Vec<String>> = HashMap::new();
+    let mut stmt = conn.prepare(
+        "SELECT l.book, t.name FROM books_tags_link l
+         JOIN tags t ON t.id = l.tag ORDER BY t.name",
+    )?;
+    for row in stmt.query_map([], |r| Ok((r.get::<_, i64>(0)?, r.get::<_, String>(1)?)))? {
+        let (book, name) = row?;
+        tags.entry(book).or_default().push(name);
+    }
+
+    let mut ratings: HashMap<i64, i64> = HashMap::new();
+    let mut stmt = conn.prepare(
+        "SELECT l.book, r.rating FROM books_ratings_link l
+         JOIN ratings r ON r.id = l.rating",
+    )?;
+    for row in stmt.query_map([], |r| Ok((r.get::<_, i64>(0)?, r.get::<_, i64>(1)?)))? {
+        let (book, rating) = row?;
+        ratings.insert(book, rating);
+    }
+
+    let mut identifiers: HashMap<i64, Vec<(String, String)>> = HashMap::new();
+    let mut stmt = conn.prepare("SELECT book, type, val FROM identifiers")?;
+    for row in stmt.query_map([], |r| {
+        Ok((
+            r.get::<_, i64>(0)?,
+            r.get::<_, String>(1)?,
+            r.get::<_, String>(2)?,
+        ))
+    })? {
+        let (book, type_field, value) = row?;
+        identifiers
+            .entry(book)
+            .or_default()
+            .push((type_field, value));
+    }
+
+    let mut entries = vec![];
+    let mut stmt = conn.prepare("SELECT id, title FROM books ORDER BY id")?;
+    for (i, row) in stmt
+        .query_map([], |r| Ok((r.get::<_, i64>(0)?, r.get::<_, String>(1)?)))?
+        .enumerate()
+    {
+        let (book, title) = row?;
+        let mut entry = ImportEntry {
+            row: i + 1,
+            title,
+            author: authors.remove(&book).unwrap_or_default(),
+            book_type: Some(BOOK_TYPE_EBOOK.to_string()),
+            tags: tags.remove(&book).unwrap_or_default(),
+            rating: ratings.get(&book).and_then(|r| map_rating(*r)),
+            ..Default::default()
+        };
+        for (type_field, value) in identifiers.remove(&book).unwrap_or_default() {
+            add_identifier(&mut entry, &type_field, &value);
+        }
+        entries.push(entry);
+    }
+
+    Ok(entries)
+}
+// endregion - metadata.db
+
+// region - OPF
+fn parse_opf(opf: &str) -> Result<ImportEntry> {
+    let mut reader = quick_xml::Reader::from_str(opf);
+    reader.config_mut().trim_text(true);
+
+    let mut entry = ImportEntry {
+        row: 1,
+        book_type: Some(BOOK_TYPE_EBOOK.to_string()),
+        ..Default::default()
+    };
+
+    // the element whose text is being read, with its identifier scheme if any
+    let mut current: Option<(String, String)> = None;
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(e)) => {
+                let name = String::from_utf8_lossy(e.local_name().as_ref()).to_string();
+                current = Some((name, attribute(&e, "scheme").unwrap_or_default()));
+            }
+            Ok(Event::Empty(e))
+                if e.local_name().as_ref() == b"meta"
+                    && attribute(&e, "name").as_deref() == Some("calibre:rating") =>
+            {
+                entry.rating = attribute(&e, "content")
+                    .and_then(|r| r.parse::<f64>().ok())
+                    .and_then(|r| map_rating(r as i64));
+            }
+            Ok(Event::Text(e)) => {
+                let text = e
+                    .unescape()
+                    .map_err(|e| Error::ParseError(e.to_string()))?
+                    .to_string();
+                match current.as_ref() {
+                    Some((name, _)) if name == "title" => entry.title = text,
+                    // the first creator is the main author
+                    Some((name, _)) if name == "creator" && entry.author.is_empty() => {
+                        entry.author = text
+                    }
+                    Some((name, _)) if name == "subject" => entry.tags.push(text),
+                    Some((name, scheme)) if name == "identifier" => {
+                        add_identifier(&mut entry, &scheme.to_lowercase(), &text)
+                    }
+                    _ => {}
+                }
+            }
+            Ok(Event::End(_)) => current = None,
+            Ok(Event::Eof) => break,
+            Err(e) => return Err(Error::ParseError(e.to_string())),
+            _ => {}
+        }
+    }
+
+    Ok(entry)
+}
+
+fn attribute(element: &BytesStart, name: &str) -> Option<String> {
+    element
+        .attributes()
+        .flatten()
+        .find(|a| a.key.local_name().as_ref() == name.as_bytes())
+        .and_then(|a| a.unescape_value().ok())
+        .map(|v| v.to_string())
+}
+// endregion - OPF
+
+/// Calibre identifiers are typed, `google` is the id of the external books API
+fn add_identifier(entry: &mut ImportEntry, type_field: &str, value: &str) {
+    match type_field {
+        "google" => entry.book_id = Some(value.trim().to_string()),
+        "isbn" => entry.isbns.extend(clean_isbn(value)),
+        _ => {}
+    }
+}
+
+/// Calibre stores ratings from 0 to 10, two for each star
+fn map_rating(rating: i64) -> Option<f32> {
+    if rating > 0 {
+        Some(rating as f32 / 2.0)
+    } else {
+        None
+    }
+}
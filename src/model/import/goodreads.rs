@@ -0,0 +1,87 @@
+use serde::Deserialize;
+
+use crate::model::books::{READING_STATUS_READ, READING_STATUS_READING, READING_STATUS_TO_READ};
+
+use super::{
+    clean_isbn, parse_date, parse_rating, read_rows, split_list, ImportEntry, ImportReport,
+};
+
+const DATE_FORMAT: &str = "%Y/%m/%d";
+
+// region - GoodreadsRow
+/// A row of the Goodreads library export. Only the columns we map are read
+#[derive(Debug, Deserialize)]
+struct GoodreadsRow {
+    #[serde(rename = "Title", default)]
+    title: String,
+    #[serde(rename = "Author", default)]
+    author: String,
+    #[serde(rename = "ISBN", default)]
+    isbn: String,
+    #[serde(rename = "ISBN13", default)]
+    isbn13: String,
+    #[serde(rename = "My Rating", default)]
+    my_rating: String,
+    #[serde(rename = "Date Read", default)]
+    date_read: String,
+    #[serde(rename = "Date Added", default)]
+    date_added: String,
+    #[serde(rename = "Bookshelves", default)]
+    bookshelves: String,
+    #[serde(rename = "Exclusive Shelf", default)]
+    exclusive_shelf: String,
+    #[serde(rename = "Private Notes", default)]
+    private_notes: String,
+}
+
+impl GoodreadsRow {
+    fn to_import_entry(&self, row: usize) -> ImportEntry {
+        // ISBN13 first, Goodreads wraps both in ="..."
+        let isbns = [&self.isbn13, &self.isbn]
+            .iter()
+            .filter_map(|isbn| clean_isbn(isbn))
+            .collect();
+
+        // the exclusive shelf is also listed in the bookshelves, keep only the custom ones
+        let exclusive_shelf = self.exclusive_shelf.trim();
+        let tags = split_list(&self.bookshelves, ',')
+            .into_iter()
+            .filter(|shelf| shelf != exclusive_shelf)
+            .collect();
+
+        ImportEntry {
+            row,
+            title: self.title.trim().to_string(),
+            author: self.author.trim().to_string(),
+            book_id: None,
+            isbns,
+            reading_status: map_exclusive_shelf(exclusive_shelf),
+            reading_start_date: parse_date(&self.date_added, DATE_FORMAT),
+            reading_end_date: parse_date(&self.date_read, DATE_FORMAT),
+            book_type: None,
+            tags,
+            rating: parse_rating(&self.my_rating),
+            notes: Some(self.private_notes.trim().to_string()).filter(|n| !n.is_empty()),
+        }
+    }
+}
+// endregion - GoodreadsRow
+
+/// Parses a Goodreads library export. Rows that cannot be read are recorded
+/// as skipped in the report
+pub fn parse(csv: &str, report: &mut ImportReport) -> Vec<ImportEntry> {
+    read_rows::<GoodreadsRow>(csv, b',', report)
+        .into_iter()
+        .map(|(row, record)| record.to_import_entry(row))
+        .collect()
+}
+
+fn map_exclusive_shelf(shelf: &str) -> Option<String> {
+    match shelf {
+        "read" => Some(READING_STATUS_READ.to_string()),
+        "currently-reading" => Some(READING_STATUS_READING.to_string()),
+        "to-read" => Some(READING_STATUS_TO_READ.to_string()),
+        "" => None,
+        other => Some(other.to_string()),
+    }
+}
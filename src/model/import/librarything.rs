@@ -0,0 +1,188 @@
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::model::books::{
+    BOOK_TYPE_AUDIOBOOK, BOOK_TYPE_EBOOK, BOOK_TYPE_PHYSICAL, READING_STATUS_READ,
+    READING_STATUS_READING, READING_STATUS_TO_READ,
+};
+
+use super::{
+    clean_isbn, parse_date, parse_rating, read_rows, split_list, ImportEntry, ImportReport,
+};
+
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+// region - LibraryThingRow
+/// A row of the LibraryThing tab-delimited export. Only the columns we map are read
+#[derive(Debug, Deserialize)]
+struct LibraryThingRow {
+    #[serde(rename = "Title", default)]
+    title: String,
+    #[serde(rename = "Primary Author", default)]
+    primary_author: String,
+    #[serde(rename = "ISBNs", default)]
+    isbns: String,
+    #[serde(rename = "ISBN", default)]
+    isbn: String,
+    #[serde(rename = "Rating", default)]
+    rating: String,
+    #[serde(rename = "Media", default)]
+    media: String,
+    #[serde(rename = "Date Started", default)]
+    date_started: String,
+    #[serde(rename = "Date Read", default)]
+    date_read: String,
+    #[serde(rename = "Tags", default)]
+    tags: String,
+    #[serde(rename = "Collections", default)]
+    collections: String,
+    #[serde(rename = "Private Comment", default)]
+    private_comment: String,
+}
+
+impl LibraryThingRow {
+    fn to_import_entry(&self, row: usize) -> ImportEntry {
+        // "ISBNs" lists every known ISBN, "ISBN" only the main one wrapped in brackets
+        let mut isbns: Vec<String> = split_list(&self.isbns, ',')
+            .iter()
+            .filter_map(|isbn| clean_isbn(isbn))
+            .collect();
+        if isbns.is_empty() {
+            isbns.extend(clean_isbn(&self.isbn));
+        }
+
+        let reading_end_date = parse_date(&self.date_read, DATE_FORMAT);
+
+        ImportEntry {
+            row,
+            title: self.title.trim().to_string(),
+            author: self.primary_author.trim().to_string(),
+            book_id: None,
+            isbns,
+            reading_status: map_collections(
+                &split_list(&self.collections, ','),
+                reading_end_date.is_some(),
+            ),
+            reading_start_date: parse_date(&self.date_started, DATE_FORMAT),
+            reading_end_date,
+            book_type: map_media(&self.media),
+            tags: split_list(&self.tags, ','),
+            rating: parse_rating(&self.rating),
+            notes: Some(self.private_comment.trim().to_string()).filter(|n| !n.is_empty()),
+        }
+    }
+}
+// endregion - LibraryThingRow
+
+/// Parses a LibraryThing export, either tab-delimited or JSON.
+/// Rows that cannot be read are recorded as skipped in the report
+pub fn parse(data: &str, report: &mut ImportReport) -> Vec<ImportEntry> {
+    if data.trim_start().starts_with('{') {
+        return parse_json(data, report);
+    }
+
+    read_rows::<LibraryThingRow>(data, b'\t', report)
+        .into_iter()
+        .map(|(row, record)| record.to_import_entry(row))
+        .collect()
+}
+
+/// The JSON export is an object with a key for each book
+fn parse_json(data: &str, report: &mut ImportReport) -> Vec<ImportEntry> {
+    let books = match serde_json::from_str::<serde_json::Map<String, Value>>(data) {
+        Ok(books) => books,
+        Err(e) => {
+            report.add_invalid_row(1, e.to_string());
+            return vec![];
+        }
+    };
+
+    books
+        .values()
+        .enumerate()
+        .map(|(i, book)| {
+            let reading_end_date = parse_date(&json_string(book, "datefinished"), DATE_FORMAT);
+
+            // the isbn field can be a string, a list or an object of strings
+            let isbns = match book.get("isbn") {
+                Some(Value::String(isbn)) => vec![isbn.clone()],
+                Some(Value::Array(isbns)) => json_strings(isbns.iter()),
+                Some(Value::Object(isbns)) => json_strings(isbns.values()),
+                _ => vec![],
+            }
+            .iter()
+            .chain(std::iter::once(&json_string(book, "originalisbn")))
+            .filter_map(|isbn| clean_isbn(isbn))
+            .collect();
+
+            let tags = match book.get("tags") {
+                Some(Value::Array(tags)) => json_strings(tags.iter()),
+                _ => vec![],
+            };
+            let collections = match book.get("collections") {
+                Some(Value::Array(collections)) => json_strings(collections.iter()),
+                _ => vec![],
+            };
+
+            ImportEntry {
+                row: i + 1,
+                title: json_string(book, "title"),
+                author: json_string(book, "primaryauthor"),
+                book_id: None,
+                isbns,
+                reading_status: map_collections(&collections, reading_end_date.is_some()),
+                reading_start_date: parse_date(&json_string(book, "datestarted"), DATE_FORMAT),
+                reading_end_date,
+                book_type: map_media(&json_string(book, "format")),
+                tags,
+                rating: parse_rating(&json_string(book, "rating")),
+                notes: Some(json_string(book, "privatecomment")).filter(|n| !n.is_empty()),
+            }
+        })
+        .collect()
+}
+
+/// Returns the value of the key as a string, numbers included
+fn json_string(book: &Value, key: &str) -> String {
+    match book.get(key) {
+        Some(Value::String(s)) => s.trim().to_string(),
+        Some(Value::Number(n)) => n.to_string(),
+        _ => "".to_string(),
+    }
+}
+
+fn json_strings<'a>(values: impl Iterator<Item = &'a Value>) -> Vec<String> {
+    values
+        .filter_map(|v| v.as_str())
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .collect()
+}
+
+/// LibraryThing has no reading status, it is derived from the default collections
+fn map_collections(collections: &[String], has_date_read: bool) -> Option<String> {
+    let in_collection = |name: &str| collections.iter().any(|c| c.eq_ignore_ascii_case(name));
+
+    if in_collection("Currently Reading") {
+        Some(READING_STATUS_READING.to_string())
+    } else if in_collection("Read But Unowned") || has_date_read {
+        Some(READING_STATUS_READ.to_string())
+    } else if in_collection("To Read") {
+        Some(READING_STATUS_TO_READ.to_string())
+    } else {
+        None
+    }
+}
+
+fn map_media(media: &str) -> Option<String> {
+    let media = media.to_lowercase();
+    if media.is_empty() {
+        None
+    } else if media.contains("ebook") {
+        Some(BOOK_TYPE_EBOOK.to_string())
+    } else if media.contains("audio") {
+        Some(BOOK_TYPE_AUDIOBOOK.to_string())
+    } else {
+        Some(BOOK_TYPE_PHYSICAL.to_string())
+    }
+}
@@ -0,0 +1,320 @@
+use std::collections::HashSet;
+
+use chrono::NaiveDate;
+use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::{
+    entities::books,
+    error::{Error, Result},
+    model::{
+        books::BookToSave,
+        books_api::search_volume_by_isbn,
+        ratings::CANONICAL_MAX_RATING,
+        volumes::{find_manual_volume_by_isbn, save_volume},
+        ModelManager,
+    },
+};
+
+pub mod calibre;
+pub mod goodreads;
+pub mod librarything;
+pub mod storygraph;
+
+// region - ImportEntry
+/// A single book read from an import file, already mapped to our fields
+/// but not yet resolved against the external books API
+#[derive(Debug, Default, Clone)]
+pub struct ImportEntry {
+    pub row: usize,
+    pub title: String,
+    pub author: String,
+    /// Id of the book in the external books API, when the import format has it
+    pub book_id: Option<String>,
+    /// ISBNs to try, in order of preference
+    pub isbns: Vec<String>,
+    pub reading_status: Option<String>,
+    pub reading_start_date: Option<NaiveDate>,
+    pub reading_end_date: Option<NaiveDate>,
+    pub book_type: Option<String>,
+    pub tags: Vec<String>,
+    pub rating: Option<f32>,
+    pub notes: Option<String>,
+}
+
+impl ImportEntry {
+    fn to_book_to_save(&self, book_id: String, user_id: String) -> BookToSave {
+        BookToSave {
+            book_id,
+            user_id,
+            reading_status: self.reading_status.clone(),
+            reading_start_date: self.reading_start_date,
+            reading_end_date: self.reading_end_date,
+            book_type: self.book_type.clone(),
+            tags: if self.tags.is_empty() {
+                None
+            } else {
+                Some(self.tags.clone())
+            },
+            rating: self.rating,
+            notes: self.notes.clone(),
+            library_id: None,
+            target_price: None,
+            custom_fields: None,
+            metadata_overrides: None,
+        }
+    }
+}
+// endregion - ImportEntry
+
+// region - ImportReport
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ImportRowStatus {
+    Imported,
+    Skipped,
+    Unmatched,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportRowResult {
+    pub row: usize,
+    pub title: String,
+    pub author: String,
+    pub status: ImportRowStatus,
+    pub book_id: Option<String>,
+    pub id: Option<String>,
+    pub reason: Option<String>,
+}
+
+/// Result of an import. In a dry run nothing is saved and the imported rows
+/// are the ones that would be imported
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportReport {
+    pub user_id: String,
+    pub dry_run: bool,
+    pub imported: usize,
+    pub skipped: usize,
+    pub unmatched: usize,
+    pub rows: Vec<ImportRowResult>,
+}
+
+impl ImportReport {
+    pub fn new(user_id: String, dry_run: bool) -> Self {
+        Self {
+            user_id,
+            dry_run,
+            ..Default::default()
+        }
+    }
+
+    pub fn add_row(&mut self, row: ImportRowResult) {
+        match row.status {
+            ImportRowStatus::Imported => self.imported += 1,
+            ImportRowStatus::Skipped => self.skipped += 1,
+            ImportRowStatus::Unmatched => self.unmatched += 1,
+        }
+        self.rows.push(row);
+    }
+
+    /// Records a row of the import file that could not be parsed
+    pub fn add_invalid_row(&mut self, row: usize, reason: String) {
+        self.add_row(ImportRowResult {
+            row,
+            title: "".to_string(),
+            author: "".to_string(),
+            status: ImportRowStatus::Skipped,
+            book_id: None,
+            id: None,
+            reason: Some(reason),
+        });
+    }
+}
+// endregion - ImportReport
+
+// region - ImportParams
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportParams {
+    pub dry_run: Option<bool>,
+}
+// endregion - ImportParams
+
+/// Resolves every entry against the external books API and saves the matched
+/// ones in the user's shelf. Books already present in the shelf, or appearing
+/// twice in the same file, are skipped.
+/// Nothing is saved if the report is a dry run
+pub async fn run_import(
+    model_manager: &ModelManager,
+    entries: Vec<ImportEntry>,
+    mut report: ImportReport,
+) -> Result<ImportReport> {
+    let user_id = report.user_id.clone();
+
+    let existing = books::Entity::find()
+        .filter(books::Column::UserId.eq(user_id.clone()))
+        .all(model_manager.db())
+        .await
+        .map_err(|e| Error::DbError(e.to_string()))?;
+    let mut book_ids: HashSet<String> = existing.into_iter().map(|b| b.book_id).collect();
+
+    for entry in entries {
+        let mut row = ImportRowResult {
+            row: entry.row,
+            title: entry.title.clone(),
+            author: entry.author.clone(),
+            status: ImportRowStatus::Unmatched,
+            book_id: None,
+            id: None,
+            reason: None,
+        };
+
+        let resolved = match entry.book_id.clone() {
+            Some(book_id) => Ok(Some(book_id)),
+            None => resolve_book_id(model_manager, &user_id, &entry.isbns).await,
+        };
+        let book_id = match resolved {
+            Ok(Some(book_id)) => book_id,
+            Ok(None) => {
+                row.reason = Some(if entry.isbns.is_empty() {
+                    "missing ISBN".to_string()
+                } else {
+                    "no book found for ISBN".to_string()
+                });
+                report.add_row(row);
+                continue;
+            }
+            Err(e) => {
+                row.reason = Some(e.as_ref().to_string());
+                report.add_row(row);
+                continue;
+            }
+        };
+        row.book_id = Some(book_id.clone());
+
+        if book_ids.contains(&book_id) {
+            row.status = ImportRowStatus::Skipped;
+            row.reason = Some("book already in the shelf".to_string());
+            report.add_row(row);
+            continue;
+        }
+
+        if !report.dry_run {
+            let book = entry
+                .to_book_to_save(book_id.clone(), user_id.clone())
+                .to_active_model()?
+                .insert(model_manager.db())
+                .await
+                .map_err(|e| Error::DbError(e.to_string()))?;
+            row.id = Some(book.id.to_string());
+        }
+
+        book_ids.insert(book_id);
+        row.status = ImportRowStatus::Imported;
+        report.add_row(row);
+    }
+
+    Ok(report)
+}
+
+/// Returns the id of the first volume matching one of the ISBNs, the manual
+/// volumes of the user first. The volume found is saved in the cache
+async fn resolve_book_id(
+    model_manager: &ModelManager,
+    user_id: &str,
+    isbns: &[String],
+) -> Result<Option<String>> {
+    if let Some(book_id) = find_manual_volume_by_isbn(model_manager, user_id, isbns).await? {
+        return Ok(Some(book_id));
+    }
+
+    for isbn in isbns {
+        if let Some(book_api) = search_volume_by_isbn(isbn).await? {
+            if let Some(book_id) = book_api.get_id() {
+                save_volume(model_manager, &book_id, &book_api).await?;
+                return Ok(Some(book_id));
+            }
+        }
+    }
+
+    Ok(None)
+}
+
+/// Reads the rows of a delimited file with a header. Rows that cannot be read
+/// are recorded as skipped in the report
+pub fn read_rows<T: DeserializeOwned>(
+    data: &str,
+    delimiter: u8,
+    report: &mut ImportReport,
+) -> Vec<(usize, T)> {
+    // tab-delimited exports are not quoted, quotes are part of the values
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .quoting(delimiter != b'\t')
+        .flexible(true)
+        .from_reader(data.as_bytes());
+
+    let mut rows = vec![];
+    for (i, record) in reader.deserialize::<T>().enumerate() {
+        // the header is row 1
+        let row = i + 2;
+        match record {
+            Ok(record) => rows.push((row, record)),
+            Err(e) => report.add_invalid_row(row, e.to_string()),
+        }
+    }
+
+    rows
+}
+
+/// Strips everything but digits and the ISBN-10 check character
+pub fn clean_isbn(isbn: &str) -> Option<String> {
+    let isbn: String = isbn
+        .chars()
+        .filter(|c| c.is_ascii_digit() || *c == 'X' || *c == 'x')
+        .map(|c| c.to_ascii_uppercase())
+        .collect();
+
+    match isbn.len() {
+        10 | 13 => Some(isbn),
+        _ => None,
+    }
+}
+
+/// Splits a list on the separator, trimming and dropping the empty values
+pub fn split_list(list: &str, separator: char) -> Vec<String> {
+    list.split(separator)
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .collect()
+}
+
+pub fn parse_date(date: &str, format: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(date.trim(), format).ok()
+}
+
+/// Parses a rating, 0 is used by the import formats for books without a rating.
+/// The import formats use 5 stars like the canonical scale
+pub fn parse_rating(rating: &str) -> Option<f32> {
+    match rating.trim().parse::<f32>() {
+        Ok(rating) if rating > 0.0 && rating as f64 <= CANONICAL_MAX_RATING => Some(rating),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_rating_skips_missing_ratings() {
+        assert_eq!(parse_rating(" 4 "), Some(4.0));
+        assert_eq!(parse_rating("3.5"), Some(3.5));
+        assert_eq!(parse_rating("0"), None);
+        assert_eq!(parse_rating(""), None);
+        assert_eq!(parse_rating("6"), None);
+        assert_eq!(parse_rating("n/a"), None);
+    }
+}
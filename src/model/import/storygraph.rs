@@ -0,0 +1,99 @@
+use serde::Deserialize;
+
+use crate::model::books::{
+    BOOK_TYPE_AUDIOBOOK, BOOK_TYPE_EBOOK, BOOK_TYPE_PHYSICAL, READING_STATUS_DID_NOT_FINISH,
+    READING_STATUS_READ, READING_STATUS_READING, READING_STATUS_TO_READ,
+};
+
+use super::{
+    clean_isbn, parse_date, parse_rating, read_rows, split_list, ImportEntry, ImportReport,
+};
+
+const DATE_FORMAT: &str = "%Y/%m/%d";
+
+// region - StoryGraphRow
+/// A row of the StoryGraph export. Only the columns we map are read
+#[derive(Debug, Deserialize)]
+struct StoryGraphRow {
+    #[serde(rename = "Title", default)]
+    title: String,
+    #[serde(rename = "Authors", default)]
+    authors: String,
+    #[serde(rename = "ISBN/UID", default)]
+    isbn: String,
+    #[serde(rename = "Format", default)]
+    format: String,
+    #[serde(rename = "Read Status", default)]
+    read_status: String,
+    #[serde(rename = "Date Added", default)]
+    date_added: String,
+    #[serde(rename = "Dates Read", default)]
+    dates_read: String,
+    #[serde(rename = "Last Date Read", default)]
+    last_date_read: String,
+    #[serde(rename = "Star Rating", default)]
+    star_rating: String,
+    #[serde(rename = "Review", default)]
+    review: String,
+    #[serde(rename = "Tags", default)]
+    tags: String,
+}
+
+impl StoryGraphRow {
+    fn to_import_entry(&self, row: usize) -> ImportEntry {
+        // "Dates Read" holds ranges like 2023/01/02-2023/01/15, the last one is the latest read
+        let last_range = split_list(&self.dates_read, ',').pop().unwrap_or_default();
+        let (start, end) = match last_range.split_once('-') {
+            Some((start, end)) => (parse_date(start, DATE_FORMAT), parse_date(end, DATE_FORMAT)),
+            None => (None, parse_date(&last_range, DATE_FORMAT)),
+        };
+
+        ImportEntry {
+            row,
+            title: self.title.trim().to_string(),
+            author: split_list(&self.authors, ',')
+                .into_iter()
+                .next()
+                .unwrap_or_default(),
+            book_id: None,
+            isbns: clean_isbn(&self.isbn).into_iter().collect(),
+            reading_status: map_read_status(self.read_status.trim()),
+            reading_start_date: start.or(parse_date(&self.date_added, DATE_FORMAT)),
+            reading_end_date: end.or(parse_date(&self.last_date_read, DATE_FORMAT)),
+            book_type: map_format(self.format.trim()),
+            tags: split_list(&self.tags, ','),
+            rating: parse_rating(&self.star_rating),
+            notes: Some(self.review.trim().to_string()).filter(|n| !n.is_empty()),
+        }
+    }
+}
+// endregion - StoryGraphRow
+
+/// Parses a StoryGraph CSV export. Rows that cannot be read are recorded
+/// as skipped in the report
+pub fn parse(csv: &str, report: &mut ImportReport) -> Vec<ImportEntry> {
+    read_rows::<StoryGraphRow>(csv, b',', report)
+        .into_iter()
+        .map(|(row, record)| record.to_import_entry(row))
+        .collect()
+}
+
+fn map_read_status(status: &str) -> Option<String> {
+    match status {
+        "read" => Some(READING_STATUS_READ.to_string()),
+        "currently-reading" => Some(READING_STATUS_READING.to_string()),
+        "to-read" => Some(READING_STATUS_TO_READ.to_string()),
+        "did-not-finish" => Some(READING_STATUS_DID_NOT_FINISH.to_string()),
+        "" => None,
+        other => Some(other.to_string()),
+    }
+}
+
+fn map_format(format: &str) -> Option<String> {
+    match format {
+        "digital" => Some(BOOK_TYPE_EBOOK.to_string()),
+        "audio" => Some(BOOK_TYPE_AUDIOBOOK.to_string()),
+        "paperback" | "hardcover" => Some(BOOK_TYPE_PHYSICAL.to_string()),
+        _ => None,
+    }
+}
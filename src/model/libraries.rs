@@ -0,0 +1,274 @@
+use chrono::Utc;
+use sea_orm::{ActiveValue, EntityTrait};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    entities::{
+        books,
+        libraries::{self, ActiveModel, Model},
+        library_members,
+    },
+    error::{Error, Result},
+};
+
+use super::ModelManager;
+
+// region - Visibility
+pub const VISIBILITY_PRIVATE: &str = "private";
+/// Anyone can read a public library and its books, without being a member
+pub const VISIBILITY_PUBLIC: &str = "public";
+
+fn validate_visibility(visibility: &str) -> Result<()> {
+    match visibility {
+        VISIBILITY_PRIVATE | VISIBILITY_PUBLIC => Ok(()),
+        _ => Err(Error::InvalidField(format!(
+            "invalid visibility: {}",
+            visibility
+        ))),
+    }
+}
+// endregion - Visibility
+
+// region - Roles
+pub const ROLE_OWNER: &str = "owner";
+pub const ROLE_EDITOR: &str = "editor";
+pub const ROLE_VIEWER: &str = "viewer";
+
+fn role_rank(role: &str) -> u8 {
+    match role {
+        ROLE_OWNER => 3,
+        ROLE_EDITOR => 2,
+        ROLE_VIEWER => 1,
+        _ => 0,
+    }
+}
+
+/// Checks if the role grants at least the permissions of the required one
+pub fn has_role(role: &str, required_role: &str) -> bool {
+    role_rank(role) >= role_rank(required_role)
+}
+
+/// Roles that can be given to the members, there is only one owner
+pub fn validate_member_role(role: &str) -> Result<()> {
+    match role {
+        ROLE_EDITOR | ROLE_VIEWER => Ok(()),
+        _ => Err(Error::InvalidField(format!("invalid role: {}", role))),
+    }
+}
+// endregion - Roles
+
+// region - LibraryId
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LibraryId {
+    pub id: String,
+}
+// endregion - LibraryId
+
+// region - LibraryToSave
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LibraryToSave {
+    pub owner_id: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub visibility: Option<String>,
+    pub sort_order: Option<i32>,
+}
+
+impl LibraryToSave {
+    pub fn to_active_model(&self) -> Result<ActiveModel> {
+        if self.name.trim().is_empty() {
+            return Err(Error::MissingFields("missing fields: name".to_string()));
+        }
+        let visibility = self
+            .visibility
+            .clone()
+            .unwrap_or(VISIBILITY_PRIVATE.to_string());
+        validate_visibility(&visibility)?;
+
+        let now = Utc::now().naive_utc();
+        Ok(ActiveModel {
+            id: ActiveValue::Set(Uuid::new_v4()),
+            created_at: ActiveValue::Set(now),
+            updated_at: ActiveValue::Set(now),
+            owner_id: ActiveValue::Set(self.owner_id.clone()),
+            name: ActiveValue::Set(self.name.trim().to_string()),
+            description: ActiveValue::Set(self.description.clone()),
+            visibility: ActiveValue::Set(visibility),
+            sort_order: ActiveValue::Set(self.sort_order.unwrap_or_default()),
+        })
+    }
+}
+// endregion - LibraryToSave
+
+// region - LibraryToUpdate
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LibraryToUpdate {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub visibility: Option<String>,
+    pub sort_order: Option<i32>,
+}
+
+impl LibraryToUpdate {
+    pub fn to_active_model(&self, db_library: Model) -> Result<ActiveModel> {
+        let mut library_to_update: ActiveModel = db_library.into();
+
+        library_to_update.updated_at = ActiveValue::Set(Utc::now().naive_utc());
+        // check if the optional fields are set and update the active model accordingly
+        if let Some(name) = self.name.clone() {
+            if name.trim().is_empty() {
+                return Err(Error::MissingFields("missing fields: name".to_string()));
+            }
+            library_to_update.name = ActiveValue::Set(name.trim().to_string());
+        };
+        if let Some(description) = self.description.clone() {
+            library_to_update.description = ActiveValue::Set(Some(description));
+        };
+        if let Some(visibility) = self.visibility.clone() {
+            validate_visibility(&visibility)?;
+            library_to_update.visibility = ActiveValue::Set(visibility);
+        };
+        if let Some(sort_order) = self.sort_order {
+            library_to_update.sort_order = ActiveValue::Set(sort_order);
+        };
+
+        Ok(library_to_update)
+    }
+}
+// endregion - LibraryToUpdate
+
+// region - Library
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Library {
+    pub id: String,
+    pub owner_id: String,
+    pub name: String,
+    pub description: String,
+    pub visibility: String,
+    pub sort_order: i32,
+    /// Role of the user of the call in the library
+    pub role: String,
+}
+
+impl Library {
+    pub fn from_db(library_db: Model, role: String) -> Self {
+        Self {
+            id: library_db.id.to_string(),
+            owner_id: library_db.owner_id,
+            name: library_db.name,
+            description: library_db.description.unwrap_or_default(),
+            visibility: library_db.visibility,
+            sort_order: library_db.sort_order,
+            role,
+        }
+    }
+}
+// endregion - Library
+
+pub fn parse_library_id(library_id: &str) -> Result<Uuid> {
+    Uuid::parse_str(library_id)
+        .map_err(|_| Error::InvalidField(format!("invalid library id: {}", library_id)))
+}
+
+/// Returns the role of the user in the library, if the user is a member
+pub async fn get_library_role(
+    model_manager: &ModelManager,
+    library: &Model,
+    user_id: &str,
+) -> Result<Option<String>> {
+    if library.owner_id == user_id {
+        return Ok(Some(ROLE_OWNER.to_string()));
+    }
+
+    let member = library_members::Entity::find_by_id((library.id, user_id.to_string()))
+        .one(model_manager.db())
+        .await
+        .map_err(|e| Error::DbError(e.to_string()))?;
+
+    Ok(member.map(|m| m.role))
+}
+
+/// Returns the library with the given id if the user has at least the required
+/// role in it. Anyone can read the public libraries, like their viewers
+pub async fn get_user_library(
+    model_manager: &ModelManager,
+    user_id: &str,
+    library_id: &str,
+    required_role: &str,
+) -> Result<Model> {
+    let library = libraries::Entity::find_by_id(parse_library_id(library_id)?)
+        .one(model_manager.db())
+        .await
+        .map_err(|e| Error::DbError(e.to_string()))?;
+
+    let library = match library {
+        None => return Err(Error::NotFound),
+        Some(library) => library,
+    };
+
+    match get_library_role(model_manager, &library, user_id).await? {
+        Some(role) if has_role(&role, required_role) => Ok(library),
+        None if library.visibility == VISIBILITY_PUBLIC && required_role == ROLE_VIEWER => {
+            Ok(library)
+        }
+        _ => Err(Error::Unathorized),
+    }
+}
+
+/// Checks if the user can act on the book with the required role: the user
+/// that saved the book can always, the members of its library depending on their role
+pub async fn can_access_book(
+    model_manager: &ModelManager,
+    book: &books::Model,
+    user_id: &str,
+    required_role: &str,
+) -> Result<bool> {
+    if book.user_id == user_id {
+        return Ok(true);
+    }
+
+    let library_id = match book.library_id {
+        Some(library_id) => library_id,
+        None => return Ok(false),
+    };
+
+    match get_user_library(
+        model_manager,
+        user_id,
+        &library_id.to_string(),
+        required_role,
+    )
+    .await
+    {
+        Ok(_) => Ok(true),
+        Err(Error::NotFound) | Err(Error::Unathorized) => Ok(false),
+        Err(e) => Err(e),
+    }
+}
+
+/// Checks that the user can save books in the library.
+/// An empty id removes the book from its library
+pub async fn validate_book_library(
+    model_manager: &ModelManager,
+    user_id: &str,
+    library_id: &Option<String>,
+) -> Result<()> {
+    let library_id = match library_id.as_deref() {
+        None | Some("") => return Ok(()),
+        Some(library_id) => library_id,
+    };
+
+    match get_user_library(model_manager, user_id, library_id, ROLE_EDITOR).await {
+        Ok(_) => Ok(()),
+        Err(Error::NotFound) | Err(Error::Unathorized) => Err(Error::InvalidField(format!(
+            "library not found: {}",
+            library_id
+        ))),
+        Err(e) => Err(e),
+    }
+}
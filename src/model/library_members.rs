@@ -0,0 +1,257 @@
+use chrono::{Duration, NaiveDateTime, Utc};
+use sea_orm::{
+    sea_query::Expr, ActiveModelTrait, ActiveValue, ColumnTrait, EntityTrait, QueryFilter,
+    TransactionTrait,
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    entities::{libraries, library_invites, library_members},
+    error::{Error, Result},
+};
+
+use super::{
+    libraries::{get_library_role, has_role, validate_member_role, ROLE_OWNER},
+    ModelManager,
+};
+
+/// Invites expire after 3 days unless requested otherwise, and at most after 30 days
+const INVITE_DEFAULT_HOURS: i64 = 72;
+const INVITE_MAX_HOURS: i64 = 720;
+/// Invites can be accepted once unless requested otherwise
+const INVITE_DEFAULT_USES: i32 = 1;
+const INVITE_MAX_USES: i32 = 100;
+
+// region - LibraryMember
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LibraryMember {
+    pub user_id: String,
+    pub role: String,
+    pub created_at: NaiveDateTime,
+}
+
+impl From<library_members::Model> for LibraryMember {
+    fn from(member_db: library_members::Model) -> Self {
+        Self {
+            user_id: member_db.user_id,
+            role: member_db.role,
+            created_at: member_db.created_at,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MemberToUpdate {
+    pub role: String,
+}
+// endregion - LibraryMember
+
+// region - LibraryInvite
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InviteToSave {
+    pub role: String,
+    pub expires_in_hours: Option<i64>,
+    /// Number of users that can accept the invite
+    pub max_uses: Option<i32>,
+}
+
+impl InviteToSave {
+    pub fn to_active_model(
+        &self,
+        library_id: Uuid,
+        created_by: String,
+    ) -> Result<library_invites::ActiveModel> {
+        validate_member_role(&self.role)?;
+
+        let hours = self
+            .expires_in_hours
+            .unwrap_or(INVITE_DEFAULT_HOURS)
+            .clamp(1, INVITE_MAX_HOURS);
+        let max_uses = self
+            .max_uses
+            .unwrap_or(INVITE_DEFAULT_USES)
+            .clamp(1, INVITE_MAX_USES);
+        let now = Utc::now().naive_utc();
+
+        Ok(library_invites::ActiveModel {
+            code: ActiveValue::Set(Uuid::new_v4().simple().to_string()),
+            library_id: ActiveValue::Set(library_id),
+            created_at: ActiveValue::Set(now),
+            created_by: ActiveValue::Set(created_by),
+            role: ActiveValue::Set(self.role.clone()),
+            expires_at: ActiveValue::Set(now + Duration::hours(hours)),
+            max_uses: ActiveValue::Set(max_uses),
+            uses: ActiveValue::Set(0),
+        })
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LibraryInvite {
+    pub code: String,
+    pub library_id: String,
+    pub created_by: String,
+    pub role: String,
+    pub expires_at: NaiveDateTime,
+    pub max_uses: i32,
+    /// Number of users that accepted the invite
+    pub uses: i32,
+}
+
+impl From<library_invites::Model> for LibraryInvite {
+    fn from(invite_db: library_invites::Model) -> Self {
+        Self {
+            code: invite_db.code,
+            library_id: invite_db.library_id.to_string(),
+            created_by: invite_db.created_by,
+            role: invite_db.role,
+            expires_at: invite_db.expires_at,
+            max_uses: invite_db.max_uses,
+            uses: invite_db.uses,
+        }
+    }
+}
+// endregion - LibraryInvite
+
+/// Adds the owner as member of a new library, in the same transaction of its creation
+pub async fn save_library_with_owner(
+    model_manager: &ModelManager,
+    library: libraries::ActiveModel,
+) -> Result<libraries::Model> {
+    let txn = model_manager
+        .db()
+        .begin()
+        .await
+        .map_err(|e| Error::DbError(e.to_string()))?;
+
+    let library = library
+        .insert(&txn)
+        .await
+        .map_err(|e| Error::DbError(e.to_string()))?;
+
+    let now = Utc::now().naive_utc();
+    library_members::ActiveModel {
+        library_id: ActiveValue::Set(library.id),
+        user_id: ActiveValue::Set(library.owner_id.clone()),
+        created_at: ActiveValue::Set(now),
+        updated_at: ActiveValue::Set(now),
+        role: ActiveValue::Set(ROLE_OWNER.to_string()),
+    }
+    .insert(&txn)
+    .await
+    .map_err(|e| Error::DbError(e.to_string()))?;
+
+    txn.commit()
+        .await
+        .map_err(|e| Error::DbError(e.to_string()))?;
+
+    Ok(library)
+}
+
+/// Makes the user a member of the library of the invite, with the role of the invite.
+/// Invites can be used by as many users as their max uses until they expire,
+/// members keep their role if it is higher than the one of the invite, without
+/// using the invite
+pub async fn accept_invite(
+    model_manager: &ModelManager,
+    code: &str,
+    user_id: &str,
+) -> Result<(libraries::Model, String)> {
+    let invite = library_invites::Entity::find_by_id(code.to_string())
+        .one(model_manager.db())
+        .await
+        .map_err(|e| Error::DbError(e.to_string()))?
+        .ok_or(Error::NotFound)?;
+
+    if invite.expires_at < Utc::now().naive_utc() {
+        return Err(Error::InvalidField("invite expired".to_string()));
+    }
+    if invite.uses >= invite.max_uses {
+        return Err(Error::InvalidField("invite already used".to_string()));
+    }
+
+    let library = libraries::Entity::find_by_id(invite.library_id)
+        .one(model_manager.db())
+        .await
+        .map_err(|e| Error::DbError(e.to_string()))?
+        .ok_or(Error::NotFound)?;
+
+    if let Some(role) = get_library_role(model_manager, &library, user_id).await? {
+        if has_role(&role, &invite.role) {
+            return Ok((library, role));
+        }
+    }
+
+    let txn = model_manager
+        .db()
+        .begin()
+        .await
+        .map_err(|e| Error::DbError(e.to_string()))?;
+
+    // the use is counted only if the invite was not used up in the meantime
+    let res = library_invites::Entity::update_many()
+        .col_expr(
+            library_invites::Column::Uses,
+            Expr::col(library_invites::Column::Uses).add(1),
+        )
+        .filter(library_invites::Column::Code.eq(invite.code.clone()))
+        .filter(
+            Expr::col(library_invites::Column::Uses)
+                .lt(Expr::col(library_invites::Column::MaxUses)),
+        )
+        .exec(&txn)
+        .await
+        .map_err(|e| Error::DbError(e.to_string()))?;
+    if res.rows_affected == 0 {
+        return Err(Error::InvalidField("invite already used".to_string()));
+    }
+
+    let now = Utc::now().naive_utc();
+    let member = library_members::ActiveModel {
+        library_id: ActiveValue::Set(library.id),
+        user_id: ActiveValue::Set(user_id.to_string()),
+        created_at: ActiveValue::Set(now),
+        updated_at: ActiveValue::Set(now),
+        role: ActiveValue::Set(invite.role.clone()),
+    };
+    library_members::Entity::insert(member)
+        .on_conflict(
+            sea_orm::sea_query::OnConflict::columns([
+                library_members::Column::LibraryId,
+                library_members::Column::UserId,
+            ])
+            .update_columns([
+                library_members::Column::Role,
+                library_members::Column::UpdatedAt,
+            ])
+            .to_owned(),
+        )
+        .exec(&txn)
+        .await
+        .map_err(|e| Error::DbError(e.to_string()))?;
+
+    txn.commit()
+        .await
+        .map_err(|e| Error::DbError(e.to_string()))?;
+
+    Ok((library, invite.role))
+}
+
+/// Returns the members of the library, owner included
+pub async fn get_library_members(
+    model_manager: &ModelManager,
+    library_id: Uuid,
+) -> Result<Vec<LibraryMember>> {
+    let members = library_members::Entity::find()
+        .filter(library_members::Column::LibraryId.eq(library_id))
+        .all(model_manager.db())
+        .await
+        .map_err(|e| Error::DbError(e.to_string()))?;
+
+    Ok(members.into_iter().map(LibraryMember::from).collect())
+}
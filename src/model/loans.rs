@@ -0,0 +1,318 @@
+use chrono::{NaiveDate, Utc};
+use sea_orm::{ActiveValue, ColumnTrait, Condition, EntityTrait, QueryFilter, QueryOrder, Select};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    entities::{
+        books,
+        loans::{self, ActiveModel, Model},
+    },
+    error::{Error, Result},
+};
+
+use super::{books::BookFull, volumes::get_volume, ModelManager};
+
+fn validate_dates(
+    lent_at: NaiveDate,
+    due_at: Option<NaiveDate>,
+    returned_at: Option<NaiveDate>,
+) -> Result<()> {
+    if due_at.is_some_and(|due_at| due_at < lent_at) {
+        return Err(Error::InvalidField("dueAt is before lentAt".to_string()));
+    }
+    if returned_at.is_some_and(|returned_at| returned_at < lent_at) {
+        return Err(Error::InvalidField(
+            "returnedAt is before lentAt".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+// region - LoanId
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LoanId {
+    pub id: String,
+}
+// endregion - LoanId
+
+// region - LoanToSave
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LoanToSave {
+    pub user_id: String,
+    /// Id of the book in the shelf
+    pub book_id: String,
+    pub borrower_name: String,
+    /// Email, phone number, anything to reach the borrower
+    pub borrower_contact: Option<String>,
+    /// Today if not set
+    pub lent_at: Option<NaiveDate>,
+    pub due_at: Option<NaiveDate>,
+}
+
+impl LoanToSave {
+    pub fn to_active_model(&self) -> Result<ActiveModel> {
+        if self.borrower_name.trim().is_empty() {
+            return Err(Error::MissingFields(
+                "missing fields: borrowerName".to_string(),
+            ));
+        }
+        let book_id = Uuid::parse_str(&self.book_id)
+            .map_err(|_| Error::InvalidField(format!("invalid book id: {}", self.book_id)))?;
+        let now = Utc::now().naive_utc();
+        let lent_at = self.lent_at.unwrap_or(now.date());
+        validate_dates(lent_at, self.due_at, None)?;
+
+        Ok(ActiveModel {
+            id: ActiveValue::Set(Uuid::new_v4()),
+            created_at: ActiveValue::Set(now),
+            updated_at: ActiveValue::Set(now),
+            book_id: ActiveValue::Set(book_id),
+            user_id: ActiveValue::Set(self.user_id.clone()),
+            borrower_name: ActiveValue::Set(self.borrower_name.trim().to_string()),
+            borrower_contact: ActiveValue::Set(
+                self.borrower_contact
+                    .clone()
+                    .filter(|c| !c.trim().is_empty()),
+            ),
+            lent_at: ActiveValue::Set(lent_at),
+            due_at: ActiveValue::Set(self.due_at),
+            returned_at: ActiveValue::Set(None),
+        })
+    }
+}
+// endregion - LoanToSave
+
+// region - LoanToUpdate
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LoanToUpdate {
+    pub borrower_name: Option<String>,
+    pub borrower_contact: Option<String>,
+    pub lent_at: Option<NaiveDate>,
+    pub due_at: Option<NaiveDate>,
+    /// Removes the due date, when dueAt is not set
+    pub clear_due_at: Option<bool>,
+}
+
+impl LoanToUpdate {
+    pub fn to_active_model(&self, db_loan: Model) -> Result<ActiveModel> {
+        let clear_due_at = self.clear_due_at.unwrap_or_default();
+        if clear_due_at && self.due_at.is_some() {
+            return Err(Error::InvalidField(
+                "dueAt cannot be set when clearing it".to_string(),
+            ));
+        }
+        let lent_at = self.lent_at.unwrap_or(db_loan.lent_at);
+        let due_at = match clear_due_at {
+            true => None,
+            false => self.due_at.or(db_loan.due_at),
+        };
+        validate_dates(lent_at, due_at, db_loan.returned_at)?;
+
+        let mut loan_to_update: ActiveModel = db_loan.into();
+
+        loan_to_update.updated_at = ActiveValue::Set(Utc::now().naive_utc());
+        // check if the optional fields are set and update the active model accordingly
+        if let Some(borrower_name) = self.borrower_name.clone() {
+            if borrower_name.trim().is_empty() {
+                return Err(Error::MissingFields(
+                    "missing fields: borrowerName".to_string(),
+                ));
+            }
+            loan_to_update.borrower_name = ActiveValue::Set(borrower_name.trim().to_string());
+        };
+        if let Some(borrower_contact) = self.borrower_contact.clone() {
+            loan_to_update.borrower_contact =
+                ActiveValue::Set(Some(borrower_contact).filter(|c| !c.trim().is_empty()));
+        };
+        if let Some(lent_at) = self.lent_at {
+            loan_to_update.lent_at = ActiveValue::Set(lent_at);
+        };
+        if let Some(due_at) = self.due_at {
+            loan_to_update.due_at = ActiveValue::Set(Some(due_at));
+        };
+        if clear_due_at {
+            loan_to_update.due_at = ActiveValue::Set(None);
+        };
+
+        Ok(loan_to_update)
+    }
+}
+// endregion - LoanToUpdate
+
+// region - LoanReturn
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct LoanReturn {
+    /// Today if not set
+    pub returned_at: Option<NaiveDate>,
+}
+
+impl LoanReturn {
+    pub fn to_active_model(&self, db_loan: Model) -> Result<ActiveModel> {
+        if db_loan.returned_at.is_some() {
+            return Err(Error::InvalidField(
+                "the book has already been returned".to_string(),
+            ));
+        }
+        let now = Utc::now().naive_utc();
+        let returned_at = self.returned_at.unwrap_or(now.date());
+        validate_dates(db_loan.lent_at, None, Some(returned_at))?;
+
+        let mut loan_to_update: ActiveModel = db_loan.into();
+        loan_to_update.updated_at = ActiveValue::Set(now);
+        loan_to_update.returned_at = ActiveValue::Set(Some(returned_at));
+
+        Ok(loan_to_update)
+    }
+}
+// endregion - LoanReturn
+
+// region - Loan
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Loan {
+    pub id: String,
+    /// Id of the book in the shelf
+    pub book_id: String,
+    pub user_id: String,
+    pub title: String,
+    pub authors: Vec<String>,
+    pub borrower_name: String,
+    pub borrower_contact: Option<String>,
+    pub lent_at: NaiveDate,
+    pub due_at: Option<NaiveDate>,
+    pub returned_at: Option<NaiveDate>,
+    /// Not returned and past its due date
+    pub overdue: bool,
+}
+
+impl From<Model> for Loan {
+    fn from(loan_db: Model) -> Self {
+        let today = Utc::now().date_naive();
+        Self {
+            id: loan_db.id.to_string(),
+            book_id: loan_db.book_id.to_string(),
+            user_id: loan_db.user_id,
+            title: "".to_string(),
+            authors: vec![],
+            borrower_name: loan_db.borrower_name,
+            borrower_contact: loan_db.borrower_contact,
+            lent_at: loan_db.lent_at,
+            due_at: loan_db.due_at,
+            returned_at: loan_db.returned_at,
+            overdue: loan_db.returned_at.is_none()
+                && loan_db.due_at.is_some_and(|due_at| due_at < today),
+        }
+    }
+}
+// endregion - Loan
+
+// region - LoansParams
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LoansParams {
+    /// Id of the book in the shelf
+    pub book_id: Option<String>,
+}
+// endregion - LoansParams
+
+/// Returns all the loans of the user, returned ones included, the newest first
+pub async fn get_user_loans(
+    model_manager: &ModelManager,
+    user_id: &str,
+    params: &LoansParams,
+) -> Result<Vec<Loan>> {
+    let mut query = loans::Entity::find().filter(loans::Column::UserId.eq(user_id));
+
+    if let Some(book_id) = params.book_id.as_deref().filter(|b| !b.is_empty()) {
+        let book_id = Uuid::parse_str(book_id)
+            .map_err(|_| Error::InvalidField(format!("invalid book id: {}", book_id)))?;
+        query = query.filter(loans::Column::BookId.eq(book_id));
+    }
+
+    find_loans(model_manager, query).await
+}
+
+/// Returns the books of the user currently lent out
+pub async fn get_current_loans(model_manager: &ModelManager, user_id: &str) -> Result<Vec<Loan>> {
+    let query = loans::Entity::find()
+        .filter(loans::Column::UserId.eq(user_id))
+        .filter(loans::Column::ReturnedAt.is_null());
+
+    find_loans(model_manager, query).await
+}
+
+/// Returns the books of the user not returned by their due date
+pub async fn get_overdue_loans(model_manager: &ModelManager, user_id: &str) -> Result<Vec<Loan>> {
+    let query = loans::Entity::find()
+        .filter(loans::Column::UserId.eq(user_id))
+        .filter(
+            Condition::all()
+                .add(loans::Column::ReturnedAt.is_null())
+                .add(loans::Column::DueAt.lt(Utc::now().date_naive())),
+        );
+
+    find_loans(model_manager, query).await
+}
+
+/// Returns the loans with the title and the authors of their books, the newest first
+async fn find_loans(
+    model_manager: &ModelManager,
+    query: Select<loans::Entity>,
+) -> Result<Vec<Loan>> {
+    let loans = query
+        .order_by_desc(loans::Column::LentAt)
+        .find_also_related(books::Entity)
+        .all(model_manager.db())
+        .await
+        .map_err(|e| Error::DbError(e.to_string()))?;
+
+    let mut res = Vec::with_capacity(loans.len());
+    for (loan_db, book) in loans {
+        let mut loan = Loan::from(loan_db);
+        if let Some(book) = book {
+            let book_api = get_volume(model_manager, &book.book_id).await?;
+            let book = BookFull::from_db_and_api(book, book_api)?;
+            loan.title = book.title;
+            loan.authors = book.authors;
+        }
+        res.push(loan);
+    }
+
+    Ok(res)
+}
+
+/// Returns the loan of the book not returned yet, if any
+pub async fn get_active_loan(model_manager: &ModelManager, book_id: Uuid) -> Result<Option<Model>> {
+    loans::Entity::find()
+        .filter(loans::Column::BookId.eq(book_id))
+        .filter(loans::Column::ReturnedAt.is_null())
+        .one(model_manager.db())
+        .await
+        .map_err(|e| Error::DbError(e.to_string()))
+}
+
+/// Returns the loan with the given id if it belongs to the user
+pub async fn get_user_loan(
+    model_manager: &ModelManager,
+    user_id: &str,
+    loan_id: &str,
+) -> Result<Model> {
+    let loan_id =
+        Uuid::parse_str(loan_id).map_err(|_| Error::ParseError("Invalid id".to_string()))?;
+
+    let loan = loans::Entity::find_by_id(loan_id)
+        .one(model_manager.db())
+        .await
+        .map_err(|e| Error::DbError(e.to_string()))?
+        .ok_or(Error::NotFound)?;
+
+    if loan.user_id != user_id {
+        return Err(Error::Unathorized);
+    }
+    Ok(loan)
+}
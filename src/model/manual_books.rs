@@ -0,0 +1,276 @@
+use sea_orm::{ColumnTrait, EntityTrait, ModelTrait, PaginatorTrait, QueryFilter};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    entities::{books, volumes::Model},
+    error::{Error, Result},
+};
+
+use super::{
+    books_api::{BooksApiResponse, ImageLinks, IndustryIdentifier, VolumeInfo},
+    covers::parse_cover_url,
+    import::clean_isbn,
+    volumes::MANUAL_VOLUME_PREFIX,
+    ModelManager,
+};
+
+/// Trims the value, an empty one is not stored
+fn optional_text(value: &str) -> Option<String> {
+    let value = value.trim();
+    if value.is_empty() {
+        None
+    } else {
+        Some(value.to_string())
+    }
+}
+
+/// Trims the values, dropping the empty ones
+fn text_list(values: &[String]) -> Option<Vec<String>> {
+    let values: Vec<String> = values.iter().filter_map(|v| optional_text(v)).collect();
+    if values.is_empty() {
+        None
+    } else {
+        Some(values)
+    }
+}
+
+fn validate_isbn(field: &str, isbn: &str, len: usize) -> Result<Option<IndustryIdentifier>> {
+    if isbn.trim().is_empty() {
+        return Ok(None);
+    }
+    match clean_isbn(isbn).filter(|isbn| isbn.len() == len) {
+        Some(isbn) => Ok(Some(IndustryIdentifier {
+            type_field: Some(format!("ISBN_{}", len)),
+            identifier: Some(isbn),
+        })),
+        None => Err(Error::InvalidField(format!("invalid {}: {}", field, isbn))),
+    }
+}
+
+// region - ManualBookId
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ManualBookId {
+    /// Id of the volume, used as the bookId of the books of the shelf
+    pub book_id: String,
+}
+// endregion - ManualBookId
+
+// region - ManualBookToSave
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ManualBookToSave {
+    pub user_id: String,
+    pub title: String,
+    pub authors: Option<Vec<String>>,
+    pub publisher: Option<String>,
+    pub published_date: Option<String>,
+    pub description: Option<String>,
+    pub isbn10: Option<String>,
+    pub isbn13: Option<String>,
+    pub page_count: Option<i64>,
+    pub categories: Option<Vec<String>>,
+    pub language: Option<String>,
+    /// Url of the cover image
+    pub cover: Option<String>,
+}
+
+impl ManualBookToSave {
+    /// Returns the id of the new volume and its data, stored like the
+    /// volumes of the external API
+    pub fn to_book_api(&self) -> Result<(String, BooksApiResponse)> {
+        let book = ManualBook {
+            book_id: format!("{}{}", MANUAL_VOLUME_PREFIX, Uuid::new_v4()),
+            user_id: self.user_id.clone(),
+            title: self.title.clone(),
+            authors: self.authors.clone().unwrap_or_default(),
+            publisher: self.publisher.clone().unwrap_or_default(),
+            published_date: self.published_date.clone().unwrap_or_default(),
+            description: self.description.clone().unwrap_or_default(),
+            isbn10: self.isbn10.clone().unwrap_or_default(),
+            isbn13: self.isbn13.clone().unwrap_or_default(),
+            page_count: self.page_count.unwrap_or_default(),
+            categories: self.categories.clone().unwrap_or_default(),
+            language: self.language.clone().unwrap_or_default(),
+            cover: self.cover.clone().unwrap_or_default(),
+        };
+
+        let book_api = book.to_book_api()?;
+        Ok((book.book_id, book_api))
+    }
+}
+// endregion - ManualBookToSave
+
+// region - ManualBookToUpdate
+/// Empty values remove the metadata, except for the title
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ManualBookToUpdate {
+    pub title: Option<String>,
+    pub authors: Option<Vec<String>>,
+    pub publisher: Option<String>,
+    pub published_date: Option<String>,
+    pub description: Option<String>,
+    pub isbn10: Option<String>,
+    pub isbn13: Option<String>,
+    pub page_count: Option<i64>,
+    pub categories: Option<Vec<String>>,
+    pub language: Option<String>,
+    pub cover: Option<String>,
+}
+
+impl ManualBookToUpdate {
+    pub fn to_book_api(&self, db_volume: Model) -> Result<BooksApiResponse> {
+        let mut book = ManualBook::from(db_volume);
+
+        // check if the optional fields are set and update the book accordingly
+        if let Some(title) = self.title.clone() {
+            book.title = title;
+        };
+        if let Some(authors) = self.authors.clone() {
+            book.authors = authors;
+        };
+        if let Some(publisher) = self.publisher.clone() {
+            book.publisher = publisher;
+        };
+        if let Some(published_date) = self.published_date.clone() {
+            book.published_date = published_date;
+        };
+        if let Some(description) = self.description.clone() {
+            book.description = description;
+        };
+        if let Some(isbn10) = self.isbn10.clone() {
+            book.isbn10 = isbn10;
+        };
+        if let Some(isbn13) = self.isbn13.clone() {
+            book.isbn13 = isbn13;
+        };
+        if let Some(page_count) = self.page_count {
+            book.page_count = page_count;
+        };
+        if let Some(categories) = self.categories.clone() {
+            book.categories = categories;
+        };
+        if let Some(language) = self.language.clone() {
+            book.language = language;
+        };
+        if let Some(cover) = self.cover.clone() {
+            book.cover = cover;
+        };
+
+        book.to_book_api()
+    }
+}
+// endregion - ManualBookToUpdate
+
+// region - ManualBook
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ManualBook {
+    pub book_id: String,
+    pub user_id: String,
+    pub title: String,
+    pub authors: Vec<String>,
+    pub publisher: String,
+    pub published_date: String,
+    pub description: String,
+    pub isbn10: String,
+    pub isbn13: String,
+    pub page_count: i64,
+    pub categories: Vec<String>,
+    pub language: String,
+    pub cover: String,
+}
+
+impl ManualBook {
+    fn to_book_api(&self) -> Result<BooksApiResponse> {
+        let title = match optional_text(&self.title) {
+            Some(title) => title,
+            None => return Err(Error::MissingFields("missing fields: title".to_string())),
+        };
+        if self.page_count < 0 {
+            return Err(Error::InvalidField(format!(
+                "invalid pageCount: {}",
+                self.page_count
+            )));
+        }
+        // the cover is downloaded by the server, only public web urls are accepted
+        let cover = optional_text(&self.cover)
+            .map(|cover| parse_cover_url(&cover).map(|_| cover))
+            .transpose()?;
+        let industry_identifiers: Vec<IndustryIdentifier> = [
+            validate_isbn("isbn10", &self.isbn10, 10)?,
+            validate_isbn("isbn13", &self.isbn13, 13)?,
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+
+        Ok(BooksApiResponse {
+            id: Some(self.book_id.clone()),
+            volume_info: Some(VolumeInfo {
+                title: Some(title),
+                authors: text_list(&self.authors),
+                publisher: optional_text(&self.publisher),
+                published_date: optional_text(&self.published_date),
+                description: optional_text(&self.description),
+                industry_identifiers: Some(industry_identifiers)
+                    .filter(|identifiers| !identifiers.is_empty()),
+                page_count: Some(self.page_count).filter(|page_count| *page_count > 0),
+                categories: text_list(&self.categories),
+                image_links: cover.map(|cover| ImageLinks {
+                    thumbnail: Some(cover),
+                    ..Default::default()
+                }),
+                language: optional_text(&self.language),
+                ..Default::default()
+            }),
+            ..Default::default()
+        })
+    }
+}
+
+impl From<Model> for ManualBook {
+    fn from(volume_db: Model) -> Self {
+        let book_api: BooksApiResponse = serde_json::from_value(volume_db.data).unwrap_or_default();
+        Self {
+            book_id: volume_db.book_id,
+            user_id: volume_db.user_id.unwrap_or_default(),
+            title: book_api.get_title(),
+            authors: book_api.get_authors(),
+            publisher: book_api.get_publisher(),
+            published_date: book_api.get_published_date(),
+            description: book_api.get_description(),
+            isbn10: book_api.get_isbn10(),
+            isbn13: book_api.get_isbn13(),
+            page_count: book_api.get_page_count(),
+            categories: book_api.get_categories(),
+            language: book_api.get_language(),
+            cover: book_api.get_cover(),
+        }
+    }
+}
+// endregion - ManualBook
+
+/// Deletes the manual volume, unless a book of a shelf still uses it
+pub async fn delete_manual_volume(model_manager: &ModelManager, volume: Model) -> Result<()> {
+    let books = books::Entity::find()
+        .filter(books::Column::BookId.eq(volume.book_id.clone()))
+        .count(model_manager.db())
+        .await
+        .map_err(|e| Error::DbError(e.to_string()))?;
+    if books > 0 {
+        return Err(Error::InvalidField(format!(
+            "manual book still in {} shelves: {}",
+            books, volume.book_id
+        )));
+    }
+
+    volume
+        .delete(model_manager.db())
+        .await
+        .map_err(|e| Error::DbError(e.to_string()))?;
+
+    Ok(())
+}
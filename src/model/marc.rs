@@ -0,0 +1,554 @@
+use chrono::Utc;
+use serde::Deserialize;
+
+use super::{
+    books::BookFull, books_api::BooksApiResponse, metadata_overrides::FIELD_TITLE,
+    volumes::is_manual_volume, xml::escape,
+};
+
+const FIELD_TERMINATOR: u8 = 0x1e;
+const RECORD_TERMINATOR: u8 = 0x1d;
+const SUBFIELD_DELIMITER: u8 = 0x1f;
+
+/// The length of a field is written with 4 digits in the directory,
+/// longer values (e.g. descriptions) are truncated
+const MAX_FIELD_LENGTH: usize = 9999;
+/// The length of a record is written with 5 digits in the leader
+const MAX_RECORD_LENGTH: usize = 99999;
+/// Leader, directory terminator and record terminator
+const RECORD_BASE_LENGTH: usize = 24 + 1 + 1;
+/// Tag, length and starting position of a field in the directory
+const DIRECTORY_ENTRY_LENGTH: usize = 12;
+
+// region - MarcFormat
+#[derive(Debug, Default, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MarcFormat {
+    #[default]
+    Marc21,
+    Marcxml,
+}
+
+impl MarcFormat {
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Self::Marc21 => "application/marc",
+            Self::Marcxml => "application/marcxml+xml",
+        }
+    }
+
+    pub fn file_name(&self, library_id: &str) -> String {
+        match self {
+            Self::Marc21 => format!("{}.mrc", library_id),
+            Self::Marcxml => format!("{}.xml", library_id),
+        }
+    }
+
+    /// Renders the records as a single file of the format
+    pub fn render(&self, records: &[MarcRecord]) -> Vec<u8> {
+        match self {
+            Self::Marc21 => records.iter().flat_map(|r| r.to_marc21()).collect(),
+            Self::Marcxml => {
+                let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+                xml.push_str("<collection xmlns=\"http://www.loc.gov/MARC21/slim\">\n");
+                for record in records {
+                    xml.push_str(&record.to_marcxml());
+                }
+                xml.push_str("</collection>\n");
+                xml.into_bytes()
+            }
+        }
+    }
+}
+// endregion - MarcFormat
+
+// region - MarcRecord
+#[derive(Debug, Clone)]
+enum MarcField {
+    Control {
+        tag: &'static str,
+        value: String,
+    },
+    Data {
+        tag: &'static str,
+        indicators: [char; 2],
+        subfields: Vec<(char, String)>,
+    },
+}
+
+impl MarcField {
+    fn data(tag: &'static str, indicators: [char; 2], subfields: Vec<(char, String)>) -> Self {
+        Self::Data {
+            tag,
+            indicators,
+            subfields,
+        }
+    }
+
+    fn tag(&self) -> &'static str {
+        match self {
+            Self::Control { tag, .. } | Self::Data { tag, .. } => tag,
+        }
+    }
+
+    /// Content of the field in the binary format, field terminator included
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![];
+        match self {
+            Self::Control { value, .. } => bytes.extend(value.as_bytes()),
+            Self::Data {
+                indicators,
+                subfields,
+                ..
+            } => {
+                bytes.extend(indicators.iter().collect::<String>().as_bytes());
+                for (code, value) in subfields {
+                    bytes.push(SUBFIELD_DELIMITER);
+                    bytes.extend(code.to_string().as_bytes());
+                    bytes.extend(value.as_bytes());
+                }
+            }
+        }
+        bytes.push(FIELD_TERMINATOR);
+        bytes
+    }
+}
+
+/// A bibliographic record of a book, built from its metadata with the
+/// overrides of the user
+#[derive(Debug, Clone)]
+pub struct MarcRecord {
+    fields: Vec<MarcField>,
+}
+
+impl MarcRecord {
+    /// The id of the book in the shelf is used as control number. The volume of
+    /// the external API only adds the subtitle, unless the title is overridden
+    pub fn from_book(book: &BookFull, book_api: &BooksApiResponse) -> Self {
+        let subtitle = match book.overridden_fields.iter().any(|f| f == FIELD_TITLE) {
+            true => None,
+            false => book_api
+                .volume_info
+                .as_ref()
+                .and_then(|v| v.subtitle.clone()),
+        };
+        let year: String = book.published_date.chars().take(4).collect();
+        let language = marc_language(&book.language);
+
+        let mut fields = vec![
+            MarcField::Control {
+                tag: "001",
+                value: book.id.clone(),
+            },
+            MarcField::Control {
+                tag: "008",
+                value: fixed_length_data(&year, language),
+            },
+        ];
+
+        for isbn in [&book.isbn13, &book.isbn10] {
+            if !isbn.is_empty() {
+                fields.push(MarcField::data(
+                    "020",
+                    [' ', ' '],
+                    vec![('a', isbn.clone())],
+                ));
+            }
+        }
+        if !is_manual_volume(&book.book_id) {
+            // 035 holds the id of the record in another system
+            fields.push(MarcField::data(
+                "035",
+                [' ', ' '],
+                vec![('a', format!("(GoogleBooks){}", book.book_id))],
+            ));
+        }
+        fields.push(MarcField::data(
+            "041",
+            ['0', ' '],
+            vec![('a', language.to_string())],
+        ));
+
+        let authors = &book.authors;
+        if let Some(author) = authors.first() {
+            fields.push(MarcField::data(
+                "100",
+                ['1', ' '],
+                vec![('a', invert_name(author))],
+            ));
+        }
+
+        let mut title = vec![('a', book.title.clone())];
+        if let Some(subtitle) = subtitle.filter(|s| !s.is_empty()) {
+            title.push(('b', subtitle));
+        }
+        if !authors.is_empty() {
+            title.push(('c', authors.join(", ")));
+        }
+        let title_indicator = if authors.is_empty() { '0' } else { '1' };
+        fields.push(MarcField::data("245", [title_indicator, '0'], title));
+
+        let mut publication = vec![];
+        if !book.publisher.is_empty() {
+            publication.push(('b', book.publisher.clone()));
+        }
+        if !year.is_empty() {
+            publication.push(('c', year.clone()));
+        }
+        if !publication.is_empty() {
+            fields.push(MarcField::data("264", [' ', '1'], publication));
+        }
+
+        if book.page_count > 0 {
+            fields.push(MarcField::data(
+                "300",
+                [' ', ' '],
+                vec![('a', format!("{} pages", book.page_count))],
+            ));
+        }
+
+        if !book.description.is_empty() {
+            fields.push(MarcField::data(
+                "520",
+                [' ', ' '],
+                vec![('a', book.description.clone())],
+            ));
+        }
+
+        for category in &book.categories {
+            fields.push(MarcField::data(
+                "650",
+                [' ', '4'],
+                vec![('a', category.clone())],
+            ));
+        }
+        for author in authors.iter().skip(1) {
+            fields.push(MarcField::data(
+                "700",
+                ['1', ' '],
+                vec![('a', invert_name(author))],
+            ));
+        }
+
+        Self {
+            fields: fit_record(fields.into_iter().map(clean_field).collect()),
+        }
+    }
+
+    /// Encodes the record in the ISO 2709 binary format
+    pub fn to_marc21(&self) -> Vec<u8> {
+        let mut directory = String::new();
+        let mut data = vec![];
+        for field in &self.fields {
+            let bytes = field.to_bytes();
+            directory.push_str(&format!(
+                "{}{:04}{:05}",
+                field.tag(),
+                bytes.len(),
+                data.len()
+            ));
+            data.extend(bytes);
+        }
+
+        // leader and directory, followed by its terminator
+        let base_address = 24 + directory.len() + 1;
+        let record_length = base_address + data.len() + 1;
+
+        let mut record = leader(record_length, base_address).into_bytes();
+        record.extend(directory.as_bytes());
+        record.push(FIELD_TERMINATOR);
+        record.extend(data);
+        record.push(RECORD_TERMINATOR);
+        record
+    }
+
+    pub fn to_marcxml(&self) -> String {
+        let binary_length = self.to_marc21().len();
+        let base_address = binary_length - self.fields_length() - 1;
+
+        let mut xml = String::from("  <record>\n");
+        xml.push_str(&format!(
+            "    <leader>{}</leader>\n",
+            leader(binary_length, base_address)
+        ));
+        for field in &self.fields {
+            match field {
+                MarcField::Control { tag, value } => xml.push_str(&format!(
+                    "    <controlfield tag=\"{}\">{}</controlfield>\n",
+                    tag,
+                    escape(value)
+                )),
+                MarcField::Data {
+                    tag,
+                    indicators,
+                    subfields,
+                } => {
+                    xml.push_str(&format!(
+                        "    <datafield tag=\"{}\" ind1=\"{}\" ind2=\"{}\">\n",
+                        tag, indicators[0], indicators[1]
+                    ));
+                    for (code, value) in subfields {
+                        xml.push_str(&format!(
+                            "      <subfield code=\"{}\">{}</subfield>\n",
+                            code,
+                            escape(value)
+                        ));
+                    }
+                    xml.push_str("    </datafield>\n");
+                }
+            }
+        }
+        xml.push_str("  </record>\n");
+        xml
+    }
+
+    fn fields_length(&self) -> usize {
+        self.fields.iter().map(|f| f.to_bytes().len()).sum()
+    }
+}
+// endregion - MarcRecord
+
+/// Leader of a record of language material, monograph, Unicode, minimal level
+fn leader(record_length: usize, base_address: usize) -> String {
+    format!("{:05}nam a22{:05}7  4500", record_length, base_address)
+}
+
+/// Field 008, the 40 positions of fixed-length data of books
+fn fixed_length_data(year: &str, language: &str) -> String {
+    let date_entered = Utc::now().format("%y%m%d");
+    let (date_type, date) = if year.len() == 4 {
+        ('s', year.to_string())
+    } else {
+        ('n', "uuuu".to_string())
+    };
+
+    // place of publication unknown, book specific positions not coded
+    format!(
+        "{}{}{}    xx {}{} d",
+        date_entered,
+        date_type,
+        date,
+        "|".repeat(17),
+        language
+    )
+}
+
+/// Removes the MARC delimiters and truncates the values so that the field,
+/// terminator included, fits in its length
+fn clean_field(field: MarcField) -> MarcField {
+    let clean = |value: String, max_length: usize| -> String {
+        let mut value: String = value
+            .chars()
+            .filter(|c| !matches!(*c as u32, 0x1d..=0x1f))
+            .collect();
+        if value.len() > max_length {
+            let mut end = max_length;
+            while !value.is_char_boundary(end) {
+                end -= 1;
+            }
+            value.truncate(end);
+        }
+        value
+    };
+
+    match field {
+        MarcField::Control { tag, value } => MarcField::Control {
+            tag,
+            value: clean(value, MAX_FIELD_LENGTH - 1),
+        },
+        MarcField::Data {
+            tag,
+            indicators,
+            subfields,
+        } => {
+            // indicators and terminator, then the delimiter and code of each subfield
+            let mut available = MAX_FIELD_LENGTH - 2 - 1;
+            let mut cleaned = vec![];
+            for (code, value) in subfields {
+                if available <= 2 {
+                    break;
+                }
+                let value = clean(value, available - 2);
+                available -= 2 + value.len();
+                cleaned.push((code, value));
+            }
+            MarcField::Data {
+                tag,
+                indicators,
+                subfields: cleaned,
+            }
+        }
+    }
+}
+
+/// Drops the last fields while the record is longer than its maximum length
+fn fit_record(mut fields: Vec<MarcField>) -> Vec<MarcField> {
+    let length = |fields: &[MarcField]| -> usize {
+        RECORD_BASE_LENGTH
+            + fields
+                .iter()
+                .map(|f| DIRECTORY_ENTRY_LENGTH + f.to_bytes().len())
+                .sum::<usize>()
+    };
+    while length(&fields) > MAX_RECORD_LENGTH {
+        fields.pop();
+    }
+    fields
+}
+
+/// "Frank Herbert" becomes "Herbert, Frank"
+fn invert_name(name: &str) -> String {
+    match name.trim().rsplit_once(' ') {
+        Some((first, last)) => format!("{}, {}", last, first),
+        None => name.trim().to_string(),
+    }
+}
+
+/// The external API uses ISO 639-1 codes, MARC uses the MARC language codes
+fn marc_language(language: &str) -> &'static str {
+    match language {
+        "ar" => "ara",
+        "ca" => "cat",
+        "cs" => "cze",
+        "da" => "dan",
+        "de" => "ger",
+        "el" => "gre",
+        "en" => "eng",
+        "es" => "spa",
+        "fi" => "fin",
+        "fr" => "fre",
+        "he" | "iw" => "heb",
+        "hi" => "hin",
+        "hu" => "hun",
+        "it" => "ita",
+        "ja" => "jpn",
+        "ko" => "kor",
+        "la" => "lat",
+        "nl" => "dut",
+        "no" | "nb" | "nn" => "nor",
+        "pl" => "pol",
+        "pt" => "por",
+        "ro" => "rum",
+        "ru" => "rus",
+        "sv" => "swe",
+        "tr" => "tur",
+        "uk" => "ukr",
+        "zh" | "zh-CN" | "zh-TW" => "chi",
+        _ => "und",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds the record like `from_book`, from the fields
+    fn record(fields: Vec<MarcField>) -> MarcRecord {
+        MarcRecord {
+            fields: fit_record(fields.into_iter().map(clean_field).collect()),
+        }
+    }
+
+    fn number(bytes: &[u8]) -> usize {
+        std::str::from_utf8(bytes).unwrap().parse().unwrap()
+    }
+
+    /// Checks the lengths of the leader and the directory against the bytes of
+    /// the record, returning the tags of the fields
+    fn check_marc21(bytes: &[u8]) -> Vec<String> {
+        assert_eq!(number(&bytes[0..5]), bytes.len());
+        assert_eq!(bytes.last(), Some(&RECORD_TERMINATOR));
+
+        let base_address = number(&bytes[12..17]);
+        assert_eq!(bytes[base_address - 1], FIELD_TERMINATOR);
+        let directory = &bytes[24..base_address - 1];
+        assert_eq!(directory.len() % DIRECTORY_ENTRY_LENGTH, 0);
+
+        let mut tags = vec![];
+        let mut next_start = 0;
+        for entry in directory.chunks(DIRECTORY_ENTRY_LENGTH) {
+            let length = number(&entry[3..7]);
+            let start = number(&entry[7..12]);
+            assert_eq!(start, next_start);
+            assert_eq!(bytes[base_address + start + length - 1], FIELD_TERMINATOR);
+            next_start = start + length;
+            tags.push(String::from_utf8(entry[0..3].to_vec()).unwrap());
+        }
+        assert_eq!(base_address + next_start + 1, bytes.len());
+        tags
+    }
+
+    #[test]
+    fn leader_has_24_positions() {
+        let leader = leader(1234, 49);
+        assert_eq!(leader.len(), 24);
+        assert_eq!(leader, "01234nam a22000497  4500");
+    }
+
+    #[test]
+    fn to_marc21_writes_the_lengths_of_the_fields() {
+        let record = record(vec![
+            MarcField::Control {
+                tag: "001",
+                value: "book-1".to_string(),
+            },
+            MarcField::data(
+                "245",
+                ['1', '0'],
+                vec![
+                    ('a', "Cien años de soledad".to_string()),
+                    ('c', "Gabriel García Márquez".to_string()),
+                ],
+            ),
+        ]);
+        let bytes = record.to_marc21();
+
+        assert_eq!(check_marc21(&bytes), ["001", "245"]);
+        // the lengths are in bytes, not characters
+        assert_eq!(&bytes[24..36], b"001000700000");
+        assert_eq!(&bytes[36..48], b"245005200007");
+    }
+
+    #[test]
+    fn clean_field_truncates_long_values() {
+        // multi-byte characters, the truncation has to keep them whole
+        let description = "é".repeat(MAX_FIELD_LENGTH);
+        let record = record(vec![
+            MarcField::data("520", [' ', ' '], vec![('a', description)]),
+            MarcField::data("650", [' ', '4'], vec![('a', "Fiction\u{1e}".to_string())]),
+        ]);
+        let bytes = record.to_marc21();
+
+        assert_eq!(check_marc21(&bytes), ["520", "650"]);
+        assert!(number(&bytes[27..31]) <= MAX_FIELD_LENGTH);
+        assert!(bytes.ends_with(b"\x1faFiction\x1e\x1d"));
+    }
+
+    #[test]
+    fn fit_record_drops_the_fields_over_the_record_length() {
+        let fields = (0..20)
+            .map(|_| MarcField::data("520", [' ', ' '], vec![('a', "a".repeat(MAX_FIELD_LENGTH))]))
+            .collect();
+        let bytes = record(fields).to_marc21();
+
+        assert!(bytes.len() <= MAX_RECORD_LENGTH);
+        assert_eq!(check_marc21(&bytes).len(), 9);
+    }
+
+    #[test]
+    fn to_marcxml_uses_the_leader_of_the_binary_record() {
+        let record = record(vec![MarcField::Control {
+            tag: "001",
+            value: "book-1".to_string(),
+        }]);
+        let bytes = record.to_marc21();
+        let xml = record.to_marcxml();
+
+        let leader = std::str::from_utf8(&bytes[0..24]).unwrap();
+        assert!(
+            xml.contains(&format!("<leader>{}</leader>", leader)),
+            "{}",
+            xml
+        );
+    }
+}
@@ -0,0 +1,266 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::error::{Error, Result};
+
+use super::{books::BookFull, import::clean_isbn};
+
+// region - OverridableField
+pub const FIELD_TITLE: &str = "title";
+pub const FIELD_AUTHORS: &str = "authors";
+pub const FIELD_PUBLISHER: &str = "publisher";
+pub const FIELD_PUBLISHED_DATE: &str = "publishedDate";
+pub const FIELD_DESCRIPTION: &str = "description";
+pub const FIELD_ISBN10: &str = "isbn10";
+pub const FIELD_ISBN13: &str = "isbn13";
+pub const FIELD_PAGE_COUNT: &str = "pageCount";
+pub const FIELD_CATEGORIES: &str = "categories";
+pub const FIELD_LANGUAGE: &str = "language";
+pub const FIELD_COVER: &str = "cover";
+
+/// Metadata fields of BookFull read from the external API
+const OVERRIDABLE_FIELDS: [&str; 11] = [
+    FIELD_TITLE,
+    FIELD_AUTHORS,
+    FIELD_PUBLISHER,
+    FIELD_PUBLISHED_DATE,
+    FIELD_DESCRIPTION,
+    FIELD_ISBN10,
+    FIELD_ISBN13,
+    FIELD_PAGE_COUNT,
+    FIELD_CATEGORIES,
+    FIELD_LANGUAGE,
+    FIELD_COVER,
+];
+// endregion - OverridableField
+
+// region - ResetOverridesParams
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResetOverridesParams {
+    /// Comma separated fields to reset, like `title,cover`. All of them if not set
+    pub fields: Option<String>,
+}
+// endregion - ResetOverridesParams
+
+/// Checks the value against the type of the metadata field of BookFull,
+/// returning it as stored in the book
+fn validate_override(field: &str, value: &Value) -> Result<Value> {
+    let invalid = || {
+        Error::InvalidField(format!(
+            "invalid value for the metadata field {}: {}",
+            field, value
+        ))
+    };
+
+    match (field, value) {
+        (FIELD_TITLE, Value::String(title)) if !title.trim().is_empty() => {
+            Ok(Value::String(title.trim().to_string()))
+        }
+        (FIELD_TITLE, _) => Err(invalid()),
+        (
+            FIELD_PUBLISHER | FIELD_PUBLISHED_DATE | FIELD_DESCRIPTION | FIELD_LANGUAGE
+            | FIELD_COVER,
+            Value::String(text),
+        ) => Ok(Value::String(text.trim().to_string())),
+        // an empty ISBN hides the wrong one of the external API
+        (FIELD_ISBN10 | FIELD_ISBN13, Value::String(isbn)) if isbn.trim().is_empty() => {
+            Ok(Value::String("".to_string()))
+        }
+        (FIELD_ISBN10, Value::String(isbn)) => clean_isbn(isbn)
+            .filter(|isbn| isbn.len() == 10)
+            .map(Value::String)
+            .ok_or_else(invalid),
+        (FIELD_ISBN13, Value::String(isbn)) => clean_isbn(isbn)
+            .filter(|isbn| isbn.len() == 13)
+            .map(Value::String)
+            .ok_or_else(invalid),
+        (FIELD_PAGE_COUNT, Value::Number(page_count)) => page_count
+            .as_i64()
+            .filter(|page_count| *page_count >= 0)
+            .map(Value::from)
+            .ok_or_else(invalid),
+        (FIELD_AUTHORS | FIELD_CATEGORIES, Value::Array(values)) => {
+            let mut list = vec![];
+            for value in values {
+                match value {
+                    Value::String(v) if !v.trim().is_empty() => {
+                        list.push(Value::String(v.trim().to_string()))
+                    }
+                    Value::String(_) => {}
+                    _ => return Err(invalid()),
+                }
+            }
+            Ok(Value::Array(list))
+        }
+        _ => Err(invalid()),
+    }
+}
+
+fn validate_field(field: &str) -> Result<()> {
+    if !OVERRIDABLE_FIELDS.contains(&field) {
+        return Err(Error::InvalidField(format!(
+            "unknown metadata field: {}",
+            field
+        )));
+    }
+    Ok(())
+}
+
+/// Validates the metadata overrides of a book, keyed by the name of the field
+/// in BookFull. Null values are kept, they reset the field when updating a book
+pub fn validate_metadata_overrides(values: &Map<String, Value>) -> Result<Map<String, Value>> {
+    let mut valid_values = Map::new();
+    for (field, value) in values {
+        validate_field(field)?;
+        let value = match value {
+            Value::Null => Value::Null,
+            value => validate_override(field, value)?,
+        };
+        valid_values.insert(field.clone(), value);
+    }
+    Ok(valid_values)
+}
+
+/// Removes the given overrides of the book, all of them if no field is given
+pub fn reset_metadata_overrides(
+    overrides: Map<String, Value>,
+    params: &ResetOverridesParams,
+) -> Result<Map<String, Value>> {
+    let fields: Vec<&str> = match params.fields.as_deref() {
+        Some(fields) => fields
+            .split(',')
+            .map(|f| f.trim())
+            .filter(|f| !f.is_empty())
+            .collect(),
+        None => return Ok(Map::new()),
+    };
+
+    let mut overrides = overrides;
+    for field in fields {
+        validate_field(field)?;
+        overrides.remove(field);
+    }
+    Ok(overrides)
+}
+
+fn string_list(value: &Value) -> Vec<String> {
+    value
+        .as_array()
+        .map(|values| {
+            values
+                .iter()
+                .filter_map(|v| v.as_str().map(str::to_string))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Layers the overrides of the user over the metadata of the external API,
+/// listing the fields replaced in the book
+pub fn apply_metadata_overrides(book: &mut BookFull, overrides: &Map<String, Value>) {
+    for (field, value) in overrides {
+        let text = || value.as_str().unwrap_or_default().to_string();
+        match field.as_str() {
+            FIELD_TITLE => book.title = text(),
+            FIELD_AUTHORS => book.authors = string_list(value),
+            FIELD_PUBLISHER => book.publisher = text(),
+            FIELD_PUBLISHED_DATE => book.published_date = text(),
+            FIELD_DESCRIPTION => book.description = text(),
+            FIELD_ISBN10 => book.isbn10 = text(),
+            FIELD_ISBN13 => book.isbn13 = text(),
+            FIELD_PAGE_COUNT => book.page_count = value.as_i64().unwrap_or_default(),
+            FIELD_CATEGORIES => book.categories = string_list(value),
+            FIELD_LANGUAGE => book.language = text(),
+            FIELD_COVER => book.cover = text(),
+            // fields no longer supported are ignored
+            _ => continue,
+        }
+        book.overridden_fields.push(field.clone());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn overrides(value: Value) -> Map<String, Value> {
+        value.as_object().unwrap().clone()
+    }
+
+    #[test]
+    fn validate_metadata_overrides_cleans_the_values() {
+        let valid = validate_metadata_overrides(&overrides(json!({
+            "title": "  Dune ",
+            "authors": [" Frank Herbert ", "", "Brian Herbert"],
+            "isbn10": "0-441-17271-7",
+            "isbn13": "978-0-441-17271-9",
+            "pageCount": 412,
+            "description": "",
+        })))
+        .unwrap();
+
+        assert_eq!(
+            Value::Object(valid),
+            json!({
+                "title": "Dune",
+                "authors": ["Frank Herbert", "Brian Herbert"],
+                "isbn10": "0441172717",
+                "isbn13": "9780441172719",
+                "pageCount": 412,
+                "description": "",
+            })
+        );
+    }
+
+    #[test]
+    fn validate_metadata_overrides_keeps_null_and_empty_isbn() {
+        let values = overrides(json!({ "cover": null, "isbn13": " " }));
+        assert_eq!(
+            Value::Object(validate_metadata_overrides(&values).unwrap()),
+            json!({ "cover": null, "isbn13": "" })
+        );
+    }
+
+    #[test]
+    fn validate_metadata_overrides_rejects_invalid_values() {
+        for invalid in [
+            json!({ "title": " " }),
+            json!({ "title": 1 }),
+            json!({ "authors": "Frank Herbert" }),
+            json!({ "categories": [1] }),
+            json!({ "isbn10": "9780441172719" }),
+            json!({ "isbn13": "123" }),
+            json!({ "pageCount": -1 }),
+            json!({ "pageCount": 1.5 }),
+            json!({ "rating": 5 }),
+        ] {
+            assert!(
+                matches!(
+                    validate_metadata_overrides(&overrides(invalid.clone())),
+                    Err(Error::InvalidField(_))
+                ),
+                "{} was accepted",
+                invalid
+            );
+        }
+    }
+
+    #[test]
+    fn reset_metadata_overrides_removes_the_given_fields() {
+        let values = overrides(json!({ "title": "Dune", "cover": "", "pageCount": 412 }));
+        let params = |fields: Option<&str>| ResetOverridesParams {
+            fields: fields.map(str::to_string),
+        };
+
+        let reset =
+            reset_metadata_overrides(values.clone(), &params(Some("title, cover"))).unwrap();
+        assert_eq!(Value::Object(reset), json!({ "pageCount": 412 }));
+        assert!(reset_metadata_overrides(values.clone(), &params(None))
+            .unwrap()
+            .is_empty());
+        assert!(reset_metadata_overrides(values, &params(Some("rating"))).is_err());
+    }
+}
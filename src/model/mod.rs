@@ -2,8 +2,32 @@ use crate::{db::connect_to_db, error::Result};
 use sea_orm::DatabaseConnection;
 use tracing::info;
 
+pub mod authors;
 pub mod books;
 pub mod books_api;
+pub mod citation;
+pub mod covers;
+pub mod custom_fields;
+pub mod export;
+pub mod highlights;
+pub mod import;
+pub mod libraries;
+pub mod library_members;
+pub mod loans;
+pub mod manual_books;
+pub mod marc;
+pub mod metadata_overrides;
+pub mod opds;
+pub mod preferences;
+pub mod ratings;
+pub mod reviews;
+pub mod series;
+pub mod smart_shelves;
+pub mod tags;
+pub mod volumes;
+pub mod wishlist;
+pub mod works;
+pub mod xml;
 
 #[derive(Clone)]
 pub struct ModelManager {
@@ -0,0 +1,323 @@
+use chrono::Utc;
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+use serde_json::{json, Value};
+
+use super::{
+    books::{BookFull, UserBooks},
+    xml::escape,
+};
+
+pub const OPDS_ATOM_NAVIGATION: &str = "application/atom+xml;profile=opds-catalog;kind=navigation";
+pub const OPDS_ATOM_ACQUISITION: &str =
+    "application/atom+xml;profile=opds-catalog;kind=acquisition";
+pub const OPDS_JSON: &str = "application/opds+json";
+
+const REL_ACQUISITION_BUY: &str = "http://opds-spec.org/acquisition/buy";
+const REL_ACQUISITION_SAMPLE: &str = "http://opds-spec.org/acquisition/sample";
+/// Link to another representation of the publication, not to the book itself
+const REL_ALTERNATE: &str = "alternate";
+/// Books of the shelves of the users in the API
+const BOOKS_PATH: &str = "/api/v0/books";
+
+// region - Feed
+/// An entry of a navigation feed, pointing to another feed of the catalog
+#[derive(Debug)]
+pub struct NavigationEntry {
+    pub title: String,
+    /// Path of the feed relative to the root of the catalog
+    pub path: String,
+    /// Number of books in the feed, when it is an acquisition feed
+    pub count: Option<usize>,
+}
+
+impl NavigationEntry {
+    /// Builds an entry from the path segments, which are percent-encoded
+    pub fn new(title: String, segments: &[&str], count: Option<usize>) -> Self {
+        let path = segments
+            .iter()
+            .map(|s| utf8_percent_encode(s, NON_ALPHANUMERIC).to_string())
+            .collect::<Vec<String>>()
+            .join("/");
+
+        Self { title, path, count }
+    }
+}
+
+#[derive(Debug)]
+pub enum FeedKind {
+    Navigation(Vec<NavigationEntry>),
+    Acquisition(UserBooks),
+}
+
+/// A feed of the OPDS catalog of a user, rendered either as OPDS 1.2 (Atom)
+/// or OPDS 2.0 (JSON)
+#[derive(Debug)]
+pub struct Feed {
+    pub title: String,
+    /// Path of the feed relative to the root of the catalog
+    pub path: String,
+    pub kind: FeedKind,
+}
+
+impl Feed {
+    pub fn content_type_atom(&self) -> &'static str {
+        match self.kind {
+            FeedKind::Navigation(_) => OPDS_ATOM_NAVIGATION,
+            FeedKind::Acquisition(_) => OPDS_ATOM_ACQUISITION,
+        }
+    }
+
+    /// Renders the feed as OPDS 1.2, `base` is the url of the root of the catalog
+    pub fn to_atom(&self, base: &str) -> String {
+        let updated = Utc::now().to_rfc3339();
+        let self_href = href(base, &self.path);
+
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        xml.push_str(
+            "<feed xmlns=\"http://www.w3.org/2005/Atom\" \
+             xmlns:dc=\"http://purl.org/dc/terms/\" \
+             xmlns:opds=\"http://opds-spec.org/2010/catalog\" \
+             xmlns:thr=\"http://purl.org/syndication/thread/1.0\">\n",
+        );
+        xml.push_str(&format!("  <id>urn:mirabel:{}</id>\n", escape(&self_href)));
+        xml.push_str(&format!("  <title>{}</title>\n", escape(&self.title)));
+        xml.push_str(&format!("  <updated>{}</updated>\n", updated));
+        xml.push_str(&atom_link("self", &self_href, self.content_type_atom()));
+        xml.push_str(&atom_link("start", &href(base, ""), OPDS_ATOM_NAVIGATION));
+
+        match &self.kind {
+            FeedKind::Navigation(entries) => {
+                for entry in entries {
+                    let entry_href = href(base, &entry.path);
+                    xml.push_str("  <entry>\n");
+                    xml.push_str(&format!(
+                        "    <id>urn:mirabel:{}</id>\n",
+                        escape(&entry_href)
+                    ));
+                    xml.push_str(&format!("    <title>{}</title>\n", escape(&entry.title)));
+                    xml.push_str(&format!("    <updated>{}</updated>\n", updated));
+                    let (type_field, count) = match entry.count {
+                        Some(count) => (OPDS_ATOM_ACQUISITION, format!(" thr:count=\"{}\"", count)),
+                        None => (OPDS_ATOM_NAVIGATION, "".to_string()),
+                    };
+                    xml.push_str(&format!(
+                        "    <link rel=\"subsection\" href=\"{}\" type=\"{}\"{}/>\n",
+                        escape(&entry_href),
+                        type_field,
+                        count
+                    ));
+                    xml.push_str("  </entry>\n");
+                }
+            }
+            FeedKind::Acquisition(user_books) => {
+                for book in user_books.get_books() {
+                    xml.push_str(&atom_book_entry(book, &updated));
+                }
+            }
+        }
+
+        xml.push_str("</feed>\n");
+        xml
+    }
+
+    /// Renders the feed as OPDS 2.0, `base` is the url of the root of the catalog
+    pub fn to_json(&self, base: &str) -> Value {
+        let mut feed = json!({
+            "metadata": { "title": self.title },
+            "links": [
+                { "rel": "self", "href": href(base, &self.path), "type": OPDS_JSON },
+                { "rel": "start", "href": href(base, ""), "type": OPDS_JSON },
+            ],
+        });
+
+        match &self.kind {
+            FeedKind::Navigation(entries) => {
+                feed["navigation"] = entries
+                    .iter()
+                    .map(|entry| {
+                        let mut link = json!({
+                            "title": entry.title,
+                            "href": href(base, &entry.path),
+                            "type": OPDS_JSON,
+                            "rel": "subsection",
+                        });
+                        if let Some(count) = entry.count {
+                            link["properties"] = json!({ "numberOfItems": count });
+                        }
+                        link
+                    })
+                    .collect();
+            }
+            FeedKind::Acquisition(user_books) => {
+                feed["metadata"]["numberOfItems"] = json!(user_books.get_books().len());
+                feed["publications"] = user_books
+                    .get_books()
+                    .iter()
+                    .map(json_publication)
+                    .collect();
+            }
+        }
+
+        feed
+    }
+}
+// endregion - Feed
+
+fn href(base: &str, path: &str) -> String {
+    if path.is_empty() {
+        base.to_string()
+    } else {
+        format!("{}/{}", base, path)
+    }
+}
+
+fn atom_link(rel: &str, href: &str, type_field: &str) -> String {
+    format!(
+        "  <link rel=\"{}\" href=\"{}\" type=\"{}\"/>\n",
+        rel,
+        escape(href),
+        type_field
+    )
+}
+
+/// Links of the book as `(rel, href, type)`. The pages of the external API to
+/// buy or preview it as acquisition links, the book in the shelf of the user as
+/// an alternate link when there are none: it is not a file readers can download
+fn publication_links(book: &BookFull) -> Vec<(&'static str, String, &'static str)> {
+    let mut links = vec![];
+    if !book.buy_link.is_empty() {
+        links.push((REL_ACQUISITION_BUY, book.buy_link.clone(), "text/html"));
+    }
+    if !book.preview_link.is_empty() {
+        links.push((
+            REL_ACQUISITION_SAMPLE,
+            book.preview_link.clone(),
+            "text/html",
+        ));
+    }
+    if links.is_empty() {
+        links.push((
+            REL_ALTERNATE,
+            format!("{}/{}/{}", BOOKS_PATH, book.user_id, book.id),
+            "application/json",
+        ));
+    }
+    links
+}
+
+fn atom_book_entry(book: &BookFull, updated: &str) -> String {
+    let mut xml = String::from("  <entry>\n");
+    xml.push_str(&format!(
+        "    <id>urn:mirabel:book:{}</id>\n",
+        escape(&book.id)
+    ));
+    xml.push_str(&format!("    <title>{}</title>\n", escape(&book.title)));
+    xml.push_str(&format!("    <updated>{}</updated>\n", updated));
+    for author in &book.authors {
+        xml.push_str(&format!(
+            "    <author><name>{}</name></author>\n",
+            escape(author)
+        ));
+    }
+    if !book.isbn13.is_empty() {
+        xml.push_str(&format!(
+            "    <dc:identifier>urn:isbn:{}</dc:identifier>\n",
+            escape(&book.isbn13)
+        ));
+    }
+    if !book.publisher.is_empty() {
+        xml.push_str(&format!(
+            "    <dc:publisher>{}</dc:publisher>\n",
+            escape(&book.publisher)
+        ));
+    }
+    if !book.published_date.is_empty() {
+        xml.push_str(&format!(
+            "    <dc:issued>{}</dc:issued>\n",
+            escape(&book.published_date)
+        ));
+    }
+    if !book.language.is_empty() {
+        xml.push_str(&format!(
+            "    <dc:language>{}</dc:language>\n",
+            escape(&book.language)
+        ));
+    }
+    for category in book.categories.iter().chain(book.tags.iter()) {
+        xml.push_str(&format!(
+            "    <category term=\"{}\" label=\"{}\"/>\n",
+            escape(category),
+            escape(category)
+        ));
+    }
+    if !book.description.is_empty() {
+        xml.push_str(&format!(
+            "    <summary type=\"text\">{}</summary>\n",
+            escape(&book.description)
+        ));
+    }
+    if !book.cover.is_empty() {
+        xml.push_str(&format!(
+            "    <link rel=\"http://opds-spec.org/image\" href=\"{}\" type=\"image/jpeg\"/>\n",
+            escape(&book.cover)
+        ));
+        xml.push_str(&format!(
+            "    <link rel=\"http://opds-spec.org/image/thumbnail\" href=\"{}\" type=\"image/jpeg\"/>\n",
+            escape(&book.cover)
+        ));
+    }
+    for (rel, href, type_field) in publication_links(book) {
+        xml.push_str(&format!(
+            "    <link rel=\"{}\" href=\"{}\" type=\"{}\"/>\n",
+            rel,
+            escape(&href),
+            type_field
+        ));
+    }
+    xml.push_str("  </entry>\n");
+    xml
+}
+
+fn json_publication(book: &BookFull) -> Value {
+    let mut metadata = json!({
+        "@type": "http://schema.org/Book",
+        "title": book.title,
+        "author": book.authors,
+        "subject": book.categories.iter().chain(book.tags.iter()).collect::<Vec<&String>>(),
+    });
+    if !book.isbn13.is_empty() {
+        metadata["identifier"] = json!(format!("urn:isbn:{}", book.isbn13));
+    }
+    if !book.publisher.is_empty() {
+        metadata["publisher"] = json!(book.publisher);
+    }
+    if !book.published_date.is_empty() {
+        metadata["published"] = json!(book.published_date);
+    }
+    if !book.language.is_empty() {
+        metadata["language"] = json!(book.language);
+    }
+    if !book.description.is_empty() {
+        metadata["description"] = json!(book.description);
+    }
+    if book.page_count > 0 {
+        metadata["numberOfPages"] = json!(book.page_count);
+    }
+
+    let images = if book.cover.is_empty() {
+        vec![]
+    } else {
+        vec![json!({ "href": book.cover, "type": "image/jpeg" })]
+    };
+
+    let links: Vec<Value> = publication_links(book)
+        .into_iter()
+        .map(|(rel, href, type_field)| json!({ "rel": rel, "href": href, "type": type_field }))
+        .collect();
+
+    json!({
+        "metadata": metadata,
+        "links": links,
+        "images": images,
+    })
+}
@@ -0,0 +1,82 @@
+use chrono::Utc;
+use sea_orm::{sea_query::OnConflict, ActiveValue, EntityTrait};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    entities::user_preferences::{self, ActiveModel, Model},
+    error::{Error, Result},
+};
+
+use super::{ratings::RatingScale, ModelManager};
+
+// region - Preferences
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Preferences {
+    pub rating_scale: RatingScale,
+}
+
+impl From<Model> for Preferences {
+    fn from(preferences_db: Model) -> Self {
+        Self {
+            rating_scale: RatingScale::from_db(&preferences_db.rating_scale),
+        }
+    }
+}
+// endregion - Preferences
+
+// region - PreferencesToUpdate
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PreferencesToUpdate {
+    pub rating_scale: Option<RatingScale>,
+}
+// endregion - PreferencesToUpdate
+
+/// Returns the preferences of the user, the default ones if never saved
+pub async fn get_preferences(model_manager: &ModelManager, user_id: &str) -> Result<Preferences> {
+    let preferences = user_preferences::Entity::find_by_id(user_id.to_string())
+        .one(model_manager.db())
+        .await
+        .map_err(|e| Error::DbError(e.to_string()))?;
+
+    Ok(preferences.map(Preferences::from).unwrap_or_default())
+}
+
+pub async fn get_rating_scale(model_manager: &ModelManager, user_id: &str) -> Result<RatingScale> {
+    Ok(get_preferences(model_manager, user_id).await?.rating_scale)
+}
+
+/// Saves the preferences of the user, the ones not set are kept
+pub async fn save_preferences(
+    model_manager: &ModelManager,
+    user_id: &str,
+    preferences_to_update: &PreferencesToUpdate,
+) -> Result<Preferences> {
+    let mut preferences = get_preferences(model_manager, user_id).await?;
+    if let Some(rating_scale) = preferences_to_update.rating_scale {
+        preferences.rating_scale = rating_scale;
+    }
+
+    let now = Utc::now().naive_utc();
+    let model = ActiveModel {
+        user_id: ActiveValue::Set(user_id.to_string()),
+        created_at: ActiveValue::Set(now),
+        updated_at: ActiveValue::Set(now),
+        rating_scale: ActiveValue::Set(preferences.rating_scale.as_str().to_string()),
+    };
+    user_preferences::Entity::insert(model)
+        .on_conflict(
+            OnConflict::column(user_preferences::Column::UserId)
+                .update_columns([
+                    user_preferences::Column::UpdatedAt,
+                    user_preferences::Column::RatingScale,
+                ])
+                .to_owned(),
+        )
+        .exec(model_manager.db())
+        .await
+        .map_err(|e| Error::DbError(e.to_string()))?;
+
+    Ok(preferences)
+}
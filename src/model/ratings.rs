@@ -0,0 +1,156 @@
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+
+/// Ratings are stored from 0 to 5 stars, with quarter-star precision
+pub const CANONICAL_MAX_RATING: f64 = 5.0;
+const CANONICAL_STEP: f64 = 0.25;
+
+// region - RatingScale
+/// Scales in which the users read and write the ratings
+#[derive(Debug, Default, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RatingScale {
+    /// 0 to 5 stars, in quarters of star
+    #[default]
+    Stars,
+    /// 0 to 5 stars, in halves of star
+    HalfStars,
+    /// 0 to 10 points, in halves of point
+    Ten,
+    /// 0 to 100 points
+    Hundred,
+}
+
+impl RatingScale {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Stars => "stars",
+            Self::HalfStars => "half-stars",
+            Self::Ten => "ten",
+            Self::Hundred => "hundred",
+        }
+    }
+
+    /// Unknown values fall back to the default scale
+    pub fn from_db(value: &str) -> Self {
+        match value {
+            "half-stars" => Self::HalfStars,
+            "ten" => Self::Ten,
+            "hundred" => Self::Hundred,
+            _ => Self::Stars,
+        }
+    }
+
+    pub fn max(&self) -> f64 {
+        match self {
+            Self::Stars | Self::HalfStars => 5.0,
+            Self::Ten => 10.0,
+            Self::Hundred => 100.0,
+        }
+    }
+
+    pub fn step(&self) -> f64 {
+        match self {
+            Self::Stars => 0.25,
+            Self::HalfStars | Self::Ten => 0.5,
+            Self::Hundred => 1.0,
+        }
+    }
+
+    /// Converts a rating of the scale to the canonical scale
+    pub fn canonical_rating(&self, rating: f64) -> Result<f64> {
+        if !(0.0..=self.max()).contains(&rating) {
+            return Err(Error::InvalidField(format!(
+                "rating must be between 0 and {}",
+                self.max()
+            )));
+        }
+        Ok(round_to_step(
+            rating * CANONICAL_MAX_RATING / self.max(),
+            CANONICAL_STEP,
+        ))
+    }
+
+    /// Converts the optional rating of a request of the books
+    pub fn canonical_book_rating(&self, rating: Option<f32>) -> Result<Option<f32>> {
+        rating
+            .map(|r| self.canonical_rating(r as f64).map(|r| r as f32))
+            .transpose()
+    }
+
+    /// Converts a canonical rating to the scale
+    pub fn display_rating(&self, rating: f64) -> f64 {
+        round_to_step(rating * self.max() / CANONICAL_MAX_RATING, self.step())
+    }
+}
+// endregion - RatingScale
+
+/// Validates a rating of the canonical scale, rounding it to quarters of star
+pub fn normalize_rating(rating: f64) -> Result<f64> {
+    RatingScale::Stars.canonical_rating(rating)
+}
+
+fn round_to_step(value: f64, step: f64) -> f64 {
+    (value / step).round() * step
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCALES: [RatingScale; 4] = [
+        RatingScale::Stars,
+        RatingScale::HalfStars,
+        RatingScale::Ten,
+        RatingScale::Hundred,
+    ];
+
+    #[test]
+    fn canonical_rating_converts_to_stars() {
+        assert_eq!(RatingScale::Stars.canonical_rating(3.25).unwrap(), 3.25);
+        assert_eq!(RatingScale::HalfStars.canonical_rating(4.5).unwrap(), 4.5);
+        assert_eq!(RatingScale::Ten.canonical_rating(7.0).unwrap(), 3.5);
+        assert_eq!(RatingScale::Hundred.canonical_rating(87.0).unwrap(), 4.25);
+        assert_eq!(RatingScale::Stars.canonical_rating(3.1).unwrap(), 3.0);
+    }
+
+    #[test]
+    fn canonical_rating_rejects_out_of_scale() {
+        for scale in SCALES {
+            assert!(scale.canonical_rating(-0.5).is_err());
+            assert!(scale.canonical_rating(scale.max() + 1.0).is_err());
+            assert_eq!(scale.canonical_rating(0.0).unwrap(), 0.0);
+            assert_eq!(scale.canonical_rating(scale.max()).unwrap(), 5.0);
+        }
+    }
+
+    #[test]
+    fn display_rating_round_trips() {
+        for scale in SCALES {
+            let mut rating = 0.0;
+            while rating <= scale.max() {
+                let canonical = scale.canonical_rating(rating).unwrap();
+                let display = scale.display_rating(canonical);
+                // the scales finer than the canonical one lose precision
+                assert!(
+                    (display - rating).abs() <= CANONICAL_STEP * scale.max() / 5.0,
+                    "{:?}: {} became {}",
+                    scale,
+                    rating,
+                    display
+                );
+                rating += scale.step();
+            }
+        }
+        assert_eq!(RatingScale::Ten.display_rating(3.5), 7.0);
+        assert_eq!(RatingScale::HalfStars.display_rating(3.75), 4.0);
+    }
+
+    #[test]
+    fn normalize_rating_rounds_to_quarters() {
+        assert_eq!(normalize_rating(2.4).unwrap(), 2.5);
+        assert_eq!(normalize_rating(0.0).unwrap(), 0.0);
+        assert!(normalize_rating(5.5).is_err());
+    }
+}
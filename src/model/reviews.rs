@@ -0,0 +1,436 @@
+use std::collections::{HashMap, HashSet};
+
+use chrono::{NaiveDateTime, Utc};
+use pulldown_cmark::{html, Options, Parser};
+use sea_orm::{ActiveValue, ColumnTrait, EntityTrait, QueryFilter, QuerySelect};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    entities::{
+        books, library_members,
+        reviews::{self, ActiveModel, Model},
+    },
+    error::{Error, Result},
+};
+
+use super::{
+    libraries::{VISIBILITY_PRIVATE, VISIBILITY_PUBLIC},
+    ratings::{normalize_rating, RatingScale, CANONICAL_MAX_RATING},
+    ModelManager,
+};
+
+/// Friends of the author of a review are the members of the library of the book
+pub const VISIBILITY_FRIENDS: &str = "friends";
+
+/// Spoiler sections of the body start with a `:::spoiler` line and end with a `:::` line
+const SPOILER_START: &str = ":::spoiler";
+const SPOILER_END: &str = ":::";
+
+fn validate_visibility(visibility: &str) -> Result<()> {
+    match visibility {
+        VISIBILITY_PRIVATE | VISIBILITY_FRIENDS | VISIBILITY_PUBLIC => Ok(()),
+        _ => Err(Error::InvalidField(format!(
+            "invalid visibility: {}",
+            visibility
+        ))),
+    }
+}
+
+/// Ratings of the dimensions are stored on the canonical scale, like the ones of
+/// the books. The routes convert them from and to the scale of the user
+fn validate_rating(name: &str, rating: Option<f64>) -> Result<Option<f64>> {
+    match rating {
+        Some(r) if !(0.0..=CANONICAL_MAX_RATING).contains(&r) => Err(Error::InvalidField(format!(
+            "{} must be between 0 and {}",
+            name, CANONICAL_MAX_RATING
+        ))),
+        Some(r) => Ok(Some(normalize_rating(r)?)),
+        None => Ok(None),
+    }
+}
+
+/// Converts a rating of a dimension received in the scale of the user
+fn canonical_rating(
+    rating_scale: RatingScale,
+    name: &str,
+    rating: Option<f64>,
+) -> Result<Option<f64>> {
+    rating
+        .map(|r| {
+            rating_scale.canonical_rating(r).map_err(|_| {
+                Error::InvalidField(format!(
+                    "{} must be between 0 and {}",
+                    name,
+                    rating_scale.max()
+                ))
+            })
+        })
+        .transpose()
+}
+
+// region - ReviewToSave
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReviewToSave {
+    pub user_id: String,
+    /// Id of the book in the shelf
+    pub book_id: String,
+    pub title: Option<String>,
+    /// Markdown
+    pub body: String,
+    pub plot_rating: Option<f64>,
+    pub characters_rating: Option<f64>,
+    pub writing_rating: Option<f64>,
+    pub visibility: Option<String>,
+}
+
+impl ReviewToSave {
+    /// Converts the ratings from the scale preferred by the user
+    pub fn canonical_ratings(&mut self, rating_scale: RatingScale) -> Result<()> {
+        self.plot_rating = canonical_rating(rating_scale, "plotRating", self.plot_rating)?;
+        self.characters_rating =
+            canonical_rating(rating_scale, "charactersRating", self.characters_rating)?;
+        self.writing_rating = canonical_rating(rating_scale, "writingRating", self.writing_rating)?;
+        Ok(())
+    }
+
+    pub fn to_active_model(&self) -> Result<ActiveModel> {
+        if self.body.trim().is_empty() {
+            return Err(Error::MissingFields("missing fields: body".to_string()));
+        }
+        let book_id = Uuid::parse_str(&self.book_id)
+            .map_err(|_| Error::InvalidField(format!("invalid book id: {}", self.book_id)))?;
+        let visibility = self
+            .visibility
+            .clone()
+            .unwrap_or(VISIBILITY_PRIVATE.to_string());
+        validate_visibility(&visibility)?;
+
+        let now = Utc::now().naive_utc();
+        Ok(ActiveModel {
+            id: ActiveValue::Set(Uuid::new_v4()),
+            created_at: ActiveValue::Set(now),
+            updated_at: ActiveValue::Set(now),
+            book_id: ActiveValue::Set(book_id),
+            user_id: ActiveValue::Set(self.user_id.clone()),
+            title: ActiveValue::Set(self.title.clone().filter(|t| !t.trim().is_empty())),
+            body: ActiveValue::Set(self.body.clone()),
+            plot_rating: ActiveValue::Set(validate_rating("plotRating", self.plot_rating)?),
+            characters_rating: ActiveValue::Set(validate_rating(
+                "charactersRating",
+                self.characters_rating,
+            )?),
+            writing_rating: ActiveValue::Set(validate_rating(
+                "writingRating",
+                self.writing_rating,
+            )?),
+            visibility: ActiveValue::Set(visibility),
+        })
+    }
+}
+// endregion - ReviewToSave
+
+// region - ReviewToUpdate
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReviewToUpdate {
+    pub title: Option<String>,
+    pub body: Option<String>,
+    pub plot_rating: Option<f64>,
+    pub characters_rating: Option<f64>,
+    pub writing_rating: Option<f64>,
+    pub visibility: Option<String>,
+}
+
+impl ReviewToUpdate {
+    /// Converts the ratings from the scale preferred by the user
+    pub fn canonical_ratings(&mut self, rating_scale: RatingScale) -> Result<()> {
+        self.plot_rating = canonical_rating(rating_scale, "plotRating", self.plot_rating)?;
+        self.characters_rating =
+            canonical_rating(rating_scale, "charactersRating", self.characters_rating)?;
+        self.writing_rating = canonical_rating(rating_scale, "writingRating", self.writing_rating)?;
+        Ok(())
+    }
+
+    pub fn to_active_model(&self, db_review: Model) -> Result<ActiveModel> {
+        let mut review_to_update: ActiveModel = db_review.into();
+
+        review_to_update.updated_at = ActiveValue::Set(Utc::now().naive_utc());
+        // check if the optional fields are set and update the active model accordingly
+        if let Some(title) = self.title.clone() {
+            review_to_update.title = ActiveValue::Set(Some(title).filter(|t| !t.trim().is_empty()));
+        };
+        if let Some(body) = self.body.clone() {
+            if body.trim().is_empty() {
+                return Err(Error::MissingFields("missing fields: body".to_string()));
+            }
+            review_to_update.body = ActiveValue::Set(body);
+        };
+        if self.plot_rating.is_some() {
+            review_to_update.plot_rating =
+                ActiveValue::Set(validate_rating("plotRating", self.plot_rating)?);
+        };
+        if self.characters_rating.is_some() {
+            review_to_update.characters_rating =
+                ActiveValue::Set(validate_rating("charactersRating", self.characters_rating)?);
+        };
+        if self.writing_rating.is_some() {
+            review_to_update.writing_rating =
+                ActiveValue::Set(validate_rating("writingRating", self.writing_rating)?);
+        };
+        if let Some(visibility) = self.visibility.clone() {
+            validate_visibility(&visibility)?;
+            review_to_update.visibility = ActiveValue::Set(visibility);
+        };
+
+        Ok(review_to_update)
+    }
+}
+// endregion - ReviewToUpdate
+
+// region - Review
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Review {
+    pub id: String,
+    pub book_id: String,
+    pub user_id: String,
+    pub title: Option<String>,
+    /// Markdown, as written by the user
+    pub body: String,
+    /// Sanitized HTML of the body, spoilers in collapsed `<details class="spoiler">` elements
+    pub html: String,
+    pub has_spoilers: bool,
+    pub plot_rating: Option<f64>,
+    pub characters_rating: Option<f64>,
+    pub writing_rating: Option<f64>,
+    pub visibility: String,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+impl From<Model> for Review {
+    fn from(review_db: Model) -> Self {
+        let (html, has_spoilers) = render_markdown(&review_db.body);
+        Self {
+            id: review_db.id.to_string(),
+            book_id: review_db.book_id.to_string(),
+            user_id: review_db.user_id,
+            title: review_db.title,
+            body: review_db.body,
+            html,
+            has_spoilers,
+            plot_rating: review_db.plot_rating,
+            characters_rating: review_db.characters_rating,
+            writing_rating: review_db.writing_rating,
+            visibility: review_db.visibility,
+            created_at: review_db.created_at,
+            updated_at: review_db.updated_at,
+        }
+    }
+}
+
+impl Review {
+    /// Converts the ratings from the canonical scale to the given one
+    pub fn convert_ratings(&mut self, rating_scale: RatingScale) {
+        for rating in [
+            &mut self.plot_rating,
+            &mut self.characters_rating,
+            &mut self.writing_rating,
+        ] {
+            *rating = rating.map(|r| rating_scale.display_rating(r));
+        }
+    }
+}
+// endregion - Review
+
+/// Renders the Markdown body to sanitized HTML, returning if it has spoilers
+pub fn render_markdown(body: &str) -> (String, bool) {
+    let mut html = String::new();
+    let mut has_spoilers = false;
+
+    let mut section = String::new();
+    let mut in_spoiler = false;
+    for line in body.lines() {
+        let trimmed = line.trim();
+        if !in_spoiler && trimmed == SPOILER_START {
+            html.push_str(&markdown_to_html(&section));
+            section.clear();
+            in_spoiler = true;
+        } else if in_spoiler && trimmed == SPOILER_END {
+            html.push_str(&spoiler_html(&section));
+            section.clear();
+            in_spoiler = false;
+            has_spoilers = true;
+        } else {
+            section.push_str(line);
+            section.push('\n');
+        }
+    }
+    // an unclosed spoiler section runs until the end of the body
+    if in_spoiler {
+        html.push_str(&spoiler_html(&section));
+        has_spoilers = true;
+    } else {
+        html.push_str(&markdown_to_html(&section));
+    }
+
+    let html = ammonia::Builder::default()
+        .add_tag_attributes("details", &["class"])
+        .clean(&html)
+        .to_string();
+    (html, has_spoilers)
+}
+
+fn markdown_to_html(markdown: &str) -> String {
+    let options = Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TABLES;
+    let mut html = String::new();
+    html::push_html(&mut html, Parser::new_ext(markdown, options));
+    html
+}
+
+fn spoiler_html(markdown: &str) -> String {
+    format!(
+        "<details class=\"spoiler\"><summary>Spoiler</summary>\n{}</details>\n",
+        markdown_to_html(markdown)
+    )
+}
+
+/// Returns the review with the given id if it belongs to the user
+pub async fn get_user_review(
+    model_manager: &ModelManager,
+    user_id: &str,
+    review_id: &str,
+) -> Result<Model> {
+    let review_id =
+        Uuid::parse_str(review_id).map_err(|_| Error::ParseError("Invalid id".to_string()))?;
+
+    let review = reviews::Entity::find_by_id(review_id)
+        .one(model_manager.db())
+        .await
+        .map_err(|e| Error::DbError(e.to_string()))?
+        .ok_or(Error::NotFound)?;
+
+    if review.user_id != user_id {
+        return Err(Error::Unathorized);
+    }
+    Ok(review)
+}
+
+/// Returns the reviews of the books that the user can read, by id of the book.
+/// Private reviews are only returned to their authors, friends-only ones also
+/// to the members of the library of the book
+pub async fn get_books_reviews(
+    model_manager: &ModelManager,
+    user_id: &str,
+    book_ids: Vec<Uuid>,
+) -> Result<HashMap<Uuid, Review>> {
+    if book_ids.is_empty() {
+        return Ok(HashMap::new());
+    }
+
+    let reviews = reviews::Entity::find()
+        .filter(reviews::Column::BookId.is_in(book_ids))
+        .find_also_related(books::Entity)
+        .all(model_manager.db())
+        .await
+        .map_err(|e| Error::DbError(e.to_string()))?;
+
+    let library_ids: HashSet<Uuid> = library_members::Entity::find()
+        .select_only()
+        .column(library_members::Column::LibraryId)
+        .filter(library_members::Column::UserId.eq(user_id))
+        .into_tuple::<Uuid>()
+        .all(model_manager.db())
+        .await
+        .map_err(|e| Error::DbError(e.to_string()))?
+        .into_iter()
+        .collect();
+
+    Ok(reviews
+        .into_iter()
+        .filter(|(r, book)| match r.visibility.as_str() {
+            _ if r.user_id == user_id => true,
+            VISIBILITY_PUBLIC => true,
+            VISIBILITY_FRIENDS => book
+                .as_ref()
+                .and_then(|b| b.library_id)
+                .is_some_and(|library_id| library_ids.contains(&library_id)),
+            _ => false,
+        })
+        .map(|(r, _)| (r.book_id, Review::from(r)))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn review_ratings_are_converted_from_the_scale_of_the_user() {
+        let mut review_to_update = ReviewToUpdate {
+            title: None,
+            body: None,
+            plot_rating: Some(8.0),
+            characters_rating: None,
+            writing_rating: Some(10.0),
+            visibility: None,
+        };
+        review_to_update
+            .canonical_ratings(RatingScale::Ten)
+            .unwrap();
+        assert_eq!(review_to_update.plot_rating, Some(4.0));
+        assert_eq!(review_to_update.characters_rating, None);
+        assert_eq!(review_to_update.writing_rating, Some(5.0));
+
+        review_to_update.plot_rating = Some(11.0);
+        assert!(matches!(
+            review_to_update.canonical_ratings(RatingScale::Ten),
+            Err(Error::InvalidField(_))
+        ));
+    }
+
+    #[test]
+    fn render_markdown_without_spoilers() {
+        let (html, has_spoilers) = render_markdown("A **great** book");
+        assert_eq!(html, "<p>A <strong>great</strong> book</p>\n");
+        assert!(!has_spoilers);
+    }
+
+    #[test]
+    fn render_markdown_splits_the_spoilers() {
+        let body = "Before\n\n:::spoiler\nThe *butler* did it\n:::\n\nAfter";
+        let (html, has_spoilers) = render_markdown(body);
+        assert_eq!(
+            html,
+            "<p>Before</p>\n\
+             <details class=\"spoiler\"><summary>Spoiler</summary>\n\
+             <p>The <em>butler</em> did it</p>\n</details>\n\
+             <p>After</p>\n"
+        );
+        assert!(has_spoilers);
+    }
+
+    #[test]
+    fn render_markdown_closes_unclosed_spoiler_at_the_end() {
+        let (html, has_spoilers) = render_markdown("Before\n  :::spoiler  \nEveryone dies");
+        assert_eq!(
+            html,
+            "<p>Before</p>\n\
+             <details class=\"spoiler\"><summary>Spoiler</summary>\n\
+             <p>Everyone dies</p>\n</details>\n"
+        );
+        assert!(has_spoilers);
+    }
+
+    #[test]
+    fn render_markdown_sanitizes_the_html() {
+        let (html, _) = render_markdown(
+            "<script>alert(1)</script>\n\n:::spoiler\n<img src=x onerror=alert(1)>\n:::",
+        );
+        assert!(!html.contains("script"), "{}", html);
+        assert!(!html.contains("onerror"), "{}", html);
+        assert!(html.contains("<details class=\"spoiler\">"), "{}", html);
+    }
+}
@@ -0,0 +1,285 @@
+use chrono::Utc;
+use sea_orm::{
+    sea_query::OnConflict, ActiveValue, ColumnTrait, EntityTrait, QueryFilter, QueryOrder,
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    entities::{
+        books,
+        series::{self, ActiveModel, Model},
+        series_books,
+    },
+    error::{Error, Result},
+};
+
+use super::{
+    books::{BookFull, READING_STATUS_READ},
+    libraries::{can_access_book, ROLE_VIEWER},
+    volumes::get_volume,
+    ModelManager,
+};
+
+// region - SeriesId
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SeriesId {
+    pub id: String,
+}
+// endregion - SeriesId
+
+// region - SeriesToSave
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SeriesToSave {
+    pub user_id: String,
+    pub name: String,
+    pub description: Option<String>,
+}
+
+impl SeriesToSave {
+    pub fn to_active_model(&self) -> Result<ActiveModel> {
+        if self.name.trim().is_empty() {
+            return Err(Error::MissingFields("missing fields: name".to_string()));
+        }
+
+        let now = Utc::now().naive_utc();
+        Ok(ActiveModel {
+            id: ActiveValue::Set(Uuid::new_v4()),
+            created_at: ActiveValue::Set(now),
+            updated_at: ActiveValue::Set(now),
+            user_id: ActiveValue::Set(self.user_id.clone()),
+            name: ActiveValue::Set(self.name.trim().to_string()),
+            description: ActiveValue::Set(
+                self.description.clone().filter(|d| !d.trim().is_empty()),
+            ),
+        })
+    }
+}
+// endregion - SeriesToSave
+
+// region - SeriesToUpdate
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SeriesToUpdate {
+    pub name: Option<String>,
+    pub description: Option<String>,
+}
+
+impl SeriesToUpdate {
+    pub fn to_active_model(&self, db_series: Model) -> Result<ActiveModel> {
+        let mut series_to_update: ActiveModel = db_series.into();
+
+        series_to_update.updated_at = ActiveValue::Set(Utc::now().naive_utc());
+        // check if the optional fields are set and update the active model accordingly
+        if let Some(name) = self.name.clone() {
+            if name.trim().is_empty() {
+                return Err(Error::MissingFields("missing fields: name".to_string()));
+            }
+            series_to_update.name = ActiveValue::Set(name.trim().to_string());
+        };
+        if let Some(description) = self.description.clone() {
+            series_to_update.description =
+                ActiveValue::Set(Some(description).filter(|d| !d.trim().is_empty()));
+        };
+
+        Ok(series_to_update)
+    }
+}
+// endregion - SeriesToUpdate
+
+// region - SeriesBookToSave
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SeriesBookToSave {
+    /// Id of the book in the shelf
+    pub book_id: String,
+    /// Reading order in the series, 2.5 for a novella between the second and the third book
+    pub position: f64,
+}
+
+impl SeriesBookToSave {
+    pub fn to_active_model(&self, series_id: Uuid) -> Result<series_books::ActiveModel> {
+        if !self.position.is_finite() || self.position < 0.0 {
+            return Err(Error::InvalidField(
+                "position must be a positive number".to_string(),
+            ));
+        }
+        let book_id = Uuid::parse_str(&self.book_id)
+            .map_err(|_| Error::InvalidField(format!("invalid book id: {}", self.book_id)))?;
+
+        let now = Utc::now().naive_utc();
+        Ok(series_books::ActiveModel {
+            id: ActiveValue::Set(Uuid::new_v4()),
+            created_at: ActiveValue::Set(now),
+            updated_at: ActiveValue::Set(now),
+            series_id: ActiveValue::Set(series_id),
+            book_id: ActiveValue::Set(book_id),
+            position: ActiveValue::Set(self.position),
+        })
+    }
+}
+// endregion - SeriesBookToSave
+
+// region - SeriesBook
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SeriesBook {
+    /// Id of the book in the shelf
+    pub book_id: String,
+    pub position: f64,
+    pub title: String,
+    pub authors: Vec<String>,
+    pub cover: String,
+    /// Reading status of the shelf entry, the one of its owner for the books of
+    /// a shared library saved by another member
+    pub reading_status: String,
+}
+// endregion - SeriesBook
+
+// region - Series
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Series {
+    pub id: String,
+    pub user_id: String,
+    pub name: String,
+    pub description: Option<String>,
+    /// In reading order
+    pub books: Vec<SeriesBook>,
+    /// Number of books read, according to the reading status of their entries
+    pub read: usize,
+    pub total: usize,
+    /// First book of the reading order not read yet, according to the reading
+    /// status of its entry
+    pub next_unread: Option<SeriesBook>,
+}
+
+impl Series {
+    fn from_db(series_db: Model, books: Vec<SeriesBook>) -> Self {
+        let read = books
+            .iter()
+            .filter(|b| b.reading_status == READING_STATUS_READ)
+            .count();
+        let next_unread = books
+            .iter()
+            .find(|b| b.reading_status != READING_STATUS_READ)
+            .cloned();
+
+        Self {
+            id: series_db.id.to_string(),
+            user_id: series_db.user_id,
+            name: series_db.name,
+            description: series_db.description,
+            total: books.len(),
+            read,
+            next_unread,
+            books,
+        }
+    }
+}
+// endregion - Series
+
+/// Returns the series with its books in reading order and the progress of the user.
+/// The series holds shelf entries, the books of a shared library saved by
+/// another member count with the reading status of that member
+pub async fn get_series_progress(model_manager: &ModelManager, series_db: Model) -> Result<Series> {
+    let series_books = series_books::Entity::find()
+        .filter(series_books::Column::SeriesId.eq(series_db.id))
+        .order_by_asc(series_books::Column::Position)
+        .find_also_related(books::Entity)
+        .all(model_manager.db())
+        .await
+        .map_err(|e| Error::DbError(e.to_string()))?;
+
+    let mut books = Vec::with_capacity(series_books.len());
+    for (series_book, book) in series_books {
+        let book = match book {
+            Some(book) => book,
+            None => continue,
+        };
+        // books of the libraries the user has left are not part of the progress anymore
+        if !can_access_book(model_manager, &book, &series_db.user_id, ROLE_VIEWER).await? {
+            continue;
+        }
+
+        let book_api = get_volume(model_manager, &book.book_id).await?;
+        let book = BookFull::from_db_and_api(book, book_api)?;
+        books.push(SeriesBook {
+            book_id: book.id,
+            position: series_book.position,
+            title: book.title,
+            authors: book.authors,
+            cover: book.cover,
+            reading_status: book.reading_status,
+        });
+    }
+
+    Ok(Series::from_db(series_db, books))
+}
+
+/// Checks that no other series of the user has the name
+pub async fn validate_series_name(
+    model_manager: &ModelManager,
+    user_id: &str,
+    name: &str,
+    series_id: Option<Uuid>,
+) -> Result<()> {
+    let series = series::Entity::find()
+        .filter(series::Column::UserId.eq(user_id))
+        .filter(series::Column::Name.eq(name.trim()))
+        .one(model_manager.db())
+        .await
+        .map_err(|e| Error::DbError(e.to_string()))?;
+
+    match series {
+        Some(series) if Some(series.id) != series_id => Err(Error::InvalidField(format!(
+            "a series is already named {}",
+            name.trim()
+        ))),
+        _ => Ok(()),
+    }
+}
+
+/// Adds the book to the series, or moves it to the new position if already in it
+pub async fn save_series_book(
+    model_manager: &ModelManager,
+    series_book: series_books::ActiveModel,
+) -> Result<()> {
+    series_books::Entity::insert(series_book)
+        .on_conflict(
+            OnConflict::columns([series_books::Column::SeriesId, series_books::Column::BookId])
+                .update_columns([
+                    series_books::Column::Position,
+                    series_books::Column::UpdatedAt,
+                ])
+                .to_owned(),
+        )
+        .exec(model_manager.db())
+        .await
+        .map_err(|e| Error::DbError(e.to_string()))?;
+
+    Ok(())
+}
+
+/// Returns the series with the given id if it belongs to the user
+pub async fn get_user_series(
+    model_manager: &ModelManager,
+    user_id: &str,
+    series_id: &str,
+) -> Result<Model> {
+    let series_id =
+        Uuid::parse_str(series_id).map_err(|_| Error::ParseError("Invalid id".to_string()))?;
+
+    let series = series::Entity::find_by_id(series_id)
+        .one(model_manager.db())
+        .await
+        .map_err(|e| Error::DbError(e.to_string()))?
+        .ok_or(Error::NotFound)?;
+
+    if series.user_id != user_id {
+        return Err(Error::Unathorized);
+    }
+    Ok(series)
+}
@@ -0,0 +1,304 @@
+use chrono::Utc;
+use sea_orm::{ActiveValue, ColumnTrait, Condition, EntityTrait, QueryFilter};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    entities::{
+        books,
+        smart_shelves::{self, ActiveModel, Model},
+    },
+    error::{Error, Result},
+};
+
+use super::{
+    books::{BookFull, UserBooks},
+    libraries::parse_library_id,
+    ratings::RatingScale,
+    tags::tag_filter,
+    ModelManager,
+};
+
+// region - ShelfFilters
+/// Filters of a smart shelf, a book has to match all the ones set.
+/// Lists match any of their values
+#[derive(Debug, Default, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ShelfFilters {
+    pub reading_statuses: Option<Vec<String>>,
+    pub book_types: Option<Vec<String>>,
+    /// Paths of tags, their descendants included
+    pub tags: Option<Vec<String>>,
+    pub library_id: Option<String>,
+    /// Ratings in the scale preferred by the user, stored in the canonical scale
+    pub min_rating: Option<f64>,
+    pub max_rating: Option<f64>,
+    pub min_page_count: Option<i64>,
+    pub max_page_count: Option<i64>,
+    pub languages: Option<Vec<String>>,
+    pub categories: Option<Vec<String>>,
+    pub authors: Option<Vec<String>>,
+}
+
+impl ShelfFilters {
+    fn validate(&self) -> Result<()> {
+        if let Some(library_id) = &self.library_id {
+            parse_library_id(library_id)?;
+        }
+        if let (Some(min), Some(max)) = (self.min_rating, self.max_rating) {
+            if min > max {
+                return Err(Error::InvalidField(
+                    "minRating is greater than maxRating".to_string(),
+                ));
+            }
+        }
+        if let (Some(min), Some(max)) = (self.min_page_count, self.max_page_count) {
+            if min > max {
+                return Err(Error::InvalidField(
+                    "minPageCount is greater than maxPageCount".to_string(),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Converts the ratings of the request to the canonical scale, the one
+    /// used to store the filters
+    fn to_canonical(&self, rating_scale: RatingScale) -> Result<Self> {
+        let mut filters = self.clone();
+        filters.min_rating = self
+            .min_rating
+            .map(|r| rating_scale.canonical_rating(r))
+            .transpose()?;
+        filters.max_rating = self
+            .max_rating
+            .map(|r| rating_scale.canonical_rating(r))
+            .transpose()?;
+        Ok(filters)
+    }
+
+    /// Condition on the columns of the books table
+    fn db_condition(&self) -> Result<Condition> {
+        let mut condition = Condition::all();
+        if let Some(reading_statuses) = non_empty(&self.reading_statuses) {
+            condition = condition.add(books::Column::ReadingStatus.is_in(reading_statuses));
+        }
+        if let Some(book_types) = non_empty(&self.book_types) {
+            condition = condition.add(books::Column::BookType.is_in(book_types));
+        }
+        if let Some(tags) = non_empty(&self.tags) {
+            condition = condition.add(
+                tags.iter()
+                    .fold(Condition::any(), |any, tag| any.add(tag_filter(tag))),
+            );
+        }
+        if let Some(library_id) = &self.library_id {
+            condition = condition.add(books::Column::LibraryId.eq(parse_library_id(library_id)?));
+        }
+        if let Some(min_rating) = self.min_rating {
+            condition = condition.add(books::Column::Rating.gte(min_rating));
+        }
+        if let Some(max_rating) = self.max_rating {
+            condition = condition.add(books::Column::Rating.lte(max_rating));
+        }
+        Ok(condition)
+    }
+
+    /// Checks the cached metadata of the book
+    fn matches_metadata(&self, book: &BookFull) -> bool {
+        if self.min_page_count.is_some_and(|min| book.page_count < min) {
+            return false;
+        }
+        if self.max_page_count.is_some_and(|max| book.page_count > max) {
+            return false;
+        }
+        if let Some(languages) = non_empty(&self.languages) {
+            if !languages
+                .iter()
+                .any(|l| l.eq_ignore_ascii_case(&book.language))
+            {
+                return false;
+            }
+        }
+        if let Some(categories) = non_empty(&self.categories) {
+            if !any_match(categories, &book.categories) {
+                return false;
+            }
+        }
+        if let Some(authors) = non_empty(&self.authors) {
+            if !any_match(authors, &book.authors) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+fn non_empty(values: &Option<Vec<String>>) -> Option<&Vec<String>> {
+    values.as_ref().filter(|v| !v.is_empty())
+}
+
+/// Case insensitive, "Fiction" matches "Fiction / Fantasy / Epic"
+fn any_match(filters: &[String], values: &[String]) -> bool {
+    filters.iter().any(|filter| {
+        let filter = filter.to_lowercase();
+        values.iter().any(|v| v.to_lowercase().contains(&filter))
+    })
+}
+// endregion - ShelfFilters
+
+// region - SmartShelfId
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SmartShelfId {
+    pub id: String,
+}
+// endregion - SmartShelfId
+
+// region - SmartShelfToSave
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SmartShelfToSave {
+    pub user_id: String,
+    pub name: String,
+    pub filters: ShelfFilters,
+}
+
+impl SmartShelfToSave {
+    pub fn to_active_model(&self, rating_scale: RatingScale) -> Result<ActiveModel> {
+        if self.name.trim().is_empty() {
+            return Err(Error::MissingFields("missing fields: name".to_string()));
+        }
+        let filters = self.filters.to_canonical(rating_scale)?;
+        filters.validate()?;
+
+        let now = Utc::now().naive_utc();
+        Ok(ActiveModel {
+            id: ActiveValue::Set(Uuid::new_v4()),
+            created_at: ActiveValue::Set(now),
+            updated_at: ActiveValue::Set(now),
+            user_id: ActiveValue::Set(self.user_id.clone()),
+            name: ActiveValue::Set(self.name.trim().to_string()),
+            filters: ActiveValue::Set(filters_to_json(&filters)?),
+        })
+    }
+}
+// endregion - SmartShelfToSave
+
+// region - SmartShelfToUpdate
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SmartShelfToUpdate {
+    pub name: Option<String>,
+    /// Replaces all the filters of the shelf
+    pub filters: Option<ShelfFilters>,
+}
+
+impl SmartShelfToUpdate {
+    pub fn to_active_model(
+        &self,
+        db_shelf: Model,
+        rating_scale: RatingScale,
+    ) -> Result<ActiveModel> {
+        let mut shelf_to_update: ActiveModel = db_shelf.into();
+
+        shelf_to_update.updated_at = ActiveValue::Set(Utc::now().naive_utc());
+        // check if the optional fields are set and update the active model accordingly
+        if let Some(name) = self.name.clone() {
+            if name.trim().is_empty() {
+                return Err(Error::MissingFields("missing fields: name".to_string()));
+            }
+            shelf_to_update.name = ActiveValue::Set(name.trim().to_string());
+        };
+        if let Some(filters) = &self.filters {
+            let filters = filters.to_canonical(rating_scale)?;
+            filters.validate()?;
+            shelf_to_update.filters = ActiveValue::Set(filters_to_json(&filters)?);
+        };
+
+        Ok(shelf_to_update)
+    }
+}
+// endregion - SmartShelfToUpdate
+
+// region - SmartShelf
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SmartShelf {
+    pub id: String,
+    pub user_id: String,
+    pub name: String,
+    pub filters: ShelfFilters,
+}
+
+impl From<Model> for SmartShelf {
+    fn from(shelf_db: Model) -> Self {
+        Self {
+            id: shelf_db.id.to_string(),
+            user_id: shelf_db.user_id,
+            name: shelf_db.name,
+            // filters are validated when saved
+            filters: serde_json::from_value(shelf_db.filters).unwrap_or_default(),
+        }
+    }
+}
+
+impl SmartShelf {
+    /// Converts the ratings of the filters from the canonical scale to the given one
+    pub fn convert_ratings(&mut self, rating_scale: RatingScale) {
+        self.filters.min_rating = self
+            .filters
+            .min_rating
+            .map(|r| rating_scale.display_rating(r));
+        self.filters.max_rating = self
+            .filters
+            .max_rating
+            .map(|r| rating_scale.display_rating(r));
+    }
+}
+// endregion - SmartShelf
+
+fn filters_to_json(filters: &ShelfFilters) -> Result<serde_json::Value> {
+    serde_json::to_value(filters).map_err(|e| Error::ParseError(e.to_string()))
+}
+
+/// Returns the books of the user matching the filters of the shelf
+pub async fn get_shelf_books(
+    model_manager: &ModelManager,
+    shelf: &SmartShelf,
+) -> Result<UserBooks> {
+    // the ratings of the filters and of the books are both canonical
+    let books = books::Entity::find()
+        .filter(books::Column::UserId.eq(shelf.user_id.clone()))
+        .filter(shelf.filters.db_condition()?)
+        .all(model_manager.db())
+        .await
+        .map_err(|e| Error::DbError(e.to_string()))?;
+
+    // the metadata filters need the full book info from the volumes cache
+    let mut user_books = UserBooks::from_db(model_manager, shelf.user_id.clone(), books).await?;
+    user_books.retain_books(|book| shelf.filters.matches_metadata(book));
+
+    Ok(user_books)
+}
+
+/// Returns the shelf with the given id if it belongs to the user
+pub async fn get_user_shelf(
+    model_manager: &ModelManager,
+    user_id: &str,
+    shelf_id: &str,
+) -> Result<Model> {
+    let shelf_id =
+        Uuid::parse_str(shelf_id).map_err(|_| Error::ParseError("Invalid id".to_string()))?;
+
+    let shelf = smart_shelves::Entity::find_by_id(shelf_id)
+        .one(model_manager.db())
+        .await
+        .map_err(|e| Error::DbError(e.to_string()))?
+        .ok_or(Error::NotFound)?;
+
+    if shelf.user_id != user_id {
+        return Err(Error::Unathorized);
+    }
+    Ok(shelf)
+}
@@ -0,0 +1,489 @@
+use std::collections::HashMap;
+
+use chrono::Utc;
+use sea_orm::{
+    sea_query::{Expr, SimpleExpr},
+    ActiveModelTrait, ActiveValue, ColumnTrait, ConnectionTrait, DbBackend, EntityTrait,
+    FromQueryResult, QueryFilter, Statement, TransactionTrait, Value,
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    entities::tags::{self, ActiveModel, Model},
+    error::{Error, Result},
+};
+
+use super::ModelManager;
+
+// region - TagCount
+#[derive(Debug, Deserialize, Serialize, FromQueryResult)]
+#[serde(rename_all = "camelCase")]
+pub struct TagCount {
+    pub tag: String,
+    pub count: i64,
+}
+// endregion - TagCount
+
+// region - TagToRename
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TagToRename {
+    pub name: String,
+}
+// endregion - TagToRename
+
+// region - TagsToMerge
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TagsToMerge {
+    pub tags: Vec<String>,
+    pub into: String,
+}
+// endregion - TagsToMerge
+
+// region - TagsUpdated
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TagsUpdated {
+    /// Number of books whose tags changed
+    pub books: u64,
+}
+// endregion - TagsUpdated
+
+// region - TagToSave
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TagToSave {
+    pub user_id: String,
+    pub name: String,
+    pub parent_id: Option<String>,
+    pub color: Option<String>,
+    pub icon: Option<String>,
+}
+
+impl TagToSave {
+    /// The parent has to be checked against the tags of the user
+    pub fn to_active_model(&self) -> Result<ActiveModel> {
+        let now = Utc::now().naive_utc();
+        Ok(ActiveModel {
+            id: ActiveValue::Set(Uuid::new_v4()),
+            created_at: ActiveValue::Set(now),
+            updated_at: ActiveValue::Set(now),
+            user_id: ActiveValue::Set(self.user_id.clone()),
+            parent_id: ActiveValue::Set(parse_parent_id(&self.parent_id)?),
+            name: ActiveValue::Set(validate_name(&self.name)?),
+            color: ActiveValue::Set(validate_color(&self.color)?),
+            icon: ActiveValue::Set(self.icon.clone().filter(|i| !i.is_empty())),
+        })
+    }
+}
+// endregion - TagToSave
+
+// region - TagToUpdate
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TagToUpdate {
+    pub name: Option<String>,
+    /// An empty string moves the tag to the root
+    pub parent_id: Option<String>,
+    pub color: Option<String>,
+    pub icon: Option<String>,
+}
+
+impl TagToUpdate {
+    pub fn to_active_model(&self, db_tag: Model) -> Result<ActiveModel> {
+        let mut tag_to_update: ActiveModel = db_tag.into();
+
+        tag_to_update.updated_at = ActiveValue::Set(Utc::now().naive_utc());
+        // check if the optional fields are set and update the active model accordingly
+        if let Some(name) = self.name.clone() {
+            tag_to_update.name = ActiveValue::Set(validate_name(&name)?);
+        };
+        if self.parent_id.is_some() {
+            tag_to_update.parent_id = ActiveValue::Set(parse_parent_id(&self.parent_id)?);
+        };
+        if self.color.is_some() {
+            tag_to_update.color = ActiveValue::Set(validate_color(&self.color)?);
+        };
+        if let Some(icon) = self.icon.clone() {
+            tag_to_update.icon = ActiveValue::Set(Some(icon).filter(|i| !i.is_empty()));
+        };
+
+        Ok(tag_to_update)
+    }
+}
+// endregion - TagToUpdate
+
+// region - Tag
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Tag {
+    pub id: String,
+    pub name: String,
+    /// Full path of the tag, as used in the tags of the books
+    pub path: String,
+    pub color: Option<String>,
+    pub icon: Option<String>,
+    pub children: Vec<Tag>,
+}
+
+impl Tag {
+    /// Builds the trees of the tags, sorted by name
+    pub fn tree(tags: &[Model]) -> Vec<Self> {
+        let paths = tag_paths(tags);
+        Self::children(tags, &paths, None)
+    }
+
+    /// Builds the tag with its descendants, `tags` being all the tags of the user
+    pub fn subtree(tags: &[Model], tag: &Model) -> Self {
+        let paths = tag_paths(tags);
+        Self::from_model(tag, tags, &paths)
+    }
+
+    fn from_model(tag: &Model, tags: &[Model], paths: &HashMap<Uuid, String>) -> Self {
+        Self {
+            id: tag.id.to_string(),
+            name: tag.name.clone(),
+            path: paths.get(&tag.id).cloned().unwrap_or_default(),
+            color: tag.color.clone(),
+            icon: tag.icon.clone(),
+            children: Self::children(tags, paths, Some(tag.id)),
+        }
+    }
+
+    fn children(
+        tags: &[Model],
+        paths: &HashMap<Uuid, String>,
+        parent_id: Option<Uuid>,
+    ) -> Vec<Self> {
+        let mut children: Vec<Self> = tags
+            .iter()
+            .filter(|t| t.parent_id == parent_id)
+            .map(|t| Self::from_model(t, tags, paths))
+            .collect();
+        children.sort_by(|a, b| a.name.cmp(&b.name));
+        children
+    }
+}
+// endregion - Tag
+
+/// Separator of the levels of the hierarchy in the tags of the books
+pub const TAG_SEPARATOR: char = '/';
+
+fn validate_name(name: &str) -> Result<String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(Error::MissingFields("missing fields: name".to_string()));
+    }
+    if name.contains(TAG_SEPARATOR) {
+        return Err(Error::InvalidField(format!(
+            "tag names cannot contain '{}': {}",
+            TAG_SEPARATOR, name
+        )));
+    }
+    Ok(name.to_string())
+}
+
+/// Colors are hex codes like `#4a90e2` or `#fff`, an empty string removes the color
+fn validate_color(color: &Option<String>) -> Result<Option<String>> {
+    let color = match color.as_deref() {
+        None | Some("") => return Ok(None),
+        Some(color) => color,
+    };
+
+    let hex = color.strip_prefix('#').unwrap_or_default();
+    if !matches!(hex.len(), 3 | 6) || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(Error::InvalidField(format!("invalid color: {}", color)));
+    }
+    Ok(Some(color.to_lowercase()))
+}
+
+fn parse_parent_id(parent_id: &Option<String>) -> Result<Option<Uuid>> {
+    match parent_id.as_deref() {
+        None | Some("") => Ok(None),
+        Some(parent_id) => Uuid::parse_str(parent_id)
+            .map(Some)
+            .map_err(|_| Error::InvalidField(format!("invalid parent id: {}", parent_id))),
+    }
+}
+
+/// Full paths of the tags, like `fiction/fantasy/epic`
+pub fn tag_paths(tags: &[Model]) -> HashMap<Uuid, String> {
+    let by_id: HashMap<Uuid, &Model> = tags.iter().map(|t| (t.id, t)).collect();
+
+    tags.iter()
+        .map(|tag| {
+            let mut names = vec![tag.name.as_str()];
+            let mut parent_id = tag.parent_id;
+            // the depth is bounded in case of a cycle
+            while let Some(parent) = parent_id.and_then(|id| by_id.get(&id)) {
+                if names.len() > tags.len() {
+                    break;
+                }
+                names.push(&parent.name);
+                parent_id = parent.parent_id;
+            }
+            names.reverse();
+            (tag.id, names.join(&TAG_SEPARATOR.to_string()))
+        })
+        .collect()
+}
+
+/// Filter of the books with the tag or one of its descendants
+pub fn tag_filter(tag: &str) -> SimpleExpr {
+    Expr::cust_with_values(
+        "EXISTS (SELECT 1 FROM unnest(tags) AS t WHERE t = $1 OR starts_with(t, $2))",
+        [tag.to_string(), format!("{}{}", tag, TAG_SEPARATOR)],
+    )
+}
+
+pub async fn get_user_tag_models(
+    model_manager: &ModelManager,
+    user_id: &str,
+) -> Result<Vec<Model>> {
+    tags::Entity::find()
+        .filter(tags::Column::UserId.eq(user_id))
+        .all(model_manager.db())
+        .await
+        .map_err(|e| Error::DbError(e.to_string()))
+}
+
+/// Checks that the parent is a tag of the user and not the tag itself or one of its descendants
+fn validate_parent(tags: &[Model], tag_id: Uuid, parent_id: Option<Uuid>) -> Result<()> {
+    let mut parent_id = match parent_id {
+        None => return Ok(()),
+        Some(parent_id) => Some(parent_id),
+    };
+
+    while let Some(id) = parent_id {
+        if id == tag_id {
+            return Err(Error::InvalidField(
+                "a tag cannot be moved under itself".to_string(),
+            ));
+        }
+        parent_id = match tags.iter().find(|t| t.id == id) {
+            Some(parent) => parent.parent_id,
+            None => return Err(Error::InvalidField(format!("tag not found: {}", id))),
+        };
+    }
+    Ok(())
+}
+
+/// Checks that no other tag with the same parent has the name
+fn validate_sibling_name(
+    tags: &[Model],
+    tag_id: Uuid,
+    parent_id: Option<Uuid>,
+    name: &str,
+) -> Result<()> {
+    if tags
+        .iter()
+        .any(|t| t.id != tag_id && t.parent_id == parent_id && t.name == name)
+    {
+        return Err(Error::InvalidField(format!(
+            "a tag with the same parent is already named {}",
+            name
+        )));
+    }
+    Ok(())
+}
+
+pub async fn save_tag(model_manager: &ModelManager, tag_to_save: &TagToSave) -> Result<Tag> {
+    let tag = tag_to_save.to_active_model()?;
+    let mut tags = get_user_tag_models(model_manager, &tag_to_save.user_id).await?;
+    validate_parent(&tags, *tag.id.as_ref(), *tag.parent_id.as_ref())?;
+    validate_sibling_name(
+        &tags,
+        *tag.id.as_ref(),
+        *tag.parent_id.as_ref(),
+        tag.name.as_ref(),
+    )?;
+
+    let tag = tag
+        .insert(model_manager.db())
+        .await
+        .map_err(|e| Error::DbError(e.to_string()))?;
+
+    tags.push(tag.clone());
+    Ok(Tag::subtree(&tags, &tag))
+}
+
+/// Updates the tag, renaming or moving it renames it in the books of the user
+/// together with its descendants
+pub async fn update_tag(
+    model_manager: &ModelManager,
+    user_id: &str,
+    id: &str,
+    tag_to_update: &TagToUpdate,
+) -> Result<Tag> {
+    let tags = get_user_tag_models(model_manager, user_id).await?;
+    let tag = find_tag(&tags, id)?;
+    let old_path = tag_paths(&tags).remove(&tag.id).unwrap_or_default();
+
+    let tag = tag_to_update.to_active_model(tag)?;
+    validate_parent(&tags, *tag.id.as_ref(), *tag.parent_id.as_ref())?;
+    validate_sibling_name(
+        &tags,
+        *tag.id.as_ref(),
+        *tag.parent_id.as_ref(),
+        tag.name.as_ref(),
+    )?;
+
+    let txn = model_manager
+        .db()
+        .begin()
+        .await
+        .map_err(|e| Error::DbError(e.to_string()))?;
+
+    let tag = tag
+        .update(&txn)
+        .await
+        .map_err(|e| Error::DbError(e.to_string()))?;
+
+    let tags: Vec<Model> = tags
+        .into_iter()
+        .map(|t| if t.id == tag.id { tag.clone() } else { t })
+        .collect();
+    let new_path = tag_paths(&tags).remove(&tag.id).unwrap_or_default();
+
+    if new_path != old_path {
+        txn.execute(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            r#"UPDATE books SET
+                tags = ARRAY(
+                    SELECT tag FROM (
+                        SELECT CASE
+                            WHEN t = $2 THEN $3
+                            WHEN starts_with(t, $2 || '/') THEN $3 || substr(t, length($2) + 1)
+                            ELSE t
+                        END AS tag, MIN(i) AS i
+                        FROM unnest(tags) WITH ORDINALITY AS u(t, i)
+                        GROUP BY 1
+                    ) moved
+                    ORDER BY i
+                ),
+                updated_at = NOW()
+            WHERE user_id = $1
+                AND EXISTS (SELECT 1 FROM unnest(tags) AS t WHERE t = $2 OR starts_with(t, $2 || '/'))"#,
+            [user_id.into(), old_path.into(), new_path.into()],
+        ))
+        .await
+        .map_err(|e| Error::DbError(e.to_string()))?;
+    }
+
+    txn.commit()
+        .await
+        .map_err(|e| Error::DbError(e.to_string()))?;
+
+    Ok(Tag::subtree(&tags, &tag))
+}
+
+/// Finds the tag with the given id between the tags of the user
+pub fn find_tag(tags: &[Model], id: &str) -> Result<Model> {
+    let id = Uuid::parse_str(id).map_err(|_| Error::ParseError("Invalid id".to_string()))?;
+    tags.iter()
+        .find(|t| t.id == id)
+        .cloned()
+        .ok_or(Error::NotFound)
+}
+
+fn clean_tag(tag: &str) -> Result<String> {
+    let tag = tag.trim();
+    if tag.is_empty() {
+        return Err(Error::MissingFields("missing fields: tag".to_string()));
+    }
+    Ok(tag.to_string())
+}
+
+/// Returns the tags of the user's books, with the number of books of each tag
+pub async fn get_user_tags(model_manager: &ModelManager, user_id: &str) -> Result<Vec<TagCount>> {
+    TagCount::find_by_statement(Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        r#"SELECT tag, COUNT(*) AS count
+        FROM books, unnest(tags) AS tag
+        WHERE user_id = $1
+        GROUP BY tag
+        ORDER BY tag"#,
+        [user_id.into()],
+    ))
+    .all(model_manager.db())
+    .await
+    .map_err(|e| Error::DbError(e.to_string()))
+}
+
+/// Replaces the tags with a single one in every book of the user.
+/// The order of the tags of the books is kept, without duplicates
+pub async fn merge_tags(
+    model_manager: &ModelManager,
+    user_id: &str,
+    tags: &[String],
+    into: &str,
+) -> Result<TagsUpdated> {
+    let tags = tags
+        .iter()
+        .map(|tag| clean_tag(tag))
+        .collect::<Result<Vec<String>>>()?;
+    if tags.is_empty() {
+        return Err(Error::MissingFields("missing fields: tags".to_string()));
+    }
+    let into = clean_tag(into)?;
+
+    let statement = Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        r#"UPDATE books SET
+            tags = ARRAY(
+                SELECT tag FROM (
+                    SELECT CASE WHEN t = ANY($2) THEN $3 ELSE t END AS tag, MIN(i) AS i
+                    FROM unnest(tags) WITH ORDINALITY AS u(t, i)
+                    GROUP BY 1
+                ) merged
+                ORDER BY i
+            ),
+            updated_at = NOW()
+        WHERE user_id = $1 AND tags && $2"#,
+        [user_id.into(), Value::from(tags), into.into()],
+    );
+
+    execute_in_transaction(model_manager, statement).await
+}
+
+/// Removes the tag from every book of the user
+pub async fn delete_tag(
+    model_manager: &ModelManager,
+    user_id: &str,
+    tag: &str,
+) -> Result<TagsUpdated> {
+    let statement = Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        r#"UPDATE books SET tags = array_remove(tags, $2), updated_at = NOW()
+        WHERE user_id = $1 AND $2 = ANY(tags)"#,
+        [user_id.into(), tag.into()],
+    );
+
+    execute_in_transaction(model_manager, statement).await
+}
+
+async fn execute_in_transaction(
+    model_manager: &ModelManager,
+    statement: Statement,
+) -> Result<TagsUpdated> {
+    let txn = model_manager
+        .db()
+        .begin()
+        .await
+        .map_err(|e| Error::DbError(e.to_string()))?;
+
+    let res = txn
+        .execute(statement)
+        .await
+        .map_err(|e| Error::DbError(e.to_string()))?;
+
+    txn.commit()
+        .await
+        .map_err(|e| Error::DbError(e.to_string()))?;
+
+    Ok(TagsUpdated {
+        books: res.rows_affected(),
+    })
+}
@@ -0,0 +1,186 @@
+use chrono::{Duration, Utc};
+use sea_orm::{
+    sea_query::OnConflict, ActiveValue, ColumnTrait, ConnectionTrait, DbBackend, EntityTrait,
+    QueryFilter, QueryOrder, Statement, Value,
+};
+
+use crate::{
+    entities::volumes,
+    error::{Error, Result},
+};
+
+use super::{
+    authors::link_volume_authors,
+    books_api::{fetch_volume, BooksApiResponse},
+    works::link_volume_work,
+    ModelManager,
+};
+
+/// Cached volumes older than this are fetched again from the external API
+const VOLUME_MAX_AGE_DAYS: i64 = 7;
+
+/// Prefix of the ids of the volumes created by the users, like `manual:<uuid>`
+pub const MANUAL_VOLUME_PREFIX: &str = "manual:";
+
+pub fn is_manual_volume(book_id: &str) -> bool {
+    book_id.starts_with(MANUAL_VOLUME_PREFIX)
+}
+
+/// Returns the volume from the cache, fetching it from the external API when
+/// it is missing or stale. A stale volume is still returned if the external
+/// API is not reachable. Manual volumes only live in the cache
+pub async fn get_volume(model_manager: &ModelManager, book_id: &str) -> Result<BooksApiResponse> {
+    let cached = volumes::Entity::find_by_id(book_id.to_string())
+        .one(model_manager.db())
+        .await
+        .map_err(|e| Error::DbError(e.to_string()))?;
+
+    if is_manual_volume(book_id) {
+        return cached
+            .and_then(|volume| serde_json::from_value(volume.data).ok())
+            .ok_or(Error::NotFound);
+    }
+
+    if let Some(volume) = cached.as_ref() {
+        let max_age = Utc::now().naive_utc() - Duration::days(VOLUME_MAX_AGE_DAYS);
+        if volume.updated_at > max_age {
+            if let Ok(book_api) = serde_json::from_value(volume.data.clone()) {
+                return Ok(book_api);
+            }
+        }
+    }
+
+    let book_api = match fetch_volume(book_id).await {
+        Ok(book_api) => book_api,
+        Err(e) => {
+            return cached
+                .and_then(|volume| serde_json::from_value(volume.data).ok())
+                .ok_or(e)
+        }
+    };
+
+    save_volume(model_manager, book_id, &book_api).await?;
+
+    Ok(book_api)
+}
+
+/// Inserts or refreshes the volume in the cache, linking it to its authors and its work
+pub async fn save_volume(
+    model_manager: &ModelManager,
+    book_id: &str,
+    book_api: &BooksApiResponse,
+) -> Result<()> {
+    upsert_volume(model_manager, book_id, None, book_api).await
+}
+
+/// Inserts or updates the manual volume created by the user, linking it to its
+/// authors and its work like the volumes of the external API
+pub async fn save_manual_volume(
+    model_manager: &ModelManager,
+    user_id: &str,
+    book_id: &str,
+    book_api: &BooksApiResponse,
+) -> Result<()> {
+    upsert_volume(model_manager, book_id, Some(user_id), book_api).await
+}
+
+async fn upsert_volume(
+    model_manager: &ModelManager,
+    book_id: &str,
+    user_id: Option<&str>,
+    book_api: &BooksApiResponse,
+) -> Result<()> {
+    let data = serde_json::to_value(book_api).map_err(|e| Error::ParseError(e.to_string()))?;
+    let now = Utc::now().naive_utc();
+
+    let volume = volumes::ActiveModel {
+        book_id: ActiveValue::Set(book_id.to_string()),
+        created_at: ActiveValue::Set(now),
+        updated_at: ActiveValue::Set(now),
+        data: ActiveValue::Set(data),
+        work_id: ActiveValue::NotSet,
+        user_id: ActiveValue::Set(user_id.map(str::to_string)),
+    };
+
+    // the user of a volume never changes
+    volumes::Entity::insert(volume)
+        .on_conflict(
+            OnConflict::column(volumes::Column::BookId)
+                .update_columns([volumes::Column::Data, volumes::Column::UpdatedAt])
+                .to_owned(),
+        )
+        .exec(model_manager.db())
+        .await
+        .map_err(|e| Error::DbError(e.to_string()))?;
+
+    link_volume_authors(model_manager, book_id, &book_api.get_authors()).await?;
+    link_volume_work(model_manager, book_id, book_api).await
+}
+
+/// Returns the manual volumes created by the user, the most recent first
+pub async fn get_user_manual_volumes(
+    model_manager: &ModelManager,
+    user_id: &str,
+) -> Result<Vec<volumes::Model>> {
+    volumes::Entity::find()
+        .filter(volumes::Column::UserId.eq(user_id))
+        .order_by_desc(volumes::Column::CreatedAt)
+        .all(model_manager.db())
+        .await
+        .map_err(|e| Error::DbError(e.to_string()))
+}
+
+/// Returns the manual volume with the given id if it was created by the user
+pub async fn get_user_manual_volume(
+    model_manager: &ModelManager,
+    user_id: &str,
+    book_id: &str,
+) -> Result<volumes::Model> {
+    if !is_manual_volume(book_id) {
+        return Err(Error::NotFound);
+    }
+
+    let volume = volumes::Entity::find_by_id(book_id.to_string())
+        .one(model_manager.db())
+        .await
+        .map_err(|e| Error::DbError(e.to_string()))?
+        .ok_or(Error::NotFound)?;
+
+    if volume.user_id.as_deref() != Some(user_id) {
+        return Err(Error::Unathorized);
+    }
+    Ok(volume)
+}
+
+/// Returns the id of the first manual volume of the user with one of the ISBNs
+pub async fn find_manual_volume_by_isbn(
+    model_manager: &ModelManager,
+    user_id: &str,
+    isbns: &[String],
+) -> Result<Option<String>> {
+    if isbns.is_empty() {
+        return Ok(None);
+    }
+
+    model_manager
+        .db()
+        .query_one(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            r#"SELECT book_id FROM volumes
+            WHERE user_id = $1
+                AND jsonb_typeof(data -> 'volumeInfo' -> 'industryIdentifiers') = 'array'
+                AND EXISTS (
+                    SELECT 1
+                    FROM jsonb_array_elements(data -> 'volumeInfo' -> 'industryIdentifiers') AS i
+                    WHERE i ->> 'identifier' = ANY($2)
+                )
+            ORDER BY created_at
+            LIMIT 1"#,
+            [user_id.into(), Value::from(isbns.to_vec())],
+        ))
+        .await
+        .map_err(|e| Error::DbError(e.to_string()))?
+        .map(|row| row.try_get::<String>("", "book_id"))
+        .transpose()
+        .map_err(|e| Error::DbError(e.to_string()))
+}
@@ -0,0 +1,257 @@
+use std::{collections::HashMap, time::Duration};
+
+use chrono::{NaiveDateTime, Utc};
+use sea_orm::{
+    sea_query::Expr, ActiveModelTrait, ActiveValue, ColumnTrait, EntityTrait, QueryFilter,
+    QueryOrder, QuerySelect,
+};
+use serde::{Deserialize, Serialize};
+use tracing::{error as tracing_error, info};
+use uuid::Uuid;
+
+use crate::{
+    entities::{books, price_history},
+    error::{Error, Result},
+};
+
+use super::{
+    books::{UserBooks, READING_STATUS_WISHLIST},
+    books_api::{fetch_volume, BooksApiResponse},
+    volumes::{save_volume, MANUAL_VOLUME_PREFIX},
+    ModelManager,
+};
+
+/// Hours between two checks of the prices, unless set with PRICE_CHECK_INTERVAL_HOURS
+const DEFAULT_PRICE_CHECK_INTERVAL_HOURS: u64 = 24;
+
+// region - PricePoint
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PricePoint {
+    pub checked_at: NaiveDateTime,
+    pub saleability: Option<String>,
+    pub list_price: Option<f64>,
+    pub retail_price: Option<f64>,
+    pub currency_code: Option<String>,
+    pub buy_link: Option<String>,
+}
+
+impl From<price_history::Model> for PricePoint {
+    fn from(price_db: price_history::Model) -> Self {
+        Self {
+            checked_at: price_db.checked_at,
+            saleability: price_db.saleability,
+            list_price: price_db.list_price,
+            retail_price: price_db.retail_price,
+            currency_code: price_db.currency_code,
+            buy_link: price_db.buy_link,
+        }
+    }
+}
+// endregion - PricePoint
+
+// region - Wishlist
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Wishlist {
+    pub books: UserBooks,
+    /// Last price checked for each book, by id of the volume
+    pub prices: HashMap<String, PricePoint>,
+}
+// endregion - Wishlist
+
+/// Returns the books of the wishlist of the user with their last checked price
+pub async fn get_wishlist(model_manager: &ModelManager, user_id: &str) -> Result<Wishlist> {
+    let books = books::Entity::find()
+        .filter(books::Column::UserId.eq(user_id))
+        .filter(books::Column::ReadingStatus.eq(READING_STATUS_WISHLIST))
+        .all(model_manager.db())
+        .await
+        .map_err(|e| Error::DbError(e.to_string()))?;
+
+    let book_ids: Vec<String> = books.iter().map(|b| b.book_id.clone()).collect();
+    let history = price_history::Entity::find()
+        .filter(price_history::Column::BookId.is_in(book_ids))
+        .order_by_asc(price_history::Column::CheckedAt)
+        .all(model_manager.db())
+        .await
+        .map_err(|e| Error::DbError(e.to_string()))?;
+
+    // the history is sorted by date, the last price of each volume is kept
+    let prices = history
+        .into_iter()
+        .map(|p| (p.book_id.clone(), PricePoint::from(p)))
+        .collect();
+
+    Ok(Wishlist {
+        books: UserBooks::from_db(model_manager, user_id.to_string(), books).await?,
+        prices,
+    })
+}
+
+/// Returns the prices checked for the volume, the oldest first
+pub async fn get_price_history(
+    model_manager: &ModelManager,
+    book_id: &str,
+) -> Result<Vec<PricePoint>> {
+    let history = price_history::Entity::find()
+        .filter(price_history::Column::BookId.eq(book_id))
+        .order_by_asc(price_history::Column::CheckedAt)
+        .all(model_manager.db())
+        .await
+        .map_err(|e| Error::DbError(e.to_string()))?;
+
+    Ok(history.into_iter().map(PricePoint::from).collect())
+}
+
+/// Checks the prices of the books of the wishlists every PRICE_CHECK_INTERVAL_HOURS
+pub fn spawn_price_tracker(model_manager: ModelManager) {
+    let hours = std::env::var("PRICE_CHECK_INTERVAL_HOURS")
+        .ok()
+        .and_then(|hours| hours.parse::<u64>().ok())
+        .filter(|hours| *hours > 0)
+        .unwrap_or(DEFAULT_PRICE_CHECK_INTERVAL_HOURS);
+
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(hours * 60 * 60));
+        loop {
+            interval.tick().await;
+            match check_prices(&model_manager).await {
+                Ok(checked) => info!("{:<6} - checked the prices of {} books", "JOB", checked),
+                Err(e) => tracing_error!("{:<6} - check_prices: {e:?}", "ERR"),
+            }
+        }
+    });
+}
+
+/// Fetches the sale info of every volume in a wishlist, saving the prices in the
+/// history and updating the price alerts of the books. Returns the number of
+/// volumes checked
+pub async fn check_prices(model_manager: &ModelManager) -> Result<usize> {
+    let book_ids: Vec<String> = books::Entity::find()
+        .select_only()
+        .column(books::Column::BookId)
+        .distinct()
+        .filter(books::Column::ReadingStatus.eq(READING_STATUS_WISHLIST))
+        // manual volumes are not sold in the external API
+        .filter(books::Column::BookId.not_like(format!("{}%", MANUAL_VOLUME_PREFIX)))
+        .into_tuple()
+        .all(model_manager.db())
+        .await
+        .map_err(|e| Error::DbError(e.to_string()))?;
+
+    let mut checked = 0;
+    for book_id in book_ids {
+        // a volume that cannot be fetched is checked again at the next run
+        let book_api = match fetch_volume(&book_id).await {
+            Ok(book_api) => book_api,
+            Err(e) => {
+                tracing_error!("{:<6} - check_prices {}: {e:?}", "ERR", book_id);
+                continue;
+            }
+        };
+        // an error on a book does not stop the check of the others
+        if let Err(e) = check_price(model_manager, &book_id, &book_api).await {
+            tracing_error!("{:<6} - check_prices {}: {e:?}", "ERR", book_id);
+            continue;
+        }
+        checked += 1;
+    }
+
+    Ok(checked)
+}
+
+async fn check_price(
+    model_manager: &ModelManager,
+    book_id: &str,
+    book_api: &BooksApiResponse,
+) -> Result<()> {
+    save_volume(model_manager, book_id, book_api).await?;
+    save_price(model_manager, book_id, book_api).await?;
+
+    let retail_price = book_api.get_retail_price().and_then(|p| p.amount);
+    update_price_alerts(model_manager, book_id, retail_price).await
+}
+
+async fn save_price(
+    model_manager: &ModelManager,
+    book_id: &str,
+    book_api: &BooksApiResponse,
+) -> Result<()> {
+    let sale_info = book_api.sale_info.clone().unwrap_or_default();
+    let retail_price = book_api.get_retail_price().unwrap_or_default();
+    let list_price = book_api.get_list_price().unwrap_or_default();
+
+    price_history::ActiveModel {
+        id: ActiveValue::Set(Uuid::new_v4()),
+        book_id: ActiveValue::Set(book_id.to_string()),
+        checked_at: ActiveValue::Set(Utc::now().naive_utc()),
+        country: ActiveValue::Set(sale_info.country),
+        saleability: ActiveValue::Set(sale_info.saleability),
+        list_price: ActiveValue::Set(list_price.amount),
+        retail_price: ActiveValue::Set(retail_price.amount),
+        currency_code: ActiveValue::Set(retail_price.currency_code.or(list_price.currency_code)),
+        buy_link: ActiveValue::Set(sale_info.buy_link),
+    }
+    .insert(model_manager.db())
+    .await
+    .map_err(|e| Error::DbError(e.to_string()))?;
+
+    Ok(())
+}
+
+/// Flags the books of the wishlists with a target price above the retail price,
+/// and removes the flag from the ones with the price back over the target, or
+/// from all of them when the book is not for sale anymore.
+/// Target prices are in the currency of the external API
+async fn update_price_alerts(
+    model_manager: &ModelManager,
+    book_id: &str,
+    retail_price: Option<f64>,
+) -> Result<()> {
+    let wishlist_books = || {
+        books::Entity::update_many()
+            .filter(books::Column::BookId.eq(book_id))
+            .filter(books::Column::ReadingStatus.eq(READING_STATUS_WISHLIST))
+    };
+
+    let retail_price = match retail_price {
+        Some(retail_price) => retail_price,
+        None => {
+            wishlist_books()
+                .col_expr(
+                    books::Column::PriceAlertAt,
+                    Expr::value(Option::<NaiveDateTime>::None),
+                )
+                .filter(books::Column::PriceAlertAt.is_not_null())
+                .exec(model_manager.db())
+                .await
+                .map_err(|e| Error::DbError(e.to_string()))?;
+            return Ok(());
+        }
+    };
+
+    wishlist_books()
+        .col_expr(
+            books::Column::PriceAlertAt,
+            Expr::value(Some(Utc::now().naive_utc())),
+        )
+        .filter(books::Column::TargetPrice.gt(retail_price))
+        .filter(books::Column::PriceAlertAt.is_null())
+        .exec(model_manager.db())
+        .await
+        .map_err(|e| Error::DbError(e.to_string()))?;
+
+    wishlist_books()
+        .col_expr(
+            books::Column::PriceAlertAt,
+            Expr::value(Option::<NaiveDateTime>::None),
+        )
+        .filter(books::Column::TargetPrice.lte(retail_price))
+        .filter(books::Column::PriceAlertAt.is_not_null())
+        .exec(model_manager.db())
+        .await
+        .map_err(|e| Error::DbError(e.to_string()))?;
+
+    Ok(())
+}
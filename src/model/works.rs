@@ -0,0 +1,294 @@
+use chrono::Utc;
+use sea_orm::{
+    sea_query::Expr, ActiveModelTrait, ActiveValue, ColumnTrait, ConnectionTrait, DbBackend,
+    EntityTrait, QueryFilter, Statement, Value,
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    entities::{books, volumes},
+    error::{Error, Result},
+};
+
+use super::{
+    authors::author_key,
+    books_api::BooksApiResponse,
+    volumes::{get_volume, is_manual_volume},
+    ModelManager,
+};
+
+/// Key of the work of a volume: the title without its parenthesized parts, like
+/// "(Hardcover)", and the first author, normalized. Has to match the one of the migration
+pub fn work_key(title: &str, author: &str) -> Option<String> {
+    let mut bare_title = String::new();
+    let mut closing = None;
+    for c in title.chars() {
+        match (closing, c) {
+            (None, '(') => closing = Some(')'),
+            (None, '[') => closing = Some(']'),
+            (Some(end), c) if c == end => {
+                closing = None;
+                bare_title.push(' ');
+            }
+            (Some(_), _) => {}
+            (None, c) => bare_title.push(c),
+        }
+    }
+
+    let title_key = author_key(&bare_title);
+    if title_key.is_empty() {
+        return None;
+    }
+    Some(format!("{}/{}", title_key, author_key(author)))
+}
+
+// region - EditionToSwitch
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EditionToSwitch {
+    /// Id of the volume of the new edition in the external API
+    pub book_id: String,
+}
+// endregion - EditionToSwitch
+
+// region - Edition
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Edition {
+    /// Id of the volume in the external API
+    pub book_id: String,
+    pub title: String,
+    pub authors: Vec<String>,
+    pub publisher: String,
+    pub published_date: String,
+    pub isbn_13: String,
+    pub page_count: i64,
+    pub cover: String,
+}
+
+impl Edition {
+    fn from_api(book_id: String, book_api: BooksApiResponse) -> Self {
+        Self {
+            book_id,
+            title: book_api.get_title(),
+            authors: book_api.get_authors(),
+            publisher: book_api.get_publisher(),
+            published_date: book_api.get_published_date(),
+            isbn_13: book_api.get_isbn13(),
+            page_count: book_api.get_page_count(),
+            cover: book_api.get_cover(),
+        }
+    }
+}
+// endregion - Edition
+
+/// Links the cached volume to its work. A volume sharing an ISBN with another
+/// one is an edition of the same work, the others are grouped by title and author
+pub async fn link_volume_work(
+    model_manager: &ModelManager,
+    book_id: &str,
+    book_api: &BooksApiResponse,
+) -> Result<()> {
+    let isbns: Vec<String> = [book_api.get_isbn10(), book_api.get_isbn13()]
+        .into_iter()
+        .filter(|isbn| !isbn.is_empty())
+        .collect();
+
+    let mut work_id = None;
+    if !isbns.is_empty() {
+        work_id = model_manager
+            .db()
+            .query_one(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                r#"SELECT work_id FROM volumes
+                WHERE work_id IS NOT NULL AND book_id <> $1
+                    AND jsonb_typeof(data -> 'volumeInfo' -> 'industryIdentifiers') = 'array'
+                    AND EXISTS (
+                        SELECT 1
+                        FROM jsonb_array_elements(data -> 'volumeInfo' -> 'industryIdentifiers') AS i
+                        WHERE i ->> 'identifier' = ANY($2)
+                    )
+                LIMIT 1"#,
+                [book_id.into(), Value::from(isbns)],
+            ))
+            .await
+            .map_err(|e| Error::DbError(e.to_string()))?
+            .map(|row| row.try_get::<Uuid>("", "work_id"))
+            .transpose()
+            .map_err(|e| Error::DbError(e.to_string()))?;
+    }
+
+    if work_id.is_none() {
+        let authors = book_api.get_authors();
+        let key = match work_key(
+            &book_api.get_title(),
+            authors.first().map(String::as_str).unwrap_or_default(),
+        ) {
+            Some(key) => key,
+            // a volume without title is not grouped
+            None => return Ok(()),
+        };
+
+        let work = model_manager
+            .db()
+            .query_one(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                r#"INSERT INTO works (id, title, key) VALUES ($1, $2, $3)
+                ON CONFLICT (key) DO UPDATE SET key = EXCLUDED.key
+                RETURNING id"#,
+                [
+                    Uuid::new_v4().into(),
+                    book_api.get_title().trim().into(),
+                    key.into(),
+                ],
+            ))
+            .await
+            .map_err(|e| Error::DbError(e.to_string()))?
+            .ok_or(Error::InternalServerError)?;
+        work_id = Some(
+            work.try_get::<Uuid>("", "id")
+                .map_err(|e| Error::DbError(e.to_string()))?,
+        );
+    }
+
+    volumes::Entity::update_many()
+        .col_expr(volumes::Column::WorkId, Expr::value(work_id))
+        .filter(volumes::Column::BookId.eq(book_id))
+        .exec(model_manager.db())
+        .await
+        .map_err(|e| Error::DbError(e.to_string()))?;
+
+    Ok(())
+}
+
+async fn find_volume(model_manager: &ModelManager, book_id: &str) -> Result<volumes::Model> {
+    volumes::Entity::find_by_id(book_id.to_string())
+        .one(model_manager.db())
+        .await
+        .map_err(|e| Error::DbError(e.to_string()))?
+        .ok_or(Error::NotFound)
+}
+
+/// The manual volumes are private to the user who created them, except the
+/// volume of the book itself
+fn is_visible_edition(volume: &volumes::Model, book_id: &str, user_id: &str) -> bool {
+    !is_manual_volume(&volume.book_id)
+        || volume.book_id == book_id
+        || volume.user_id.as_deref() == Some(user_id)
+}
+
+/// Returns the cached editions of the work of the volume visible to the user,
+/// the volume included
+pub async fn get_editions(
+    model_manager: &ModelManager,
+    book_id: &str,
+    user_id: &str,
+) -> Result<Vec<Edition>> {
+    let volume = find_volume(model_manager, book_id).await?;
+
+    let volumes = match volume.work_id {
+        Some(work_id) => volumes::Entity::find()
+            .filter(volumes::Column::WorkId.eq(work_id))
+            .all(model_manager.db())
+            .await
+            .map_err(|e| Error::DbError(e.to_string()))?,
+        None => vec![volume],
+    };
+
+    Ok(volumes
+        .into_iter()
+        .filter(|v| is_visible_edition(v, book_id, user_id))
+        .filter_map(|v| {
+            let book_api = serde_json::from_value(v.data).ok()?;
+            Some(Edition::from_api(v.book_id, book_api))
+        })
+        .collect())
+}
+
+/// Moves the shelf entry to another edition, keeping its dates, rating, notes
+/// and everything attached to it. The price alert is checked again for the new edition.
+/// Only the manual volumes of the user can be used
+pub async fn switch_edition(
+    model_manager: &ModelManager,
+    book: books::Model,
+    user_id: &str,
+    edition: &EditionToSwitch,
+) -> Result<books::Model> {
+    let book_id = edition.book_id.trim();
+    if book_id.is_empty() {
+        return Err(Error::MissingFields("missing fields: bookId".to_string()));
+    }
+    if book_id == book.book_id {
+        return Ok(book);
+    }
+    // caches both editions, failing if the new one does not exist
+    get_volume(model_manager, &book.book_id).await?;
+    get_volume(model_manager, book_id).await?;
+
+    // only the editions of the work of the book can replace it
+    let work_id = find_volume(model_manager, &book.book_id).await?.work_id;
+    let edition_volume = find_volume(model_manager, book_id).await?;
+    if !is_visible_edition(&edition_volume, &book.book_id, user_id) {
+        return Err(Error::Unathorized);
+    }
+    if work_id.is_none() || work_id != edition_volume.work_id {
+        return Err(Error::InvalidField(format!(
+            "{} is not an edition of the work of the book",
+            book_id
+        )));
+    }
+
+    let mut book_to_update: books::ActiveModel = book.into();
+    book_to_update.updated_at = ActiveValue::Set(Some(Utc::now().naive_utc()));
+    book_to_update.book_id = ActiveValue::Set(book_id.to_string());
+    book_to_update.price_alert_at = ActiveValue::Set(None);
+
+    book_to_update
+        .update(model_manager.db())
+        .await
+        .map_err(|e| Error::DbError(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn work_key_ignores_edition_notes() {
+        let key = work_key("Dune", "Frank Herbert");
+        assert_eq!(key.as_deref(), Some("dune/frank herbert"));
+        assert_eq!(
+            work_key("Dune (40th Anniversary Edition)", "Frank Herbert"),
+            key
+        );
+        assert_eq!(work_key("DUNE [Illustrated]", "frank herbert."), key);
+    }
+
+    #[test]
+    fn work_key_keeps_the_text_around_the_notes() {
+        assert_eq!(
+            work_key(
+                "The Hobbit (Deluxe) or There and Back Again",
+                "J.R.R. Tolkien"
+            )
+            .as_deref(),
+            Some("the hobbit or there and back again/j r r tolkien")
+        );
+    }
+
+    #[test]
+    fn work_key_differs_by_author() {
+        assert_ne!(
+            work_key("Collected Poems", "Sylvia Plath"),
+            work_key("Collected Poems", "W. H. Auden")
+        );
+        assert_eq!(work_key("Untitled", "").as_deref(), Some("untitled/"));
+    }
+
+    #[test]
+    fn work_key_needs_a_title() {
+        assert_eq!(work_key("", "Frank Herbert"), None);
+        assert_eq!(work_key("(Box Set)", "Frank Herbert"), None);
+    }
+}